@@ -1,13 +1,16 @@
-use std::collections::HashMap;
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::ops::{Deref, DerefMut};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use binrw::meta::{EndianKind, ReadEndian, WriteEndian};
 use binrw::{binread, binrw, BinRead, BinResult, BinWrite, Endian, NullString};
 use byteorder::{BigEndian, WriteBytesExt};
 use encoding_rs::UTF_8;
 use itertools::{izip, Itertools};
+use serde::{Deserialize, Serialize};
+
+use crate::asset_serde::{deserialize_texture, hex_bytes, serialize_texture};
+use crate::{CustomAsset, UnityVersion};
 
 pub const ASSET_BUNDLE_HASH: i128 = -138975531846078832632480790701341156713;
 pub const TEXT_ASSET_HASH: i128 = -73723634408196252373272760413176173752;
@@ -31,6 +34,11 @@ pub const TERRAIN_MONO_BEHAVIOR_TYPE_HASH: i128 = 161821592088346330348225465071
 pub const ANIMATION_CLIP_HASH: i128 = -80937412517696055409803870673809846754;
 pub const ANIMATOR_OVERRIDE_CONTROLLER_HASH: i128 = -102229199973352171437435367599983726207;
 pub const ANIMATOR_CONTROLLER_HASH: i128 = -115873685307230980538653717616089922820;
+pub const CUBEMAP_HASH: i128 = -83128552552940598365950339540772048186;
+pub const TEXTURE_3D_HASH: i128 = 96263084959981670933435819820976365628;
+pub const TEXTURE_2D_ARRAY_HASH: i128 = -111064173265327115496765812595839753850;
+
+pub const MONO_BEHAVIOR_CLASS_ID: u32 = 114;
 
 fn write_padding<W: Write + Seek>(writer: &mut W, align: u64) -> BinResult<()> {
     while writer.stream_position()? % align != 0 {
@@ -40,7 +48,7 @@ fn write_padding<W: Write + Seek>(writer: &mut W, align: u64) -> BinResult<()> {
 }
 
 #[binread]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[br(little, assert(ref_type_count == 0))]
 pub struct AssetFile {
     #[brw(big)]
@@ -63,7 +71,7 @@ pub struct AssetFile {
     #[br(temp)]
     script_count: u32,
     #[br(count = script_count)]
-    scripts: Vec<AssetScript>,
+    pub scripts: Vec<AssetScript>,
 
     #[br(temp)]
     external_count: u32,
@@ -73,11 +81,49 @@ pub struct AssetFile {
     ref_type_count: u32,
     user_info: NullString,
 
-    #[br(parse_with = |reader, endian, _: ()| read_assets(reader, endian, &types, &objects, header.data_offset))]
+    #[br(parse_with = |reader, endian, _: ()| read_assets(reader, endian, &types, &objects, header.data_offset, UnityVersion::from_header(&header.unity_version.to_string())))]
     pub assets: Vec<Asset>,
 }
 
 impl AssetFile {
+    pub fn new(unity_version: &str) -> Self {
+        Self {
+            header: AssetFileHeader {
+                junk: 0,
+                version: 22,
+                junk2: 0,
+                meta_data_size: 0,
+                file_size: 0,
+                data_offset: 0,
+                junk3: 0,
+                unity_version: NullString::from(unity_version),
+                platform: 38,
+                enable_type_tree: 1,
+            },
+            types: vec![],
+            path_ids: vec![],
+            object_order: vec![],
+            scripts: vec![],
+            externals: vec![],
+            user_info: NullString::default(),
+            assets: vec![],
+        }
+    }
+
+    /// The Unity version from the header, which decides the layout of versioned asset types.
+    pub fn unity_version(&self) -> UnityVersion {
+        UnityVersion::from_header(&self.header.unity_version.to_string())
+    }
+
+    /// The target platform from the header, using Unity's BuildTarget values.
+    pub fn platform(&self) -> u32 {
+        self.header.platform
+    }
+
+    pub fn set_platform(&mut self, platform: u32) {
+        self.header.platform = platform;
+    }
+
     pub fn get_asset_by_path_id(&self, path_id: i64) -> Option<&Asset> {
         let index = self
             .path_ids
@@ -106,6 +152,50 @@ impl AssetFile {
             .find_map(|(actual_index, elem)| (*elem == index).then_some(actual_index))?;
         self.assets.get_mut(actual_index)
     }
+
+    pub fn path_id_of(&self, asset_index: usize) -> Option<i64> {
+        self.object_order
+            .get(asset_index)
+            .and_then(|object_index| self.path_ids.get(*object_index))
+            .map(|path_id| *path_id as i64)
+    }
+
+    pub fn iter_with_path_ids(&self) -> impl Iterator<Item = (i64, &Asset)> {
+        self.object_order
+            .iter()
+            .zip(&self.assets)
+            .map(|(object_index, asset)| (self.path_ids[*object_index] as i64, asset))
+    }
+
+    pub fn remove_asset(&mut self, path_id: i64) -> Option<Asset> {
+        let object_index = self
+            .path_ids
+            .iter()
+            .position(|elem| *elem as i64 == path_id)?;
+        let actual_index = self
+            .object_order
+            .iter()
+            .position(|elem| *elem == object_index)?;
+        self.path_ids.remove(object_index);
+        self.object_order.remove(actual_index);
+        for elem in &mut self.object_order {
+            if *elem > object_index {
+                *elem -= 1;
+            }
+        }
+        Some(self.assets.remove(actual_index))
+    }
+
+    pub fn insert_asset(&mut self, path_id: i64, mut asset: Asset) -> Result<()> {
+        if self.path_ids.iter().any(|elem| *elem as i64 == path_id) {
+            bail!("path ID '{}' is already in use", path_id);
+        }
+        asset.set_path_id(path_id);
+        self.path_ids.push(path_id as u64);
+        self.object_order.push(self.path_ids.len() - 1);
+        self.assets.push(asset);
+        Ok(())
+    }
 }
 
 impl BinWrite for AssetFile {
@@ -150,18 +240,13 @@ impl BinWrite for AssetFile {
         }
 
         // Build the assets blob + objects table.
-        let type_hash_to_id: HashMap<i128, usize> = self
-            .types
-            .iter()
-            .enumerate()
-            .map(|(index, ty)| (ty.type_hash, index))
-            .collect();
         let mut objects = vec![AssetFileObject::default(); self.assets.len()];
+        let version = self.unity_version();
         let start = writer.stream_position()?;
         for (asset, object_index) in izip!(&self.assets, &self.object_order) {
             write_padding(writer, 8)?;
             let offset = writer.stream_position()? - start;
-            asset.write_options(writer, endian, ())?;
+            asset.write_options(writer, endian, (version,))?;
             let pre_padding_size = (writer.stream_position()? - start - offset) as u32;
             write_padding(writer, 4)?;
             let post_padding_size = (writer.stream_position()? - start - offset) as u32;
@@ -173,9 +258,9 @@ impl BinWrite for AssetFile {
                 } else {
                     post_padding_size
                 },
-                type_id: type_hash_to_id
-                    .get(&asset.type_hash())
-                    .map(|id| *id as u32)
+                type_id: self
+                    .type_index(asset)
+                    .map(|id| id as u32)
                     .ok_or_else(|| binrw::Error::AssertFail {
                         pos: writer.stream_position().unwrap_or_default(),
                         message: String::from("could not map asset back to its type ID"),
@@ -198,7 +283,6 @@ impl BinWrite for AssetFile {
         header.meta_data_size = (meta_data_size + header.unity_version.len() as u64 + 6) as u32;
         header.file_size = end_position - base_position;
         header.data_offset = data_offset;
-        header.platform = 38;
         header.enable_type_tree = 1;
         header.write_be(writer)?;
         writer.seek(SeekFrom::Start(objects_position))?;
@@ -218,11 +302,12 @@ fn read_assets<R: Read + Seek>(
     types: &[AssetFileType],
     objects: &[AssetFileObject],
     data_offset: u64,
+    version: UnityVersion,
 ) -> BinResult<Vec<Asset>> {
     let mut assets = vec![];
     let mut sorted_objects = objects.iter().collect_vec();
     sorted_objects.sort_by(|a, b| a.offset.cmp(&b.offset));
-    for obj in sorted_objects {
+    for obj in &sorted_objects {
         let ty = &types[obj.type_id as usize]; // TODO: Bounds check.
         reader.seek(SeekFrom::Start(data_offset + obj.offset))?;
         assets.push(Asset::read_options(
@@ -230,8 +315,10 @@ fn read_assets<R: Read + Seek>(
             endian,
             AssetReadOptions {
                 size: obj.size as usize,
+                class_id: ty.class_id,
                 type_hash: ty.type_hash,
                 pptr: obj.path_id,
+                version,
             },
         )?);
     }
@@ -267,7 +354,7 @@ pub struct AssetFileHeader {
 }
 
 #[binrw(little)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AssetFileType {
     pub class_id: u32,
     pub is_stripped_type: u8,
@@ -281,14 +368,26 @@ pub struct AssetFileType {
 }
 
 impl AssetFileType {
-    pub fn dump_tree(&self) -> Result<()> {
-        println!("{} {}", self.type_hash, self.script_id);
-        self.type_tree.dump()
+    pub fn new(class_id: u32, type_hash: i128) -> Self {
+        Self {
+            class_id,
+            is_stripped_type: 0,
+            script_type_index: -1,
+            script_id: 0,
+            type_hash,
+            type_tree: Default::default(),
+            junk: 0,
+        }
+    }
+
+    pub fn dump_tree<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "{} {}", self.type_hash, self.script_id)?;
+        self.type_tree.dump(writer)
     }
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct AssetFileTypeTree {
     pub node_count: u32,
     pub str_buffer_size: u32,
@@ -299,133 +398,18 @@ pub struct AssetFileTypeTree {
 }
 
 impl AssetFileTypeTree {
-    pub fn dump(&self) -> Result<()> {
-        for node in &self.nodes {
-            println!(
-                "{}{}: {}",
-                " ".repeat((node.level * 4) as usize),
-                self.get_string(node.name_str_offset)?,
-                self.get_string(node.type_str_offset)?,
-            )
-        }
+    /// Write [AssetFileTypeTree::to_tree_string] to `writer`.
+    pub fn dump<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write!(writer, "{}", self.to_tree_string()?)?;
         Ok(())
     }
 
     pub fn get_string(&self, value: u32) -> Result<String> {
         if (value & 0x80000000) != 0 {
-            Ok(match value & 0x7FFFFFFF {
-                0 => "AABB",
-                5 => "AnimationClip",
-                19 => "AnimationCurve",
-                34 => "AnimationState",
-                49 => "Array",
-                55 => "Base",
-                60 => "BitField",
-                69 => "bitset",
-                76 => "bool",
-                81 => "char",
-                86 => "ColorRGBA",
-                96 => "Component",
-                106 => "data",
-                111 => "deque",
-                117 => "double",
-                124 => "dynamic_array",
-                138 => "FastPropertyName",
-                155 => "first",
-                161 => "float",
-                167 => "Font",
-                172 => "GameObject",
-                183 => "Generic Mono",
-                196 => "GradientNEW",
-                208 => "GUID",
-                213 => "GUIStyle",
-                222 => "int",
-                226 => "list",
-                231 => "long long",
-                241 => "map",
-                245 => "Matrix4x4f",
-                256 => "MdFour",
-                263 => "MonoBehaviour",
-                277 => "MonoScript",
-                288 => "m_ByteSize",
-                299 => "m_Curve",
-                307 => "m_EditorClassIdentifier",
-                331 => "m_EditorHideFlags",
-                349 => "m_Enabled",
-                359 => "m_ExtensionPtr",
-                374 => "m_GameObject",
-                387 => "m_Index",
-                395 => "m_IsArray",
-                405 => "m_IsStatic",
-                416 => "m_MetaFlag",
-                427 => "m_Name",
-                434 => "m_ObjectHideFlags",
-                452 => "m_PrefabInternal",
-                469 => "m_PrefabParentObject",
-                490 => "m_Script",
-                499 => "m_StaticEditorFlags",
-                519 => "m_Type",
-                526 => "m_Version",
-                536 => "Object",
-                543 => "pair",
-                548 => "PPtr<Component>",
-                564 => "PPtr<GameObject>",
-                581 => "PPtr<Material>",
-                596 => "PPtr<MonoBehaviour>",
-                616 => "PPtr<MonoScript>",
-                633 => "PPtr<Object>",
-                646 => "PPtr<Prefab>",
-                659 => "PPtr<Sprite>",
-                672 => "PPtr<TextAsset>",
-                688 => "PPtr<Texture>",
-                702 => "PPtr<Texture2D>",
-                718 => "PPtr<Transform>",
-                734 => "Prefab",
-                741 => "Quaternionf",
-                753 => "Rectf",
-                759 => "RectInt",
-                767 => "RectOffset",
-                778 => "second",
-                785 => "set",
-                789 => "short",
-                795 => "size",
-                800 => "SInt16",
-                807 => "SInt32",
-                814 => "SInt64",
-                821 => "SInt8",
-                827 => "staticvector",
-                840 => "string",
-                847 => "TextAsset",
-                857 => "TextMesh",
-                866 => "Texture",
-                874 => "Texture2D",
-                884 => "Transform",
-                894 => "TypelessData",
-                907 => "UInt16",
-                914 => "UInt32",
-                921 => "UInt64",
-                928 => "UInt8",
-                934 => "unsigned int",
-                947 => "unsigned long long",
-                966 => "unsigned short",
-                981 => "vector",
-                988 => "Vector2f",
-                997 => "Vector3f",
-                1006 => "Vector4f",
-                1015 => "m_ScriptingClassIdentifier",
-                1042 => "Gradient",
-                1051 => "Type*",
-                1057 => "int2_storage",
-                1070 => "int3_storage",
-                1083 => "BoundsInt",
-                1093 => "m_CorrespondingSourceObject",
-                1121 => "m_PrefabInstance",
-                1138 => "m_PrefabAsset",
-                1152 => "FileSize",
-                1161 => "Hash128",
-                _ => bail!("unknown type value '{}'", value & 0x7FFFFFFF),
-            }
-            .to_string())
+            let offset = value & 0x7FFFFFFF;
+            common_string(offset)
+                .map(|s| s.to_string())
+                .ok_or_else(|| anyhow!("unknown common string offset '{}'", offset))
         } else if value as usize > self.str_buffer.len() {
             bail!("value '{}' is out of bounds for str buffer", value);
         } else {
@@ -437,8 +421,145 @@ impl AssetFileTypeTree {
     }
 }
 
+// Unity's built-in string buffer. Type tree string offsets with the high bit set index into
+// these strings as if they were laid out back to back with null terminators.
+const COMMON_STRINGS: &[&str] = &[
+    "AABB",
+    "AnimationClip",
+    "AnimationCurve",
+    "AnimationState",
+    "Array",
+    "Base",
+    "BitField",
+    "bitset",
+    "bool",
+    "char",
+    "ColorRGBA",
+    "Component",
+    "data",
+    "deque",
+    "double",
+    "dynamic_array",
+    "FastPropertyName",
+    "first",
+    "float",
+    "Font",
+    "GameObject",
+    "Generic Mono",
+    "GradientNEW",
+    "GUID",
+    "GUIStyle",
+    "int",
+    "list",
+    "long long",
+    "map",
+    "Matrix4x4f",
+    "MdFour",
+    "MonoBehaviour",
+    "MonoScript",
+    "m_ByteSize",
+    "m_Curve",
+    "m_EditorClassIdentifier",
+    "m_EditorHideFlags",
+    "m_Enabled",
+    "m_ExtensionPtr",
+    "m_GameObject",
+    "m_Index",
+    "m_IsArray",
+    "m_IsStatic",
+    "m_MetaFlag",
+    "m_Name",
+    "m_ObjectHideFlags",
+    "m_PrefabInternal",
+    "m_PrefabParentObject",
+    "m_Script",
+    "m_StaticEditorFlags",
+    "m_Type",
+    "m_Version",
+    "Object",
+    "pair",
+    "PPtr<Component>",
+    "PPtr<GameObject>",
+    "PPtr<Material>",
+    "PPtr<MonoBehaviour>",
+    "PPtr<MonoScript>",
+    "PPtr<Object>",
+    "PPtr<Prefab>",
+    "PPtr<Sprite>",
+    "PPtr<TextAsset>",
+    "PPtr<Texture>",
+    "PPtr<Texture2D>",
+    "PPtr<Transform>",
+    "Prefab",
+    "Quaternionf",
+    "Rectf",
+    "RectInt",
+    "RectOffset",
+    "second",
+    "set",
+    "short",
+    "size",
+    "SInt16",
+    "SInt32",
+    "SInt64",
+    "SInt8",
+    "staticvector",
+    "string",
+    "TextAsset",
+    "TextMesh",
+    "Texture",
+    "Texture2D",
+    "Transform",
+    "TypelessData",
+    "UInt16",
+    "UInt32",
+    "UInt64",
+    "UInt8",
+    "unsigned int",
+    "unsigned long long",
+    "unsigned short",
+    "vector",
+    "Vector2f",
+    "Vector3f",
+    "Vector4f",
+    "m_ScriptingClassIdentifier",
+    "Gradient",
+    "Type*",
+    "int2_storage",
+    "int3_storage",
+    "BoundsInt",
+    "m_CorrespondingSourceObject",
+    "m_PrefabInstance",
+    "m_PrefabAsset",
+    "FileSize",
+    "Hash128",
+    "RenderingLayerMask",
+];
+
+fn common_string(offset: u32) -> Option<&'static str> {
+    let mut position = 0;
+    for s in COMMON_STRINGS {
+        if position == offset {
+            return Some(s);
+        }
+        position += s.len() as u32 + 1;
+    }
+    None
+}
+
+pub(crate) fn common_string_offset(value: &str) -> Option<u32> {
+    let mut position = 0;
+    for s in COMMON_STRINGS {
+        if *s == value {
+            return Some(position);
+        }
+        position += s.len() as u32 + 1;
+    }
+    None
+}
+
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AssetFileTypeTreeNode {
     pub node_version: u16,
     pub level: u8,
@@ -461,14 +582,14 @@ pub struct AssetFileObject {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AssetScript {
     pub file_id: u32,
     pub object_id: u64,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AssetExternal {
     pub unknown: NullString,
     pub guid: i128,
@@ -477,35 +598,47 @@ pub struct AssetExternal {
 }
 
 pub struct AssetReadOptions {
-    size: usize,
-    type_hash: i128,
-    pptr: u64,
+    pub(crate) size: usize,
+    pub(crate) class_id: u32,
+    pub(crate) type_hash: i128,
+    pub(crate) pptr: u64,
+    pub(crate) version: UnityVersion,
 }
 
-#[derive(Debug, BinWrite)]
+#[derive(Debug, Clone, BinWrite, Serialize, Deserialize)]
+#[bw(import(version: UnityVersion))]
 pub enum Asset {
     Bundle(AssetBundle),
     Text(TextAsset),
     Script(MonoScript),
     Terrain(MonoBehavior<TerrainData>),
-    Texture2D(Texture2D, u64),
+    #[serde(
+        serialize_with = "serialize_texture",
+        deserialize_with = "deserialize_texture"
+    )]
+    Texture2D(#[bw(args(version))] Texture2D, #[bw(ignore)] u64),
+    Texture2DArray(#[bw(args(version))] Texture2DArray),
+    Cubemap(#[bw(args(version))] Cubemap),
+    Texture3D(#[bw(args(version))] Texture3D),
     SpriteAtlas(SpriteAtlas),
     Sprite(Sprite),
     EmptyMonoBehavior(MonoBehavior<()>),
     GameObject(GameObject),
     Animator(Animator),
-    Mesh(Mesh),
+    Mesh(#[bw(args(version))] Mesh),
     MeshFilter(MeshFilter),
-    MeshRenderer(MeshRenderer),
+    MeshRenderer(#[bw(args(version))] MeshRenderer),
     Avatar(Avatar),
     Transform(Transform),
     Material(Material),
-    SkinnedMeshRenderer(SkinnedMeshRenderer),
+    SkinnedMeshRenderer(#[bw(args(version))] SkinnedMeshRenderer),
     SpringJob(MonoBehavior<SpringJob>),
     SpringBone(MonoBehavior<SpringBone>),
     AnimationClip(AnimationClip),
     AnimatorOverrideController(AnimatorOverrideController),
     AnimatorController(AnimatorController),
+    #[serde(skip)]
+    Custom(CustomAsset),
     Unparsed(Unparsed),
 }
 
@@ -517,6 +650,9 @@ impl Asset {
             Asset::Script(_) => MONO_SCRIPT_HASH,
             Asset::Terrain(_) => TERRAIN_MONO_BEHAVIOR_TYPE_HASH,
             Asset::Texture2D(_, _) => TEXTURE_2D_HASH,
+            Asset::Texture2DArray(_) => TEXTURE_2D_ARRAY_HASH,
+            Asset::Cubemap(_) => CUBEMAP_HASH,
+            Asset::Texture3D(_) => TEXTURE_3D_HASH,
             Asset::SpriteAtlas(_) => SPRITE_ATLAS_HASH,
             Asset::Sprite(_) => SPRITE_HASH,
             Asset::EmptyMonoBehavior(_) => EMPTY_MONO_BEHAVIOR_HASH,
@@ -534,9 +670,72 @@ impl Asset {
             Asset::AnimationClip(_) => ANIMATION_CLIP_HASH,
             Asset::AnimatorOverrideController(_) => ANIMATOR_OVERRIDE_CONTROLLER_HASH,
             Asset::AnimatorController(_) => ANIMATOR_CONTROLLER_HASH,
+            Asset::Custom(custom) => custom.type_hash,
             Asset::Unparsed(blob) => blob.type_hash,
         }
     }
+
+    /// Class ID of built in types. Script types have to be found by [Asset::type_hash].
+    pub fn class_id(&self) -> Option<u32> {
+        let class_id = match self {
+            Asset::Bundle(_) => 142,
+            Asset::Text(_) => 49,
+            Asset::Script(_) => 115,
+            Asset::Texture2D(_, _) => 28,
+            Asset::Texture2DArray(_) => 187,
+            Asset::Cubemap(_) => 89,
+            Asset::Texture3D(_) => 117,
+            Asset::SpriteAtlas(_) => 687078895,
+            Asset::Sprite(_) => 213,
+            Asset::GameObject(_) => 1,
+            Asset::Animator(_) => 95,
+            Asset::MeshFilter(_) => 33,
+            Asset::Mesh(_) => 43,
+            Asset::MeshRenderer(_) => 23,
+            Asset::Avatar(_) => 90,
+            Asset::Transform(_) => 4,
+            Asset::Material(_) => 21,
+            Asset::SkinnedMeshRenderer(_) => 137,
+            Asset::AnimationClip(_) => 74,
+            Asset::AnimatorOverrideController(_) => 221,
+            Asset::AnimatorController(_) => 91,
+            _ => return None,
+        };
+        Some(class_id)
+    }
+
+    /// Where the asset's data is stored when it's streamed from a resource file.
+    pub fn stream_data(&self) -> Option<&StreamingInfo> {
+        match self {
+            Asset::Texture2D(texture, _) => Some(&texture.stream_data),
+            Asset::Cubemap(texture) => Some(&texture.stream_data),
+            Asset::Texture2DArray(texture) => Some(&texture.stream_data),
+            Asset::Texture3D(texture) => Some(&texture.stream_data),
+            Asset::Mesh(mesh) => Some(&mesh.stream_data),
+            _ => None,
+        }
+        .filter(|stream_data| !stream_data.path.0.is_empty())
+    }
+
+    pub fn stream_data_mut(&mut self) -> Option<&mut StreamingInfo> {
+        match self {
+            Asset::Texture2D(texture, _) => Some(&mut texture.stream_data),
+            Asset::Cubemap(texture) => Some(&mut texture.stream_data),
+            Asset::Texture2DArray(texture) => Some(&mut texture.stream_data),
+            Asset::Texture3D(texture) => Some(&mut texture.stream_data),
+            Asset::Mesh(mesh) => Some(&mut mesh.stream_data),
+            _ => None,
+        }
+        .filter(|stream_data| !stream_data.path.0.is_empty())
+    }
+
+    pub(crate) fn set_path_id(&mut self, path_id: i64) {
+        match self {
+            Asset::Texture2D(_, id) => *id = path_id as u64,
+            Asset::Unparsed(blob) => blob.path_id = path_id as u64,
+            _ => {}
+        }
+    }
 }
 
 impl BinRead for Asset {
@@ -547,85 +746,107 @@ impl BinRead for Asset {
         endian: Endian,
         args: Self::Args<'_>,
     ) -> BinResult<Self> {
-        let AssetReadOptions { size, type_hash, pptr } = args;
+        let AssetReadOptions {
+            size,
+            class_id,
+            type_hash,
+            pptr,
+            version,
+        } = args;
+        // Script types only differ by hash. Built in classes are matched by class ID so files
+        // from other Unity versions, whose type hashes differ, are still parsed.
         match type_hash {
-            ASSET_BUNDLE_HASH => AssetBundle::read_options(reader, endian, ()).map(Self::Bundle),
-            TEXT_ASSET_HASH => TextAsset::read_options(reader, endian, ()).map(Self::Text),
-            MONO_SCRIPT_HASH => MonoScript::read_options(reader, endian, ()).map(Self::Script),
             TERRAIN_MONO_BEHAVIOR_TYPE_HASH => {
-                MonoBehavior::<TerrainData>::read_options(reader, endian, ()).map(Self::Terrain)
-            }
-            TEXTURE_2D_HASH => Texture2D::read_options(reader, endian, ())
-                .map(|texture| Self::Texture2D(texture, pptr)),
-            SPRITE_ATLAS_HASH => {
-                SpriteAtlas::read_options(reader, endian, ()).map(Self::SpriteAtlas)
+                return MonoBehavior::<TerrainData>::read_options(reader, endian, ())
+                    .map(Self::Terrain)
             }
             EMPTY_MONO_BEHAVIOR_HASH => {
-                MonoBehavior::<()>::read_options(reader, endian, ()).map(Self::EmptyMonoBehavior)
-            }
-            SPRITE_HASH => Sprite::read_options(reader, endian, ()).map(Self::Sprite),
-            GAME_OBJECT_HASH => GameObject::read_options(reader, endian, ()).map(Self::GameObject),
-            ANIMATOR_HASH => Animator::read_options(reader, endian, ()).map(Self::Animator),
-            MESH_HASH => Mesh::read_options(reader, endian, ()).map(Self::Mesh),
-            MESH_FILTER_HASH => MeshFilter::read_options(reader, endian, ()).map(Self::MeshFilter),
-            MESH_RENDERER_HASH => {
-                MeshRenderer::read_options(reader, endian, ()).map(Self::MeshRenderer)
-            }
-            AVATAR_HASH => Avatar::read_options(reader, endian, ()).map(Self::Avatar),
-            TRANSFORM_HASH => Transform::read_options(reader, endian, ()).map(Self::Transform),
-            MATERIAL_HASH => Material::read_options(reader, endian, ()).map(Self::Material),
-            SKINNED_MESH_RENDERER_HASH => {
-                SkinnedMeshRenderer::read_options(reader, endian, ()).map(Self::SkinnedMeshRenderer)
+                return MonoBehavior::<()>::read_options(reader, endian, ())
+                    .map(Self::EmptyMonoBehavior)
             }
             SPRING_JOB_MONO_BEHAVIOR_HASH => {
-                MonoBehavior::<SpringJob>::read_options(reader, endian, ()).map(Self::SpringJob)
+                return MonoBehavior::<SpringJob>::read_options(reader, endian, ())
+                    .map(Self::SpringJob)
             }
             SPRING_BONE_MONO_BEHAVIOR_HASH => {
-                MonoBehavior::<SpringBone>::read_options(reader, endian, ()).map(Self::SpringBone)
-            }
-            ANIMATION_CLIP_HASH => {
-                AnimationClip::read_options(reader, endian, ()).map(Self::AnimationClip)
+                return MonoBehavior::<SpringBone>::read_options(reader, endian, ())
+                    .map(Self::SpringBone)
             }
-            ANIMATOR_OVERRIDE_CONTROLLER_HASH => {
-                AnimatorOverrideController::read_options(reader, endian, ()).map(Self::AnimatorOverrideController)
+            _ => {}
+        }
+        match class_id {
+            1 => GameObject::read_options(reader, endian, ()).map(Self::GameObject),
+            4 => Transform::read_options(reader, endian, ()).map(Self::Transform),
+            21 => Material::read_options(reader, endian, ()).map(Self::Material),
+            23 => MeshRenderer::read_options(reader, endian, (version,)).map(Self::MeshRenderer),
+            28 => Texture2D::read_options(reader, endian, (version,))
+                .map(|texture| Self::Texture2D(texture, pptr)),
+            33 => MeshFilter::read_options(reader, endian, ()).map(Self::MeshFilter),
+            43 => Mesh::read_options(reader, endian, (version,)).map(Self::Mesh),
+            49 => TextAsset::read_options(reader, endian, ()).map(Self::Text),
+            74 => AnimationClip::read_options(reader, endian, ()).map(Self::AnimationClip),
+            89 => Cubemap::read_options(reader, endian, (version,)).map(Self::Cubemap),
+            90 => Avatar::read_options(reader, endian, ()).map(Self::Avatar),
+            91 => {
+                // The controller is parsed all the way down, so keep the raw data rather than
+                // guess when its layout doesn't account for every byte.
+                let start = reader.stream_position()?;
+                let controller = AnimatorController::read_options(reader, endian, ());
+                let consumed = (reader.stream_position()? - start) as usize;
+                match controller {
+                    Ok(controller) if size.next_multiple_of(4) == consumed.next_multiple_of(4) => {
+                        Ok(Self::AnimatorController(controller))
+                    }
+                    _ => {
+                        reader.seek(SeekFrom::Start(start))?;
+                        read_unparsed(reader, size, type_hash, pptr)
+                    }
+                }
             }
-            ANIMATOR_CONTROLLER_HASH => {
-                let reader_position = reader.stream_position()?;
-                let name: UString = BinRead::read_options(reader, endian, ())?;
-                let reader_position_after_name = reader.stream_position()?;
-
-                // account for the shift from after reading the name.
-                let remaining_size = size - (reader_position_after_name - reader_position) as usize;
-                let mut blob = vec![0; remaining_size];
-                reader.read_exact(&mut blob)?;
-                Ok(Self::AnimatorController(AnimatorController {
-                    name,
-                    blob,
-                }))
+            95 => Animator::read_options(reader, endian, ()).map(Self::Animator),
+            115 => MonoScript::read_options(reader, endian, ()).map(Self::Script),
+            117 => Texture3D::read_options(reader, endian, (version,)).map(Self::Texture3D),
+            137 => SkinnedMeshRenderer::read_options(reader, endian, (version,))
+                .map(Self::SkinnedMeshRenderer),
+            142 => AssetBundle::read_options(reader, endian, ()).map(Self::Bundle),
+            187 => {
+                Texture2DArray::read_options(reader, endian, (version,)).map(Self::Texture2DArray)
             }
-            _ => {
-                let mut blob = vec![0; size];
-                reader.read_exact(&mut blob)?;
-                Ok(Self::Unparsed(Unparsed {
-                    type_hash,
-                    path_id: pptr,
-                    blob,
-                }))
-            },
+            213 => Sprite::read_options(reader, endian, ()).map(Self::Sprite),
+            221 => AnimatorOverrideController::read_options(reader, endian, ())
+                .map(Self::AnimatorOverrideController),
+            687078895 => SpriteAtlas::read_options(reader, endian, ()).map(Self::SpriteAtlas),
+            _ => read_unparsed(reader, size, type_hash, pptr),
         }
     }
 }
 
+fn read_unparsed<R: Read + Seek>(
+    reader: &mut R,
+    size: usize,
+    type_hash: i128,
+    pptr: u64,
+) -> BinResult<Asset> {
+    let mut blob = vec![0; size];
+    reader.read_exact(&mut blob)?;
+    Ok(Asset::Unparsed(Unparsed {
+        type_hash,
+        path_id: pptr,
+        blob,
+    }))
+}
+
 impl ReadEndian for Asset {
     const ENDIAN: EndianKind = EndianKind::Endian(Endian::Little);
 }
 
 #[binread]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Unparsed {
     pub type_hash: i128,
     pub path_id: u64,
     #[br(ignore)]
+    #[serde(with = "hex_bytes")]
     pub blob: Vec<u8>,
 }
 
@@ -811,7 +1032,7 @@ impl BinWrite for UString {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetBundle {
     pub name: UString,
     pub preloads: UArray<PPtr>,
@@ -836,7 +1057,7 @@ pub struct PPtr {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetInfo {
     pub preload_index: u32,
     pub preload_size: u32,
@@ -844,7 +1065,7 @@ pub struct AssetInfo {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameObject {
     pub component: UArray<PPtr>,
     pub layer: u32,
@@ -855,7 +1076,7 @@ pub struct GameObject {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transform {
     pub game_object: PPtr,
     pub local_rotation: Quaternionf,
@@ -866,7 +1087,7 @@ pub struct Transform {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Animator {
     pub game_object: PPtr,
     pub enabled: u8,
@@ -884,14 +1105,14 @@ pub struct Animator {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextAsset {
     pub name: UString,
     pub data: UArray<u8>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonoScript {
     pub name: UString,
     #[brw(align_before = 4)]
@@ -902,7 +1123,22 @@ pub struct MonoScript {
     pub assembly_name: UString,
 }
 
-#[derive(Debug)]
+impl MonoScript {
+    pub fn full_name(&self) -> String {
+        if self.namespace.is_empty() {
+            self.class_name.0.clone()
+        } else {
+            format!("{}.{}", self.namespace, self.class_name)
+        }
+    }
+
+    /// Match against either the bare class name or the namespace qualified name.
+    pub fn is_class(&self, class_name: &str) -> bool {
+        self.class_name.0 == class_name || self.full_name() == class_name
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MonoBehavior<T: std::fmt::Debug> {
     pub game_object: PPtr,
     pub enabled: u8,
@@ -990,7 +1226,7 @@ where
 }
 
 #[binrw]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TerrainData {
     #[brw(align_before = 4)]
     pub x: i32,
@@ -1003,7 +1239,7 @@ pub struct TerrainData {
 }
 
 #[binrw]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TerrainLayerData {
     #[brw(align_before = 4)]
     pub x: i32,
@@ -1015,7 +1251,7 @@ pub struct TerrainLayerData {
 }
 
 #[binrw]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TerrainOverlapData {
     #[brw(align_before = 4)]
     pub x: i32,
@@ -1024,37 +1260,45 @@ pub struct TerrainOverlapData {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Texture2D {
     pub name: UString,
     #[brw(align_before = 4)]
     pub forced_fallback_format: i32,
     pub downscale_fallback: u8,
+    #[brw(if(version >= UnityVersion::new(2020, 2, 0)))]
     pub is_alpha_channel_optional: u8,
     #[brw(align_before = 4)]
     pub width: u32,
     pub height: u32,
     pub complete_image_size: u32,
+    #[brw(if(version >= UnityVersion::new(2020, 1, 0)))]
     pub mips_stripped: u32,
     pub texture_format: TextureFormat,
     pub mip_count: u32,
     pub is_readable: u8,
+    #[brw(if(version >= UnityVersion::new(2020, 1, 0)))]
     pub is_pre_processed: u8,
+    #[brw(if(version >= UnityVersion::new(2019, 3, 0)))]
     pub ignore_master_texture_limit: u8,
     pub streaming_mipmaps: u8,
+    #[brw(align_before = 4)]
     pub streaming_mipmaps_priority: i32,
     pub image_count: u32,
     pub texture_dimension: u32,
     pub texture_settings: GlTextureSettings,
     pub lightmap_format: i32,
     pub color_space: i32,
+    #[brw(if(version >= UnityVersion::new(2020, 2, 0)))]
     pub platform_blob: UArray<u8>,
     pub image_data: UArray<u8>,
+    #[brw(args(version))]
     pub stream_data: StreamingInfo,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlTextureSettings {
     pub filter_mode: i32,
     pub aniso: i32,
@@ -1064,18 +1308,140 @@ pub struct GlTextureSettings {
     pub wrap_w: i32,
 }
 
+/// A cubemap is stored as a Texture2D with six images, ordered +X, -X, +Y, -Y, +Z, -Z.
 #[binrw]
-#[derive(Debug)]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cubemap {
+    #[brw(args(version))]
+    pub texture: Texture2D,
+    pub source_textures: UArray<PPtr>,
+}
+
+impl Deref for Cubemap {
+    type Target = Texture2D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.texture
+    }
+}
+
+impl DerefMut for Cubemap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.texture
+    }
+}
+
+/// The layout shared by Texture2DArray and Texture3D. Both store `depth` slices of the same
+/// size and describe their format with Unity's GraphicsFormat rather than TextureFormat.
+#[binrw]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureArrayData {
+    pub name: UString,
+    #[brw(align_before = 4)]
+    pub forced_fallback_format: i32,
+    pub downscale_fallback: u8,
+    #[brw(if(version >= UnityVersion::new(2020, 2, 0)))]
+    pub is_alpha_channel_optional: u8,
+    #[brw(align_before = 4)]
+    pub color_space: i32,
+    pub format: i32,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub mip_count: u32,
+    pub data_size: u32,
+    pub texture_settings: GlTextureSettings,
+    #[brw(if(version >= UnityVersion::new(2020, 2, 0)))]
+    pub usage_mode: i32,
+    pub is_readable: u8,
+    pub image_data: UArray<u8>,
+    #[brw(args(version))]
+    pub stream_data: StreamingInfo,
+}
+
+#[binrw]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Texture2DArray(#[brw(args(version))] pub TextureArrayData);
+
+#[binrw]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Texture3D(#[brw(args(version))] pub TextureArrayData);
+
+impl Deref for Texture2DArray {
+    type Target = TextureArrayData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Texture2DArray {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Deref for Texture3D {
+    type Target = TextureArrayData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Texture3D {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[binrw]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamingInfo {
+    // 32 bits before 2020.1.
+    #[br(parse_with = read_streaming_offset, args(version))]
+    #[bw(write_with = write_streaming_offset, args(version))]
     pub offset: u64,
     pub size: u32,
     pub path: UString,
 }
 
+fn read_streaming_offset<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    (version,): (UnityVersion,),
+) -> BinResult<u64> {
+    if version >= UnityVersion::new(2020, 1, 0) {
+        u64::read_options(reader, endian, ())
+    } else {
+        u32::read_options(reader, endian, ()).map(u64::from)
+    }
+}
+
+fn write_streaming_offset<W: Write + Seek>(
+    offset: &u64,
+    writer: &mut W,
+    endian: Endian,
+    (version,): (UnityVersion,),
+) -> BinResult<()> {
+    if version >= UnityVersion::new(2020, 1, 0) {
+        offset.write_options(writer, endian, ())
+    } else {
+        (*offset as u32).write_options(writer, endian, ())
+    }
+}
+
 // Borrowed from https://github.com/gameltb/io_unity/
 #[binrw]
 #[brw(repr = u32)]
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
 pub enum TextureFormat {
     Alpha8 = 1,
@@ -1150,8 +1516,144 @@ pub enum TextureFormat {
     RGBA64,
 }
 
+impl TextureFormat {
+    /// Width, height and size in bytes of a block. Uncompressed formats use 1x1 blocks.
+    /// Crunched formats are variable length and return None.
+    pub fn block_info(&self) -> Option<(usize, usize, usize)> {
+        let info = match self {
+            TextureFormat::Alpha8 | TextureFormat::R8 => (1, 1, 1),
+            TextureFormat::ARGB4444
+            | TextureFormat::RGBA4444
+            | TextureFormat::RGB565
+            | TextureFormat::R16
+            | TextureFormat::RHalf
+            | TextureFormat::RG16 => (1, 1, 2),
+            TextureFormat::RGB24 | TextureFormat::BGR24 => (1, 1, 3),
+            TextureFormat::RGBA32
+            | TextureFormat::ARGB32
+            | TextureFormat::BGRA32
+            | TextureFormat::RGHalf
+            | TextureFormat::RFloat
+            | TextureFormat::RGB9e5Float
+            | TextureFormat::RG32 => (1, 1, 4),
+            TextureFormat::RGB48 => (1, 1, 6),
+            TextureFormat::RGBAHalf | TextureFormat::RGFloat | TextureFormat::RGBA64 => (1, 1, 8),
+            TextureFormat::RGBFloat => (1, 1, 12),
+            TextureFormat::ARGBFloat | TextureFormat::RGBAFloat => (1, 1, 16),
+            TextureFormat::YUY2 => (2, 1, 4),
+            TextureFormat::DXT1
+            | TextureFormat::BC4
+            | TextureFormat::ETC_RGB4
+            | TextureFormat::ETC_RGB4_3DS
+            | TextureFormat::ATC_RGB4
+            | TextureFormat::EAC_R
+            | TextureFormat::EAC_R_SIGNED
+            | TextureFormat::ETC2_RGB
+            | TextureFormat::ETC2_RGBA1 => (4, 4, 8),
+            TextureFormat::DXT3
+            | TextureFormat::DXT5
+            | TextureFormat::BC5
+            | TextureFormat::BC6H
+            | TextureFormat::BC7
+            | TextureFormat::ETC_RGBA8_3DS
+            | TextureFormat::ATC_RGBA8
+            | TextureFormat::EAC_RG
+            | TextureFormat::EAC_RG_SIGNED
+            | TextureFormat::ETC2_RGBA8 => (4, 4, 16),
+            TextureFormat::PVRTC_RGB2 | TextureFormat::PVRTC_RGBA2 => (8, 4, 8),
+            TextureFormat::PVRTC_RGB4 | TextureFormat::PVRTC_RGBA4 => (4, 4, 8),
+            TextureFormat::ASTC_RGB_4x4
+            | TextureFormat::ASTC_RGBA_4x4
+            | TextureFormat::ASTC_HDR_4x4 => (4, 4, 16),
+            TextureFormat::ASTC_RGB_5x5
+            | TextureFormat::ASTC_RGBA_5x5
+            | TextureFormat::ASTC_HDR_5x5 => (5, 5, 16),
+            TextureFormat::ASTC_RGB_6x6
+            | TextureFormat::ASTC_RGBA_6x6
+            | TextureFormat::ASTC_HDR_6x6 => (6, 6, 16),
+            TextureFormat::ASTC_RGB_8x8
+            | TextureFormat::ASTC_RGBA_8x8
+            | TextureFormat::ASTC_HDR_8x8 => (8, 8, 16),
+            TextureFormat::ASTC_RGB_10x10
+            | TextureFormat::ASTC_RGBA_10x10
+            | TextureFormat::ASTC_HDR_10x10 => (10, 10, 16),
+            TextureFormat::ASTC_RGB_12x12
+            | TextureFormat::ASTC_RGBA_12x12
+            | TextureFormat::ASTC_HDR_12x12 => (12, 12, 16),
+            TextureFormat::DXT1Crunched
+            | TextureFormat::DXT5Crunched
+            | TextureFormat::ETC_RGB4Crunched
+            | TextureFormat::ETC2_RGBA8Crunched => return None,
+        };
+        Some(info)
+    }
+}
+
+impl TextureFormat {
+    /// Map one of Unity's GraphicsFormat values to the TextureFormat with the same data layout.
+    /// sRGB and linear variants map to the same format.
+    pub fn from_graphics_format(format: i32) -> Option<Self> {
+        let format = match format {
+            1 | 5 | 9 | 13 | 17 => TextureFormat::R8,
+            2 | 6 | 10 | 14 | 18 => TextureFormat::RG16,
+            3 | 7 | 11 | 15 | 19 => TextureFormat::RGB24,
+            4 | 8 | 12 | 16 | 20 => TextureFormat::RGBA32,
+            21 | 25 | 29 | 33 => TextureFormat::R16,
+            22 | 26 | 30 | 34 => TextureFormat::RG32,
+            23 | 27 | 31 | 35 => TextureFormat::RGB48,
+            24 | 28 | 32 | 36 => TextureFormat::RGBA64,
+            45 => TextureFormat::RHalf,
+            46 => TextureFormat::RGHalf,
+            48 => TextureFormat::RGBAHalf,
+            49 => TextureFormat::RFloat,
+            50 => TextureFormat::RGFloat,
+            51 => TextureFormat::RGBFloat,
+            52 => TextureFormat::RGBAFloat,
+            56 | 58 | 60 | 62 | 64 => TextureFormat::BGR24,
+            57 | 59 | 61 | 63 | 65 => TextureFormat::BGRA32,
+            66 => TextureFormat::RGBA4444,
+            68 => TextureFormat::RGB565,
+            73 => TextureFormat::RGB9e5Float,
+            96 | 97 => TextureFormat::DXT1,
+            98 | 99 => TextureFormat::DXT3,
+            100 | 101 => TextureFormat::DXT5,
+            102 | 103 => TextureFormat::BC4,
+            104 | 105 => TextureFormat::BC5,
+            106 | 107 => TextureFormat::BC6H,
+            108 | 109 => TextureFormat::BC7,
+            110 | 111 => TextureFormat::PVRTC_RGB2,
+            112 | 113 => TextureFormat::PVRTC_RGB4,
+            114 | 115 => TextureFormat::PVRTC_RGBA2,
+            116 | 117 => TextureFormat::PVRTC_RGBA4,
+            118 => TextureFormat::ETC_RGB4,
+            119 | 120 => TextureFormat::ETC2_RGB,
+            121 | 122 => TextureFormat::ETC2_RGBA1,
+            123 | 124 => TextureFormat::ETC2_RGBA8,
+            125 => TextureFormat::EAC_R,
+            126 => TextureFormat::EAC_R_SIGNED,
+            127 => TextureFormat::EAC_RG,
+            128 => TextureFormat::EAC_RG_SIGNED,
+            129 | 130 => TextureFormat::ASTC_RGBA_4x4,
+            131 | 132 => TextureFormat::ASTC_RGBA_5x5,
+            133 | 134 => TextureFormat::ASTC_RGBA_6x6,
+            135 | 136 => TextureFormat::ASTC_RGBA_8x8,
+            137 | 138 => TextureFormat::ASTC_RGBA_10x10,
+            139 | 140 => TextureFormat::ASTC_RGBA_12x12,
+            141 => TextureFormat::YUY2,
+            145 => TextureFormat::ASTC_HDR_4x4,
+            146 => TextureFormat::ASTC_HDR_5x5,
+            147 => TextureFormat::ASTC_HDR_6x6,
+            148 => TextureFormat::ASTC_HDR_8x8,
+            149 => TextureFormat::ASTC_HDR_10x10,
+            150 => TextureFormat::ASTC_HDR_12x12,
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpriteAtlas {
     pub name: UString,
     pub packed_sprites: UArray<PPtr>,
@@ -1162,7 +1664,7 @@ pub struct SpriteAtlas {
 }
 
 #[binrw]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RenderDataKey {
     #[brw(align_before = 4)]
     pub guid: i128,
@@ -1170,7 +1672,7 @@ pub struct RenderDataKey {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpriteAtlasData {
     pub texture: PPtr,
     pub alpha_texture: PPtr,
@@ -1184,7 +1686,7 @@ pub struct SpriteAtlasData {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sprite {
     pub name: UString,
     pub rect: RectF,
@@ -1203,7 +1705,7 @@ pub struct Sprite {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RectF {
     #[brw(align_before = 4)]
     pub x: f32,
@@ -1213,7 +1715,7 @@ pub struct RectF {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vector2f {
     #[brw(align_before = 4)]
     pub x: f32,
@@ -1221,7 +1723,7 @@ pub struct Vector2f {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vector3f {
     #[brw(align_before = 4)]
     pub x: f32,
@@ -1230,7 +1732,7 @@ pub struct Vector3f {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vector4f {
     #[brw(align_before = 4)]
     pub x: f32,
@@ -1240,7 +1742,7 @@ pub struct Vector4f {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpriteRenderData {
     pub texture: PPtr,
     pub alpha_texture: PPtr,
@@ -1258,14 +1760,14 @@ pub struct SpriteRenderData {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecondarySpriteTexture {
     pub texture: PPtr,
     pub name: UString,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubMesh {
     #[brw(align_before = 4)]
     pub first_byte: u32,
@@ -1278,14 +1780,14 @@ pub struct SubMesh {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AABB {
     pub center: Vector3f,
     pub extent: Vector3f,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VertexData {
     #[brw(align_before = 4)]
     pub vertex_count: u32,
@@ -1294,7 +1796,7 @@ pub struct VertexData {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelInfo {
     pub stream: u8,
     pub offset: u8,
@@ -1303,7 +1805,7 @@ pub struct ChannelInfo {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Matrix4x4f {
     pub e00: f32,
     pub e01: f32,
@@ -1324,7 +1826,7 @@ pub struct Matrix4x4f {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpriteBone {
     pub name: UString,
     pub position: Vector3f,
@@ -1334,7 +1836,7 @@ pub struct SpriteBone {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Quaternionf {
     pub x: f32,
     pub y: f32,
@@ -1343,7 +1845,8 @@ pub struct Quaternionf {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mesh {
     pub name: UString,
     pub sub_meshes: UArray<SubMesh>,
@@ -1368,11 +1871,12 @@ pub struct Mesh {
     pub baked_triangle_collision_mesh: UArray<u8>,
     pub mesh_metrics_0: f32,
     pub mesh_metrics_1: f32,
+    #[brw(args(version))]
     pub stream_data: StreamingInfo,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlendShapeData {
     pub vertices: UArray<BlendShapeVertex>,
     pub shapes: UArray<MeshBlendShape>,
@@ -1381,7 +1885,7 @@ pub struct BlendShapeData {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlendShapeVertex {
     pub vertex: Vector3f,
     pub normal: Vector3f,
@@ -1390,7 +1894,7 @@ pub struct BlendShapeVertex {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshBlendShape {
     #[brw(align_before = 4)]
     pub first_vertex: u32,
@@ -1400,7 +1904,7 @@ pub struct MeshBlendShape {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshBlendShapeChannel {
     pub name: UString,
     pub name_hash: u32,
@@ -1409,14 +1913,14 @@ pub struct MeshBlendShapeChannel {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MinMaxAABB {
-    min: Vector3f,
-    max: Vector3f,
+    pub min: Vector3f,
+    pub max: Vector3f,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressedMesh {
     pub vertices: PackedBitVector,
     pub uv: PackedBitVector,
@@ -1433,7 +1937,7 @@ pub struct CompressedMesh {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackedBitVector {
     #[brw(align_before = 4)]
     pub num_items: u32,
@@ -1444,7 +1948,7 @@ pub struct PackedBitVector {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackedBitVector2 {
     #[brw(align_before = 4)]
     pub num_items: u32,
@@ -1453,7 +1957,7 @@ pub struct PackedBitVector2 {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Avatar {
     pub name: UString,
     pub avatar_size: u32,
@@ -1463,7 +1967,7 @@ pub struct Avatar {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TosPair {
     #[brw(align_before = 4)]
     pub first: u32,
@@ -1471,7 +1975,7 @@ pub struct TosPair {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AvatarConstant {
     pub skeleton: Skeleton,
     pub avatar_skeleton_pose: SkeletonPose,
@@ -1488,7 +1992,7 @@ pub struct AvatarConstant {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Skeleton {
     pub node: UArray<SkeletonNode>,
     pub id: UArray<u32>,
@@ -1496,14 +2000,14 @@ pub struct Skeleton {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonNode {
     pub parent_id: u32,
     pub axes_id: u32,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonAxes {
     pub pre_q: Vector4f,
     pub post_q: Vector4f,
@@ -1514,20 +2018,20 @@ pub struct SkeletonAxes {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonLimit {
     pub min: Vector3f,
     pub max: Vector3f,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonPose {
     pub transform: UArray<SkeletonTransform>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonTransform {
     pub transform: Vector3f,
     pub quaternion: Quaternionf,
@@ -1535,7 +2039,7 @@ pub struct SkeletonTransform {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AvatarHuman {
     pub root_x: SkeletonTransform,
     pub skeleton: Skeleton,
@@ -1558,7 +2062,7 @@ pub struct AvatarHuman {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HumanDescription {
     pub human: UArray<HumanBone>,
     pub skeleton: UArray<SkeletonBone>,
@@ -1577,7 +2081,7 @@ pub struct HumanDescription {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HumanBone {
     pub bone_name: UString,
     pub human_name: UString,
@@ -1585,7 +2089,7 @@ pub struct HumanBone {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonBoneLimit {
     pub min: Vector3f,
     pub max: Vector3f,
@@ -1595,7 +2099,7 @@ pub struct SkeletonBoneLimit {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkeletonBone {
     pub name: UString,
     pub parent_name: UString,
@@ -1605,7 +2109,7 @@ pub struct SkeletonBone {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Material {
     pub name: UString,
     pub shader: PPtr,
@@ -1623,7 +2127,7 @@ pub struct Material {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnityPropertySheet {
     pub text_envs: UArray<(UString, TexEnv)>,
     pub floats: UArray<FloatPropertySheetPair>,
@@ -1631,7 +2135,7 @@ pub struct UnityPropertySheet {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TexEnv {
     #[brw(align_before = 4)]
     pub texture: PPtr,
@@ -1640,7 +2144,7 @@ pub struct TexEnv {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FloatPropertySheetPair {
     pub key: UString,
     #[brw(align_before = 4)]
@@ -1648,7 +2152,7 @@ pub struct FloatPropertySheetPair {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorRGBA {
     #[brw(align_before = 4)]
     pub r: f32,
@@ -1658,24 +2162,29 @@ pub struct ColorRGBA {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshFilter {
     pub game_object: PPtr,
     pub mesh: PPtr,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshRenderer {
     pub game_object: PPtr,
     pub enabled: u8,
     pub cast_shadows: u8,
     pub receive_shadows: u8,
     pub dynamic_occludee: u8,
+    #[brw(if(version >= UnityVersion::new(2021, 1, 0)))]
+    pub static_shadow_caster: u8,
     pub motion_vectors: u8,
     pub light_probe_usage: u8,
     pub reflection_probe_usage: u8,
+    #[brw(if(version >= UnityVersion::new(2019, 3, 0)))]
     pub ray_tracing_mode: u8,
+    #[brw(if(version >= UnityVersion::new(2020, 1, 0)))]
     pub ray_tracing_procedural: u8,
     #[brw(align_before = 4)]
     pub rendering_layer_mask: u32,
@@ -1697,24 +2206,29 @@ pub struct MeshRenderer {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StaticBatchInfo {
     pub first_sub_mesh: u16,
     pub sub_mesh_count: u16,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[brw(import(version: UnityVersion))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkinnedMeshRenderer {
     pub game_object: PPtr,
     pub enabled: u8,
     pub cast_shadows: u8,
     pub receive_shadows: u8,
     pub dynamic_occludee: u8,
+    #[brw(if(version >= UnityVersion::new(2021, 1, 0)))]
+    pub static_shadow_caster: u8,
     pub motion_vectors: u8,
     pub light_probe_usage: u8,
     pub reflection_probe_usage: u8,
+    #[brw(if(version >= UnityVersion::new(2019, 3, 0)))]
     pub ray_tracing_mode: u8,
+    #[brw(if(version >= UnityVersion::new(2020, 1, 0)))]
     pub ray_trace_procedural: u8,
     #[brw(align_before = 4)]
     pub rendering_layer_mask: u32,
@@ -1745,7 +2259,7 @@ pub struct SkinnedMeshRenderer {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpringJob {
     pub optimize_transform: u32,
     pub is_paused: u32,
@@ -1777,7 +2291,7 @@ pub struct SpringJob {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpringBoneProperties {
     pub stiffness_force: f32,
     pub drag_force: f32,
@@ -1797,7 +2311,7 @@ pub struct SpringBoneProperties {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AngleLimits {
     pub active: u8,
     #[brw(align_before = 4)]
@@ -1806,7 +2320,7 @@ pub struct AngleLimits {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpringColliderProperty {
     pub ty: u32,
     pub radius: f32,
@@ -1815,14 +2329,14 @@ pub struct SpringColliderProperty {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LengthLimitProperty {
     pub target_index: u32,
     pub target: f32,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpringBone {
     pub index: u32,
     pub enabled_job_system: u8,
@@ -1844,7 +2358,7 @@ pub struct SpringBone {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimationClip {
     pub name: UString,
     #[brw(align_before = 4)]
@@ -1872,7 +2386,7 @@ pub struct AnimationClip {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipMuscleConstant {
     pub delta_pose: HumanPose,
     pub start_x: XForm,
@@ -1905,14 +2419,14 @@ pub struct ClipMuscleConstant {
 
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuaternionCurve {
     pub curve: QuaternionAnimationCurve,
     pub path: UString,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuaternionAnimationCurve {
     pub curve: UArray<QuaternionCurveKeyframe>,
     pub pre_infinity: i32,
@@ -1921,7 +2435,7 @@ pub struct QuaternionAnimationCurve {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vector3Curve {
     pub curve: UArray<Vector3f>,
     pub pre_infinity: i32,
@@ -1930,7 +2444,7 @@ pub struct Vector3Curve {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FloatCurve {
     pub curve: UArray<f32>,
     pub pre_infinity: i32,
@@ -1939,7 +2453,7 @@ pub struct FloatCurve {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PPtrCurve {
     pub curve: UArray<PPtr>,
     pub pre_infinity: i32,
@@ -1948,7 +2462,7 @@ pub struct PPtrCurve {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuaternionCurveKeyframe {
     pub time: f32,
     pub value: Quaternionf,
@@ -1960,7 +2474,7 @@ pub struct QuaternionCurveKeyframe {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressedAnimationCurve {
     pub path: UString,
     pub times: PackedIntVector,
@@ -1971,7 +2485,7 @@ pub struct CompressedAnimationCurve {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackedIntVector {
     pub num_items: u32,
     pub data: UArray<u8>,
@@ -1979,14 +2493,14 @@ pub struct PackedIntVector {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackdQuatVector {
     pub num_items: u32,
     pub data: UArray<u8>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackedFloatVector {
     pub num_items: u32,
     pub range: f32,
@@ -1996,7 +2510,7 @@ pub struct PackedFloatVector {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XForm {
     pub t: Vector3f,
     pub q: Quaternionf,
@@ -2004,7 +2518,7 @@ pub struct XForm {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HumanPose {
     pub root_x: XForm,
     pub look_at_position: Vector3f,
@@ -2017,7 +2531,7 @@ pub struct HumanPose {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HumanGoal {
     pub x: XForm,
     pub weight_t: f32,
@@ -2027,7 +2541,7 @@ pub struct HumanGoal {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HandPose {
     pub grab_x: XForm,
     pub do_f_array: UArray<f32>,
@@ -2038,46 +2552,45 @@ pub struct HandPose {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamedClip {
-    data: UArray<u32>,
-    curve_count: u32,
+    pub data: UArray<u32>,
+    pub curve_count: u32,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DenseClip {
-    frame_count: i32,
-    curve_count: u32,
-    sample_rate: f32,
-    begin_time: f32,
-    sample_array: UArray<f32>,
+    pub frame_count: i32,
+    pub curve_count: u32,
+    pub sample_rate: f32,
+    pub begin_time: f32,
+    pub sample_array: UArray<f32>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConstantClip {
-    data: UArray<f32>,
+    pub data: UArray<f32>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValueConstant {
     pub id: u32,
-    pub type_id: u32,
     pub m_type: u32,
     pub index: u32,
 }
 
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValueArrayConstant {
     pub value_array: UArray<ValueConstant>,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Clip {
     pub streamed_clip: StreamedClip,
     pub dense_clip: DenseClip,
@@ -2085,14 +2598,14 @@ pub struct Clip {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValueDelta {
     pub start: f32,
     pub stop: f32,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenericBinding {
     pub path: u32,
     pub attribute: u32,
@@ -2104,14 +2617,14 @@ pub struct GenericBinding {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimationClipBindingConstant {
     pub generic_bindings: UArray<GenericBinding>,
     pub pptr_curve_mappings: UArray<PPtr>
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimationEvent {
     pub time: f32,
     pub function_name: UString,
@@ -2123,7 +2636,7 @@ pub struct AnimationEvent {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimatorOverrideController {
     pub name: UString,
     pub controller: PPtr,
@@ -2131,16 +2644,393 @@ pub struct AnimatorOverrideController {
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimationClipOverride {
     pub original_clip: PPtr,
     pub override_clip: PPtr,
 }
 
 #[binrw]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimatorController {
     pub name: UString,
-    #[br(ignore)]
-    pub blob: Vec<u8>,
-}
\ No newline at end of file
+    pub controller_size: u32,
+    pub controller: ControllerConstant,
+    pub tos: UArray<TosPair>,
+    pub animation_clips: UArray<PPtr>,
+    pub state_machine_behaviour_vector_description: StateMachineBehaviourVectorDescription,
+    pub state_machine_behaviours: UArray<PPtr>,
+    #[brw(align_after = 4)]
+    pub multi_threaded_state_machine: u8,
+}
+
+impl AnimatorController {
+    /// Unity's Animator.StringToHash. State, layer and parameter IDs are hashes of their names.
+    pub fn string_to_hash(value: &str) -> u32 {
+        crc32fast::hash(value.as_bytes())
+    }
+
+    /// Look up the string behind an ID in the TOS table.
+    pub fn name_of(&self, id: u32) -> Option<&str> {
+        self.tos
+            .iter()
+            .find(|pair| pair.first == id)
+            .map(|pair| pair.second.0.as_str())
+    }
+
+    /// Find a state by name or full path (ex. "Base Layer.Idle").
+    /// Returns the index of the state machine and the index of the state within it.
+    pub fn find_state(&self, name: &str) -> Option<(usize, usize)> {
+        let hash = Self::string_to_hash(name);
+        self.controller
+            .state_machine_array
+            .iter()
+            .enumerate()
+            .find_map(|(machine_index, machine)| {
+                machine
+                    .state_constant_array
+                    .iter()
+                    .position(|state| state.full_path_id == hash || state.name_id == hash)
+                    .map(|state_index| (machine_index, state_index))
+            })
+    }
+
+    /// Every clip a state plays. Blend trees list each clip they blend between.
+    pub fn state_clips(&self, state: &StateConstant) -> Vec<&PPtr> {
+        state
+            .blend_tree_constant_array
+            .iter()
+            .flat_map(|tree| tree.node_array.iter())
+            .filter(|node| node.child_indices.is_empty())
+            .filter_map(|node| self.animation_clips.get(node.clip_id as usize))
+            .collect()
+    }
+
+    /// Make a state play a different clip. States that play a blend tree are rejected
+    /// since there's no telling which of their clips should be replaced.
+    pub fn set_state_clip(&mut self, name: &str, clip: PPtr) -> Result<()> {
+        let (machine_index, state_index) = self
+            .find_state(name)
+            .ok_or_else(|| anyhow!("unknown state '{}'", name))?;
+        let state =
+            &self.controller.state_machine_array[machine_index].state_constant_array[state_index];
+        if state.blend_tree_constant_array.is_empty() {
+            bail!("state '{}' has no motion", name);
+        }
+        if state
+            .blend_tree_constant_array
+            .iter()
+            .any(|tree| tree.node_array.len() != 1)
+        {
+            bail!("state '{}' plays a blend tree", name);
+        }
+        let clip_id = self.add_clip(clip);
+        let state = &mut self.controller.state_machine_array[machine_index].state_constant_array
+            [state_index];
+        for tree in state.blend_tree_constant_array.iter_mut() {
+            tree.node_array[0].clip_id = clip_id;
+        }
+        Ok(())
+    }
+
+    /// Add a state that plays `clip` to a state machine and return its index.
+    /// Settings such as speed and write defaults are copied from the machine's default state.
+    /// The new state has no transitions, so other states need one to reach it.
+    pub fn add_state(&mut self, state_machine: usize, name: &str, clip: PPtr) -> Result<usize> {
+        let machine = self
+            .controller
+            .state_machine_array
+            .get(state_machine)
+            .ok_or_else(|| anyhow!("state machine index '{}' is out of bounds", state_machine))?;
+        if machine
+            .state_constant_array
+            .iter()
+            .any(|state| state.name_id == Self::string_to_hash(name))
+        {
+            bail!("state '{}' already exists", name);
+        }
+        let mut state = machine
+            .state_constant_array
+            .get(machine.default_state as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("state machine '{}' has no default state", state_machine))?;
+        let mut node = state
+            .blend_tree_constant_array
+            .iter()
+            .flat_map(|tree| tree.node_array.iter())
+            .find(|node| node.child_indices.is_empty())
+            .cloned()
+            .ok_or_else(|| anyhow!("the default state doesn't play a clip"))?;
+
+        // Paths are built from the default state's so that sub-state machines are handled.
+        let template_name = self
+            .name_of(state.name_id)
+            .ok_or_else(|| anyhow!("the default state's name is missing from the TOS table"))?;
+        let replace_name = |id: u32| {
+            self.name_of(id)
+                .and_then(|path| path.strip_suffix(template_name))
+                .map(|prefix| format!("{}{}", prefix, name))
+                .ok_or_else(|| anyhow!("the default state's path is missing from the TOS table"))
+        };
+        let path = replace_name(state.path_id)?;
+        let full_path = replace_name(state.full_path_id)?;
+
+        state.name_id = self.add_tos(name);
+        state.path_id = self.add_tos(&path);
+        state.full_path_id = self.add_tos(&full_path);
+        state.transition_constant_array.clear();
+        node.clip_id = self.add_clip(clip);
+        state.blend_tree_constant_array.items = vec![BlendTreeConstant {
+            node_array: UArray { items: vec![node] },
+        }];
+        for index in state.blend_tree_constant_index_array.iter_mut() {
+            if *index >= 0 {
+                *index = 0;
+            }
+        }
+
+        let states = &mut self.controller.state_machine_array[state_machine].state_constant_array;
+        states.push(state);
+        Ok(states.len() - 1)
+    }
+
+    fn add_clip(&mut self, clip: PPtr) -> u32 {
+        let index = match self
+            .animation_clips
+            .iter()
+            .position(|c| c.file_id == clip.file_id && c.path_id == clip.path_id)
+        {
+            Some(index) => index,
+            None => {
+                self.animation_clips.push(clip);
+                self.animation_clips.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    // The table is a map in Unity, so it's kept sorted by ID.
+    fn add_tos(&mut self, value: &str) -> u32 {
+        let id = Self::string_to_hash(value);
+        if let Err(index) = self.tos.binary_search_by_key(&id, |pair| pair.first) {
+            self.tos.insert(
+                index,
+                TosPair {
+                    first: id,
+                    second: UString(value.to_string()),
+                },
+            );
+        }
+        id
+    }
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerConstant {
+    pub layer_array: UArray<LayerConstant>,
+    pub state_machine_array: UArray<StateMachineConstant>,
+    pub values: ValueArrayConstant,
+    pub default_values: ValueArray,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerConstant {
+    pub state_machine_index: u32,
+    pub state_machine_motion_set_index: u32,
+    pub body_mask: HumanPoseMask,
+    pub skeleton_mask: SkeletonMask,
+    pub binding: u32,
+    pub layer_blending_mode: i32,
+    pub default_weight: f32,
+    pub ik_pass: u8,
+    #[brw(align_after = 4)]
+    pub synced_layer_affects_timing: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HumanPoseMask {
+    pub word0: u32,
+    pub word1: u32,
+    pub word2: u32,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkeletonMask {
+    pub data: UArray<SkeletonMaskElement>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkeletonMaskElement {
+    pub path_hash: u32,
+    pub weight: f32,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMachineConstant {
+    pub state_constant_array: UArray<StateConstant>,
+    pub any_state_transition_constant_array: UArray<TransitionConstant>,
+    pub selector_state_constant_array: UArray<SelectorStateConstant>,
+    pub default_state: u32,
+    pub motion_set_count: u32,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateConstant {
+    pub transition_constant_array: UArray<TransitionConstant>,
+    pub blend_tree_constant_index_array: UArray<i32>,
+    pub blend_tree_constant_array: UArray<BlendTreeConstant>,
+    pub name_id: u32,
+    pub path_id: u32,
+    pub full_path_id: u32,
+    pub tag_id: u32,
+    pub speed_param_id: u32,
+    pub mirror_param_id: u32,
+    pub cycle_offset_param_id: u32,
+    pub time_param_id: u32,
+    pub speed: f32,
+    pub cycle_offset: f32,
+    pub ik_on_feet: u8,
+    pub write_default_values: u8,
+    pub m_loop: u8,
+    #[brw(align_after = 4)]
+    pub mirror: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionConstant {
+    pub condition_constant_array: UArray<ConditionConstant>,
+    pub destination_state: u32,
+    pub full_path_id: u32,
+    pub id: u32,
+    pub user_id: u32,
+    pub transition_duration: f32,
+    pub transition_offset: f32,
+    pub exit_time: f32,
+    pub has_exit_time: u8,
+    #[brw(align_after = 4)]
+    pub has_fixed_duration: u8,
+    pub interruption_source: i32,
+    pub ordered_interruption: u8,
+    #[brw(align_after = 4)]
+    pub can_transition_to_self: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionConstant {
+    pub condition_mode: u32,
+    pub event_id: u32,
+    pub event_threshold: f32,
+    pub exit_time: f32,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorStateConstant {
+    pub transition_constant_array: UArray<SelectorTransitionConstant>,
+    pub full_path_id: u32,
+    #[brw(align_after = 4)]
+    pub is_entry: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorTransitionConstant {
+    pub destination: u32,
+    pub condition_constant_array: UArray<ConditionConstant>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendTreeConstant {
+    pub node_array: UArray<BlendTreeNodeConstant>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendTreeNodeConstant {
+    pub blend_type: u32,
+    pub blend_event_id: u32,
+    pub blend_event_y_id: u32,
+    pub child_indices: UArray<u32>,
+    pub blend_1d_data: Blend1dDataConstant,
+    pub blend_2d_data: Blend2dDataConstant,
+    pub blend_direct_data: BlendDirectDataConstant,
+    /// Index into [AnimatorController::animation_clips].
+    pub clip_id: u32,
+    pub duration: f32,
+    pub cycle_offset: f32,
+    #[brw(align_after = 4)]
+    pub mirror: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blend1dDataConstant {
+    pub child_threshold_array: UArray<f32>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blend2dDataConstant {
+    pub child_position_array: UArray<Vector2f>,
+    pub child_magnitude_array: UArray<f32>,
+    pub child_pair_vector_array: UArray<Vector2f>,
+    pub child_pair_avg_mag_inv_array: UArray<f32>,
+    pub child_neighbor_list_array: UArray<MotionNeighborList>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionNeighborList {
+    pub neighbor_array: UArray<u32>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendDirectDataConstant {
+    pub child_blend_event_id_array: UArray<u32>,
+    #[brw(align_after = 4)]
+    pub normalized_blend_values: u8,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueArray {
+    pub position_values: UArray<Vector3f>,
+    pub quaternion_values: UArray<Vector4f>,
+    pub scale_values: UArray<Vector3f>,
+    pub float_values: UArray<f32>,
+    pub int_values: UArray<i32>,
+    #[brw(align_after = 4)]
+    pub bool_values: UArray<u8>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMachineBehaviourVectorDescription {
+    pub state_machine_behaviour_ranges: UArray<(StateKey, StateRange)>,
+    pub state_machine_behaviour_indices: UArray<u32>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateKey {
+    pub state_id: u32,
+    pub layer_index: i32,
+}
+
+#[binrw]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateRange {
+    pub start_index: u32,
+    pub count: u32,
+}
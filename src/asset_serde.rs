@@ -0,0 +1,777 @@
+use std::borrow::Cow;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use binrw::{BinRead, BinWrite, Endian};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    Asset, AssetFile, AssetFileType, AssetReadOptions, PPtr, Texture2D, UArray, UString, Unparsed,
+};
+
+impl Serialize for UString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for UString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+impl<T: Serialize + std::fmt::Debug> Serialize for UArray<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + std::fmt::Debug> Deserialize<'de> for UArray<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(|items| Self { items })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PPtrFields {
+    file_id: i32,
+    path_id: i64,
+}
+
+// Null pointers are written as null to keep exported files readable.
+impl Serialize for PPtr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.file_id == 0 && self.path_id == 0 {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(&PPtrFields {
+                file_id: self.file_id,
+                path_id: self.path_id,
+            })
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PPtr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<PPtrFields>::deserialize(deserializer)?
+            .map(|fields| PPtr {
+                file_id: fields.file_id,
+                path_id: fields.path_id,
+            })
+            .unwrap_or_default())
+    }
+}
+
+// The path ID stored with a texture comes from the file, so only the texture itself is written.
+pub(crate) fn serialize_texture<S: Serializer>(
+    texture: &Texture2D,
+    _path_id: &u64,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    texture.serialize(serializer)
+}
+
+pub(crate) fn deserialize_texture<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(Texture2D, u64), D::Error> {
+    Ok((Texture2D::deserialize(deserializer)?, 0))
+}
+
+/// Raw data as a hex string.
+pub(crate) mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let text: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if !text.is_ascii() || text.len() % 2 != 0 {
+            return Err(D::Error::custom("expected a hex string"));
+        }
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedObject<A> {
+    path_id: i64,
+    asset: A,
+}
+
+impl AssetFile {
+    /// Write every object to `dir` as `<path ID>.json`.
+    /// Custom assets have no serde support, so they are written as raw data. Apply the
+    /// [crate::AssetRegistry] again after [AssetFile::import_json] to parse them.
+    pub fn export_json<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (path_id, asset) in self.iter_with_path_ids() {
+            let asset = match asset {
+                Asset::Custom(custom) => {
+                    let mut cursor = Cursor::new(vec![]);
+                    custom.write_options(&mut cursor, Endian::Little, ())?;
+                    Cow::Owned(Asset::Unparsed(Unparsed {
+                        type_hash: custom.type_hash,
+                        path_id: path_id as u64,
+                        blob: cursor.into_inner(),
+                    }))
+                }
+                _ => Cow::Borrowed(asset),
+            };
+            let text = serde_json::to_string_pretty(&non_finite::Finite(&ExportedObject {
+                path_id,
+                asset: asset.as_ref(),
+            }))
+            .with_context(|| format!("failed to serialize path ID '{}'", path_id))?;
+            fs::write(dir.join(format!("{}.json", path_id)), text)?;
+        }
+        Ok(())
+    }
+
+    /// Read objects written by [AssetFile::export_json]. Objects with a path ID in the file are
+    /// replaced and the rest are added. Types the file doesn't have yet are taken from
+    /// [AssetFileType::known].
+    pub fn import_json<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map(|ext| ext == "json")
+                .unwrap_or_default()
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        for path in paths {
+            let text = fs::read_to_string(&path)?;
+            let mut deserializer = serde_json::Deserializer::from_str(&text);
+            let object: ExportedObject<Asset> = non_finite::deserialize(&mut deserializer)
+                .and_then(|object| deserializer.end().map(|_| object))
+                .with_context(|| format!("failed to parse '{}'", path.display()))?;
+            let mut asset = match object.asset {
+                // Parse raw data again in case it belongs to a type the crate knows.
+                Asset::Unparsed(unparsed) => Asset::read_options(
+                    &mut Cursor::new(&unparsed.blob),
+                    Endian::Little,
+                    AssetReadOptions {
+                        size: unparsed.blob.len(),
+                        class_id: self
+                            .get_type(unparsed.type_hash)
+                            .map(|ty| ty.class_id)
+                            .unwrap_or_default(),
+                        type_hash: unparsed.type_hash,
+                        pptr: object.path_id as u64,
+                        version: self.unity_version(),
+                    },
+                )?,
+                asset => asset,
+            };
+            asset.set_path_id(object.path_id);
+            match self.get_asset_by_path_id_mut(object.path_id) {
+                Some(target) => *target = asset,
+                None => {
+                    if self.asset_type(&asset).is_none() {
+                        let ty = match asset.class_id() {
+                            Some(class_id) => AssetFileType::known_class(class_id),
+                            None => AssetFileType::known(asset.type_hash()),
+                        };
+                        let ty = ty.ok_or_else(|| {
+                            anyhow!("the type of '{}' is not in the file", path.display())
+                        })?;
+                        self.add_type(ty);
+                    }
+                    self.insert_asset(object.path_id, asset)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// serde_json writes NaN and infinities as null, which can't be read back as a float. Exported
+/// objects go through these wrappers, which write non-finite floats as the strings "NaN",
+/// "Infinity" and "-Infinity" and accept them when reading.
+pub(crate) mod non_finite {
+    use std::fmt;
+
+    use serde::de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+    use serde::ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    };
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) struct Finite<'a, T: ?Sized>(pub &'a T);
+
+    impl<T: Serialize + ?Sized> Serialize for Finite<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(FiniteSerializer(serializer))
+        }
+    }
+
+    pub(crate) fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(FiniteDeserializer(deserializer))
+    }
+
+    fn name(value: f64) -> &'static str {
+        if value.is_nan() {
+            "NaN"
+        } else if value > 0. {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+    }
+
+    fn parse(value: &str) -> Option<f64> {
+        match value {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        }
+    }
+
+    struct FiniteSerializer<S>(S);
+
+    macro_rules! forward_serialize {
+        ($($method:ident($ty:ty),)*) => {
+            $(
+                fn $method(self, value: $ty) -> Result<S::Ok, S::Error> {
+                    self.0.$method(value)
+                }
+            )*
+        };
+    }
+
+    impl<S: Serializer> Serializer for FiniteSerializer<S> {
+        type Ok = S::Ok;
+        type Error = S::Error;
+        type SerializeSeq = Compound<S::SerializeSeq>;
+        type SerializeTuple = Compound<S::SerializeTuple>;
+        type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+        type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+        type SerializeMap = Compound<S::SerializeMap>;
+        type SerializeStruct = Compound<S::SerializeStruct>;
+        type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+        forward_serialize! {
+            serialize_bool(bool),
+            serialize_i8(i8),
+            serialize_i16(i16),
+            serialize_i32(i32),
+            serialize_i64(i64),
+            serialize_i128(i128),
+            serialize_u8(u8),
+            serialize_u16(u16),
+            serialize_u32(u32),
+            serialize_u64(u64),
+            serialize_u128(u128),
+            serialize_char(char),
+            serialize_str(&str),
+            serialize_bytes(&[u8]),
+            serialize_unit_struct(&'static str),
+        }
+
+        fn serialize_f32(self, value: f32) -> Result<S::Ok, S::Error> {
+            if value.is_finite() {
+                self.0.serialize_f32(value)
+            } else {
+                self.0.serialize_str(name(value as f64))
+            }
+        }
+
+        fn serialize_f64(self, value: f64) -> Result<S::Ok, S::Error> {
+            if value.is_finite() {
+                self.0.serialize_f64(value)
+            } else {
+                self.0.serialize_str(name(value))
+            }
+        }
+
+        fn serialize_none(self) -> Result<S::Ok, S::Error> {
+            self.0.serialize_none()
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+            self.0.serialize_some(&Finite(value))
+        }
+
+        fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+            self.0.serialize_unit()
+        }
+
+        fn serialize_unit_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+        ) -> Result<S::Ok, S::Error> {
+            self.0.serialize_unit_variant(name, variant_index, variant)
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(
+            self,
+            name: &'static str,
+            value: &T,
+        ) -> Result<S::Ok, S::Error> {
+            self.0.serialize_newtype_struct(name, &Finite(value))
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<S::Ok, S::Error> {
+            self.0
+                .serialize_newtype_variant(name, variant_index, variant, &Finite(value))
+        }
+
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+            self.0.serialize_seq(len).map(Compound)
+        }
+
+        fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+            self.0.serialize_tuple(len).map(Compound)
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            name: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleStruct, S::Error> {
+            self.0.serialize_tuple_struct(name, len).map(Compound)
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleVariant, S::Error> {
+            self.0
+                .serialize_tuple_variant(name, variant_index, variant, len)
+                .map(Compound)
+        }
+
+        fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+            self.0.serialize_map(len).map(Compound)
+        }
+
+        fn serialize_struct(
+            self,
+            name: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeStruct, S::Error> {
+            self.0.serialize_struct(name, len).map(Compound)
+        }
+
+        fn serialize_struct_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeStructVariant, S::Error> {
+            self.0
+                .serialize_struct_variant(name, variant_index, variant, len)
+                .map(Compound)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            self.0.is_human_readable()
+        }
+    }
+
+    struct Compound<C>(C);
+
+    impl<C: SerializeSeq> SerializeSeq for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+            self.0.serialize_element(&Finite(value))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeTuple> SerializeTuple for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+            self.0.serialize_element(&Finite(value))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeTupleStruct> SerializeTupleStruct for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+            self.0.serialize_field(&Finite(value))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeTupleVariant> SerializeTupleVariant for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+            self.0.serialize_field(&Finite(value))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeMap> SerializeMap for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+            self.0.serialize_key(&Finite(key))
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+            self.0.serialize_value(&Finite(value))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeStruct> SerializeStruct for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), C::Error> {
+            self.0.serialize_field(key, &Finite(value))
+        }
+
+        fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+            self.0.skip_field(key)
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    impl<C: SerializeStructVariant> SerializeStructVariant for Compound<C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), C::Error> {
+            self.0.serialize_field(key, &Finite(value))
+        }
+
+        fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+            self.0.skip_field(key)
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> {
+            self.0.end()
+        }
+    }
+
+    struct FiniteDeserializer<D>(D);
+
+    macro_rules! forward_deserialize {
+        ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+            $(
+                fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error> {
+                    self.0.$method($($arg,)* FiniteVisitor(visitor))
+                }
+            )*
+        };
+    }
+
+    impl<'de, D: Deserializer<'de>> Deserializer<'de> for FiniteDeserializer<D> {
+        type Error = D::Error;
+
+        forward_deserialize! {
+            deserialize_any(),
+            deserialize_bool(),
+            deserialize_i8(),
+            deserialize_i16(),
+            deserialize_i32(),
+            deserialize_i64(),
+            deserialize_i128(),
+            deserialize_u8(),
+            deserialize_u16(),
+            deserialize_u32(),
+            deserialize_u64(),
+            deserialize_u128(),
+            deserialize_char(),
+            deserialize_str(),
+            deserialize_string(),
+            deserialize_bytes(),
+            deserialize_byte_buf(),
+            deserialize_option(),
+            deserialize_unit(),
+            deserialize_unit_struct(name: &'static str),
+            deserialize_newtype_struct(name: &'static str),
+            deserialize_seq(),
+            deserialize_tuple(len: usize),
+            deserialize_tuple_struct(name: &'static str, len: usize),
+            deserialize_map(),
+            deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+            deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+            deserialize_identifier(),
+            deserialize_ignored_any(),
+        }
+
+        // Floats may be numbers or one of the names written for non-finite values.
+        fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+            self.0.deserialize_any(FloatVisitor(visitor))
+        }
+
+        fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+            self.0.deserialize_any(FloatVisitor(visitor))
+        }
+
+        fn is_human_readable(&self) -> bool {
+            self.0.is_human_readable()
+        }
+    }
+
+    struct FloatVisitor<V>(V);
+
+    impl<'de, V: Visitor<'de>> Visitor<'de> for FloatVisitor<V> {
+        type Value = V::Value;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            self.0.expecting(formatter)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<V::Value, E> {
+            self.0.visit_i64(value)
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<V::Value, E> {
+            self.0.visit_u64(value)
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<V::Value, E> {
+            self.0.visit_f64(value)
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<V::Value, E> {
+            match parse(value) {
+                Some(value) => self.0.visit_f64(value),
+                None => self.0.visit_str(value),
+            }
+        }
+    }
+
+    struct FiniteVisitor<V>(V);
+
+    macro_rules! forward_visit {
+        ($($method:ident($ty:ty),)*) => {
+            $(
+                fn $method<E: serde::de::Error>(self, value: $ty) -> Result<V::Value, E> {
+                    self.0.$method(value)
+                }
+            )*
+        };
+    }
+
+    impl<'de, V: Visitor<'de>> Visitor<'de> for FiniteVisitor<V> {
+        type Value = V::Value;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            self.0.expecting(formatter)
+        }
+
+        forward_visit! {
+            visit_bool(bool),
+            visit_i8(i8),
+            visit_i16(i16),
+            visit_i32(i32),
+            visit_i64(i64),
+            visit_i128(i128),
+            visit_u8(u8),
+            visit_u16(u16),
+            visit_u32(u32),
+            visit_u64(u64),
+            visit_u128(u128),
+            visit_f32(f32),
+            visit_f64(f64),
+            visit_char(char),
+            visit_str(&str),
+            visit_borrowed_str(&'de str),
+            visit_string(String),
+            visit_bytes(&[u8]),
+            visit_borrowed_bytes(&'de [u8]),
+            visit_byte_buf(Vec<u8>),
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<V::Value, E> {
+            self.0.visit_none()
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<V::Value, E> {
+            self.0.visit_unit()
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+            self.0.visit_some(FiniteDeserializer(deserializer))
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<V::Value, D::Error> {
+            self.0
+                .visit_newtype_struct(FiniteDeserializer(deserializer))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+            self.0.visit_seq(Access(seq))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+            self.0.visit_map(Access(map))
+        }
+
+        fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+            self.0.visit_enum(Access(data))
+        }
+    }
+
+    struct Access<A>(A);
+
+    impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for Access<A> {
+        type Error = A::Error;
+
+        fn next_element_seed<T: DeserializeSeed<'de>>(
+            &mut self,
+            seed: T,
+        ) -> Result<Option<T::Value>, A::Error> {
+            self.0.next_element_seed(Seed(seed))
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            self.0.size_hint()
+        }
+    }
+
+    impl<'de, A: MapAccess<'de>> MapAccess<'de> for Access<A> {
+        type Error = A::Error;
+
+        fn next_key_seed<K: DeserializeSeed<'de>>(
+            &mut self,
+            seed: K,
+        ) -> Result<Option<K::Value>, A::Error> {
+            self.0.next_key_seed(Seed(seed))
+        }
+
+        fn next_value_seed<T: DeserializeSeed<'de>>(
+            &mut self,
+            seed: T,
+        ) -> Result<T::Value, A::Error> {
+            self.0.next_value_seed(Seed(seed))
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            self.0.size_hint()
+        }
+    }
+
+    impl<'de, A: EnumAccess<'de>> EnumAccess<'de> for Access<A> {
+        type Error = A::Error;
+        type Variant = Access<A::Variant>;
+
+        fn variant_seed<T: DeserializeSeed<'de>>(
+            self,
+            seed: T,
+        ) -> Result<(T::Value, Self::Variant), A::Error> {
+            self.0
+                .variant_seed(Seed(seed))
+                .map(|(value, variant)| (value, Access(variant)))
+        }
+    }
+
+    impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for Access<A> {
+        type Error = A::Error;
+
+        fn unit_variant(self) -> Result<(), A::Error> {
+            self.0.unit_variant()
+        }
+
+        fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+            self,
+            seed: T,
+        ) -> Result<T::Value, A::Error> {
+            self.0.newtype_variant_seed(Seed(seed))
+        }
+
+        fn tuple_variant<V: Visitor<'de>>(
+            self,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, A::Error> {
+            self.0.tuple_variant(len, FiniteVisitor(visitor))
+        }
+
+        fn struct_variant<V: Visitor<'de>>(
+            self,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, A::Error> {
+            self.0.struct_variant(fields, FiniteVisitor(visitor))
+        }
+    }
+
+    struct Seed<T>(T);
+
+    impl<'de, T: DeserializeSeed<'de>> DeserializeSeed<'de> for Seed<T> {
+        type Value = T::Value;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T::Value, D::Error> {
+            self.0.deserialize(FiniteDeserializer(deserializer))
+        }
+    }
+}
@@ -1,18 +1,35 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+use crate::texture::{texture_data, ChannelMode, Surface, TextureSwizzle};
+use crate::texture_decode::decode_image;
+use crate::texture_encode::encode_image;
 use crate::{
     Asset, AssetFile, Bundle, BundleFile, RenderDataKey, Sprite, SpriteAtlas, SpriteAtlasData,
-    Texture2D, TextureFormat,
+    Texture2D, TextureFormat, UArray,
 };
 use anyhow::{anyhow, bail, Result};
-use astc_decode::Footprint;
-use image::{DynamicImage, GrayImage, RgbaImage};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use md4::{Digest, Md4};
 
+/// The sprites of a SpriteAtlas along with its textures. Textures are decoded on first use,
+/// so read them through [`SpriteAtlasWrapper::texture`] and [`SpriteAtlasWrapper::texture_ids`].
 pub struct SpriteAtlasWrapper {
-    pub textures: HashMap<i64, DynamicImage>,
+    textures: HashMap<i64, AtlasTexture>,
     render_data: HashMap<RenderDataKey, SpriteAtlasData>,
     sprites: HashMap<String, Sprite>,
+    modified_textures: HashSet<i64>,
+    cache_dir: Option<PathBuf>,
+}
+
+/// An atlas texture, decoded the first time one of its sprites is needed.
+struct AtlasTexture {
+    /// The texture with its image data stored inline. `None` for textures that were
+    /// passed in already decoded.
+    source: Option<(Texture2D, TextureSwizzle)>,
+    image: OnceLock<DynamicImage>,
 }
 
 impl SpriteAtlasWrapper {
@@ -20,6 +37,45 @@ impl SpriteAtlasWrapper {
         textures: HashMap<i64, DynamicImage>,
         atlas: SpriteAtlas,
         sprites: Vec<Sprite>,
+    ) -> Self {
+        let textures = textures
+            .into_iter()
+            .map(|(id, image)| {
+                let texture = AtlasTexture {
+                    source: None,
+                    image: OnceLock::from(image),
+                };
+                (id, texture)
+            })
+            .collect();
+        Self::from_textures(textures, atlas, sprites)
+    }
+
+    /// Build an atlas that decodes its textures on demand. Each texture's image data must be
+    /// stored inline.
+    fn lazy(
+        textures: Vec<(i64, Texture2D)>,
+        swizzle: TextureSwizzle,
+        atlas: SpriteAtlas,
+        sprites: Vec<Sprite>,
+    ) -> Self {
+        let textures = textures
+            .into_iter()
+            .map(|(id, texture)| {
+                let texture = AtlasTexture {
+                    source: Some((texture, swizzle)),
+                    image: OnceLock::new(),
+                };
+                (id, texture)
+            })
+            .collect();
+        Self::from_textures(textures, atlas, sprites)
+    }
+
+    fn from_textures(
+        textures: HashMap<i64, AtlasTexture>,
+        atlas: SpriteAtlas,
+        sprites: Vec<Sprite>,
     ) -> Self {
         // TODO: Validate that everything uses the supported packing flags
         Self {
@@ -29,34 +85,188 @@ impl SpriteAtlasWrapper {
                 .into_iter()
                 .map(|sprite| (sprite.name.0.clone(), sprite))
                 .collect(),
+            modified_textures: HashSet::new(),
+            cache_dir: None,
         }
     }
 
-    pub fn unwrap_sprites(&self) -> HashMap<String, DynamicImage> {
+    /// Store decoded RGBA textures in `dir`, keyed by a hash of the encoded texture, and reuse
+    /// them instead of decoding again. The directory is created if it doesn't exist.
+    pub fn set_cache_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.cache_dir = Some(dir.into());
+    }
+
+    pub fn texture_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.textures.keys().copied()
+    }
+
+    /// The decoded texture with the given path ID, decoding it if no sprite from it has been
+    /// requested yet.
+    pub fn texture(&self, path_id: i64) -> Result<&DynamicImage> {
+        let texture = self
+            .textures
+            .get(&path_id)
+            .ok_or_else(|| anyhow!("atlas does not contain texture {}", path_id))?;
+        if let Some(image) = texture.image.get() {
+            return Ok(image);
+        }
+        let image = texture.decode(self.cache_dir.as_deref())?;
+        // Another thread may have decoded the same texture in the meantime, which is harmless.
+        Ok(texture.image.get_or_init(|| image))
+    }
+
+    /// Decode every texture up front, in parallel when the `parallel` feature is enabled.
+    pub fn decode_all(&self) -> Result<()> {
+        let ids: Vec<i64> = self.texture_ids().collect();
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            ids.par_iter()
+                .try_for_each(|id| self.texture(*id).map(|_| ()))
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            ids.iter().try_for_each(|id| self.texture(*id).map(|_| ()))
+        }
+    }
+
+    pub fn unwrap_sprites(&self) -> Result<HashMap<String, DynamicImage>> {
         self.sprites
             .keys()
-            .filter_map(|key| self.get_sprite(key).map(|sprite| (key.to_owned(), sprite)))
+            .filter_map(|key| {
+                let sprite = self.get_sprite(key).transpose()?;
+                Some(sprite.map(|sprite| (key.to_owned(), sprite)))
+            })
             .collect()
     }
 
-    pub fn get_sprite(&self, name: &str) -> Option<DynamicImage> {
+    /// Crop a sprite out of its atlas texture. Returns `None` if the atlas doesn't have the
+    /// sprite and an error if its texture can't be decoded.
+    pub fn get_sprite(&self, name: &str) -> Result<Option<DynamicImage>> {
+        let Some((texture_id, rect)) = self.sprite_rect(name) else {
+            return Ok(None);
+        };
+        let sprite = self
+            .texture(texture_id)?
+            .crop_imm(rect.0, rect.1, rect.2, rect.3);
+        Ok(Some(sprite.flipv()))
+    }
+
+    /// Overwrite a sprite's area of its atlas texture. The image must be the same size as the
+    /// sprite and is expected the right way up, as returned by [`SpriteAtlasWrapper::get_sprite`].
+    pub fn set_sprite(&mut self, name: &str, image: &DynamicImage) -> Result<()> {
+        let (texture_id, (x, y, width, height)) = self
+            .sprite_rect(name)
+            .ok_or_else(|| anyhow!("atlas does not contain sprite '{}'", name))?;
+        if image.dimensions() != (width, height) {
+            bail!(
+                "sprite '{}' is {}x{} but the image is {}x{}",
+                name,
+                width,
+                height,
+                image.width(),
+                image.height()
+            );
+        }
+        self.texture(texture_id)?;
+        let texture = self
+            .textures
+            .get_mut(&texture_id)
+            .and_then(|texture| texture.image.get_mut())
+            .ok_or_else(|| anyhow!("atlas does not contain texture {}", texture_id))?;
+        let mut updated = texture.to_rgba8();
+        image::imageops::replace(&mut updated, &image.flipv().to_rgba8(), x as i64, y as i64);
+        *texture = match texture {
+            DynamicImage::ImageRgba8(_) => DynamicImage::ImageRgba8(updated),
+            _ => DynamicImage::ImageRgba32F(DynamicImage::ImageRgba8(updated).into_rgba32f()),
+        };
+        self.modified_textures.insert(texture_id);
+        Ok(())
+    }
+
+    /// The texture path ID and pixel rect (x, y, width, height) of a sprite.
+    fn sprite_rect(&self, name: &str) -> Option<(i64, (u32, u32, u32, u32))> {
         let sprite = self.sprites.get(name)?;
         let render_data = self.render_data.get(&sprite.render_data_key)?;
-        let texture = self.textures.get(&render_data.texture.path_id)?;
         let rect = &render_data.texture_rect;
-        Some(
-            texture
-                .crop_imm(
-                    rect.x as u32,
-                    rect.y as u32,
-                    rect.w.ceil() as u32,
-                    rect.h.ceil() as u32,
-                )
-                .flipv(),
-        )
+        Some((
+            render_data.texture.path_id,
+            (
+                rect.x as u32,
+                rect.y as u32,
+                rect.w.ceil() as u32,
+                rect.h.ceil() as u32,
+            ),
+        ))
+    }
+}
+
+impl AtlasTexture {
+    fn decode(&self, cache_dir: Option<&Path>) -> Result<DynamicImage> {
+        let Some((texture, swizzle)) = &self.source else {
+            bail!("atlas texture has no image data")
+        };
+        let Some(cache_dir) = cache_dir else {
+            return texture.decode(&[], *swizzle);
+        };
+        let path = cache_dir.join(format!("{}.rgba", cache_key(texture, *swizzle)));
+        // A missing, unreadable or stale cache entry is decoded again.
+        if let Some(image) = read_cached_image(&path)
+            .filter(|image| image.dimensions() == (texture.width, texture.height))
+        {
+            return Ok(image);
+        }
+        let image = texture.decode(&[], *swizzle)?;
+        if let DynamicImage::ImageRgba8(rgba) = &image {
+            // The cache is only an optimization, so failing to write it isn't an error.
+            let _ = write_cached_image(&path, rgba);
+        }
+        Ok(image)
     }
 }
 
+/// Hash everything that affects the decoded texture.
+fn cache_key(texture: &Texture2D, swizzle: TextureSwizzle) -> String {
+    let mut hasher = Md4::new();
+    hasher.update((texture.texture_format as u32).to_le_bytes());
+    hasher.update(texture.width.to_le_bytes());
+    hasher.update(texture.height.to_le_bytes());
+    hasher.update([swizzle as u8]);
+    hasher.update(&texture.image_data.items);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Cached images are the width and height as little endian u32s followed by RGBA8 pixels.
+fn read_cached_image(path: &Path) -> Option<DynamicImage> {
+    let data = std::fs::read(path).ok()?;
+    let width = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+    let height = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?);
+    // from_raw accepts trailing bytes, which would hide a corrupted header.
+    if data.len() as u64 != 8 + width as u64 * height as u64 * 4 {
+        return None;
+    }
+    RgbaImage::from_raw(width, height, data[8..].to_vec()).map(DynamicImage::ImageRgba8)
+}
+
+fn write_cached_image(path: &Path, image: &RgbaImage) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut data = Vec::with_capacity(8 + image.as_raw().len());
+    data.extend(image.width().to_le_bytes());
+    data.extend(image.height().to_le_bytes());
+    data.extend(image.as_raw());
+    // Write to a temporary file first so readers never see a partial image.
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, data)?;
+    std::fs::rename(temp_path, path)?;
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct AtlasBundle(Bundle);
 
@@ -69,21 +279,35 @@ impl AtlasBundle {
         Bundle::from_slice(raw_bundle).map(Self)
     }
 
-    pub fn extract_data(mut self) -> Result<SpriteAtlasWrapper> {
-        let resource_file = self.0.files.pop().map(|v| v.1);
-        let assets_file = self.0.files.pop().map(|v| v.1);
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<()> {
+        self.0.save(path)
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        self.0.serialize()
+    }
+
+    /// Read the atlas and its sprites. Textures are decoded when a sprite from them is first
+    /// requested, or all at once with [`SpriteAtlasWrapper::decode_all`].
+    pub fn extract_data(&self) -> Result<SpriteAtlasWrapper> {
+        let mut files = self.0.files.values().rev();
+        let resource_file = files.next();
+        let assets_file = files.next();
         if let (Some(BundleFile::Assets(asset_file)), Some(BundleFile::Raw(image_data))) =
             (assets_file, resource_file)
         {
+            let swizzle = TextureSwizzle::from_platform(asset_file.platform());
             let assets = extract_atlas_assets(asset_file)?;
-            let mut textures = HashMap::new();
-            let mut slice_start = 0;
-            for (id, texture) in assets.textures {
-                textures.insert(id as i64, decode(&texture, &image_data[slice_start..])?);
-                slice_start += texture.width as usize * texture.height as usize;
+            let mut textures = vec![];
+            for (id, mut texture) in assets.textures {
+                // Copy streamed data so textures can be decoded later without the bundle.
+                let data = texture.image_data(image_data)?.to_vec();
+                texture.image_data = UArray { items: data };
+                textures.push((id as i64, texture));
             }
-            Ok(SpriteAtlasWrapper::new(
+            Ok(SpriteAtlasWrapper::lazy(
                 textures,
+                swizzle,
                 assets.atlas,
                 assets.sprites,
             ))
@@ -91,6 +315,16 @@ impl AtlasBundle {
             bail!("could not identify asset and texture files in bundle")
         }
     }
+
+    /// Re-encode every texture changed through [`SpriteAtlasWrapper::set_sprite`],
+    /// keeping each texture's format.
+    pub fn replace_textures(&mut self, atlas: &SpriteAtlasWrapper) -> Result<()> {
+        for texture_id in &atlas.modified_textures {
+            self.0
+                .replace_texture(*texture_id, atlas.texture(*texture_id)?, None)?;
+        }
+        Ok(())
+    }
 }
 
 struct AtlasAssets {
@@ -99,15 +333,15 @@ struct AtlasAssets {
     atlas: SpriteAtlas,
 }
 
-fn extract_atlas_assets(asset_file: AssetFile) -> Result<AtlasAssets> {
+fn extract_atlas_assets(asset_file: &AssetFile) -> Result<AtlasAssets> {
     let mut sprites = vec![];
     let mut textures = vec![];
     let mut atlas = None;
-    for asset in asset_file.assets {
+    for asset in &asset_file.assets {
         match asset {
-            Asset::Texture2D(asset, id) => textures.push((id, asset)),
-            Asset::SpriteAtlas(asset) => atlas = Some(asset),
-            Asset::Sprite(asset) => sprites.push(asset),
+            Asset::Texture2D(asset, id) => textures.push((*id, asset.clone())),
+            Asset::SpriteAtlas(asset) => atlas = Some(asset.clone()),
+            Asset::Sprite(asset) => sprites.push(asset.clone()),
             _ => {}
         }
     }
@@ -122,50 +356,263 @@ fn extract_atlas_assets(asset_file: AssetFile) -> Result<AtlasAssets> {
     }
 }
 
-fn decode(texture: &Texture2D, image_data: &[u8]) -> Result<DynamicImage> {
-    let width = texture.width as usize;
-    let height = texture.height as usize;
-    let size = width * height * 4;
-
-    let (block_width, block_height, bytes_per_pixel) = match texture.texture_format {
-        TextureFormat::ASTC_RGB_4x4 => (4, 4, 16),
-        TextureFormat::ASTC_RGB_5x5 => (5, 5, 16),
-        TextureFormat::R8 => (1, 1, 1),
-        _ => bail!("unsupported texture format '{:?}'", texture.texture_format),
-    };
-
-    let block_height_mip0 =
-        tegra_swizzle::block_height_mip0(tegra_swizzle::div_round_up(height, block_height));
-
-    let input = tegra_swizzle::swizzle::deswizzle_block_linear(
-        tegra_swizzle::div_round_up(width, block_width),
-        tegra_swizzle::div_round_up(height, block_height),
-        1,
-        image_data,
-        block_height_mip0,
-        bytes_per_pixel,
-    )?;
-
-    match texture.texture_format {
-        TextureFormat::ASTC_RGB_4x4 | TextureFormat::ASTC_RGB_5x5 => {
-            let mut output = vec![[0u8; 4]; size];
-            astc_decode::astc_decode(
-                input.as_slice(),
-                texture.width,
-                texture.height,
-                Footprint::new(block_width as u32, block_height as u32),
-                |x, y, color| {
-                    output[x as usize + y as usize * width] = color;
-                },
-            )?;
-            RgbaImage::from_raw(width as u32, height as u32, output.concat())
-                .ok_or_else(|| anyhow!("failed to build image"))
-                .map(DynamicImage::ImageRgba8)
-        }
-        // This technically isn't correct, but the alternative is storing in a *much* larger texture.
-        TextureFormat::R8 => GrayImage::from_raw(width as u32, height as u32, input)
-            .ok_or_else(|| anyhow!("failed to build image"))
-            .map(DynamicImage::ImageLuma8),
-        _ => bail!("unsupported texture format '{:?}'", texture.texture_format),
+impl Texture2D {
+    /// The encoded data for this texture. Textures that aren't stored inline are read from
+    /// `resource` using the texture's stream data.
+    pub fn image_data<'a>(&'a self, resource: &'a [u8]) -> Result<&'a [u8]> {
+        texture_data(&self.name.0, &self.image_data, &self.stream_data, resource)
+    }
+
+    /// Decode the top mip level. `resource` is the contents of the texture's resource file
+    /// and is ignored when the image data is stored inline.
+    pub fn decode(&self, resource: &[u8], swizzle: TextureSwizzle) -> Result<DynamicImage> {
+        // Crunched textures are a compressed stream rather than a surface, so they're never swizzled.
+        if self.texture_format.block_info().is_none() {
+            return decode_image(
+                self.texture_format,
+                self.image_data(resource)?,
+                self.width as usize,
+                self.height as usize,
+            );
+        }
+        let surface = Surface {
+            mip_count: 1,
+            ..self.surface(resource, swizzle)?
+        };
+        surface.decode(Some(0)).map(|mut mips| mips.remove(0))
+    }
+
+    /// Decode the top mip level with the given channel mode, such as reconstructing Z for a
+    /// normal map.
+    pub fn decode_channels(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        mode: ChannelMode,
+    ) -> Result<DynamicImage> {
+        let image = self.decode(resource, swizzle)?;
+        Ok(mode.apply(self.texture_format, image))
+    }
+
+    /// Decode a single mip level. Level 0 is the full size image.
+    pub fn decode_mip(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        level: u32,
+    ) -> Result<DynamicImage> {
+        if level >= self.mip_count.max(1) {
+            bail!(
+                "texture '{}' has {} mip levels but level {} was requested",
+                self.name.0,
+                self.mip_count,
+                level
+            );
+        }
+        if level == 0 {
+            return self.decode(resource, swizzle);
+        }
+        self.decode_mips(resource, swizzle, Some(level))
+            .map(|mut mips| mips.remove(0))
+    }
+
+    /// Decode every mip level, from largest to smallest.
+    pub fn decode_all_mips(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+    ) -> Result<Vec<DynamicImage>> {
+        self.decode_mips(resource, swizzle, None)
+    }
+
+    fn decode_mips(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        only_level: Option<u32>,
+    ) -> Result<Vec<DynamicImage>> {
+        if self.texture_format.block_info().is_none() {
+            // Crunched data only stores the top level.
+            return Ok(vec![self.decode(resource, swizzle)?]);
+        }
+        let surface = self.surface(resource, swizzle)?;
+        let surface_size = surface.swizzled_size()?;
+        if surface_size != self.complete_image_size as usize {
+            bail!(
+                "texture '{}' should have {} bytes of image data for {} mip levels but complete_image_size is {}",
+                self.name.0,
+                surface_size,
+                surface.mip_count,
+                self.complete_image_size
+            );
+        }
+        surface.decode(only_level.map(|level| level as usize))
+    }
+
+    pub(crate) fn surface<'a>(
+        &'a self,
+        resource: &'a [u8],
+        swizzle: TextureSwizzle,
+    ) -> Result<Surface<'a>> {
+        Ok(Surface {
+            format: self.texture_format,
+            swizzle,
+            data: self.image_data(resource)?,
+            width: self.width as usize,
+            height: self.height as usize,
+            depth: 1,
+            layer_count: 1,
+            mip_count: self.mip_count.max(1) as usize,
+        })
+    }
+
+    /// Encode an image into `format`, optionally with a full mip chain, and update the texture's
+    /// dimensions, format and sizes to match. Returns the image data laid out for `swizzle`,
+    /// which belongs either in `image_data` or in the texture's resource file. ASTC 10x10 and
+    /// 12x12 can't be encoded because the ISPC encoder stops at 8x8 blocks.
+    pub fn encode(
+        &mut self,
+        image: &DynamicImage,
+        format: TextureFormat,
+        swizzle: TextureSwizzle,
+        mipmaps: bool,
+    ) -> Result<Vec<u8>> {
+        if format.block_info().is_none() {
+            bail!("encoding to texture format '{:?}' is not supported", format)
+        };
+        let (width, height) = image.dimensions();
+        let mip_count = if mipmaps {
+            u32::BITS - width.max(height).leading_zeros()
+        } else {
+            1
+        };
+
+        let mut mips = vec![];
+        for level in 0..mip_count {
+            if level == 0 {
+                mips.extend(encode_image(format, image)?);
+            } else {
+                let mip_width = (width >> level).max(1);
+                let mip_height = (height >> level).max(1);
+                let mip = image.resize_exact(mip_width, mip_height, FilterType::Triangle);
+                mips.extend(encode_image(format, &mip)?);
+            }
+        }
+        let data = Surface {
+            format,
+            swizzle,
+            data: &mips,
+            width: width as usize,
+            height: height as usize,
+            depth: 1,
+            layer_count: 1,
+            mip_count: mip_count as usize,
+        }
+        .swizzle()?;
+
+        self.width = width;
+        self.height = height;
+        self.texture_format = format;
+        self.mip_count = mip_count;
+        self.complete_image_size = data.len() as u32;
+        Ok(data)
+    }
+}
+
+impl Bundle {
+    /// Decode the Texture2D with the given path ID from this bundle's assets file,
+    /// reading streamed data from the matching resource file in the bundle.
+    pub fn decode_texture(&self, path_id: i64) -> Result<DynamicImage> {
+        let (texture, swizzle) = self.get_texture_2d(path_id)?;
+        texture.decode(self.stream_resource(&texture.stream_data)?, swizzle)
+    }
+
+    pub(crate) fn get_texture_2d(&self, path_id: i64) -> Result<(&Texture2D, TextureSwizzle)> {
+        let (asset, swizzle) = self.get_texture_asset(path_id)?;
+        let Asset::Texture2D(texture, _) = asset else {
+            bail!("asset with path ID {} is not a Texture2D", path_id)
+        };
+        Ok((texture, swizzle))
+    }
+
+    /// Replace the Texture2D with the given path ID. The texture keeps its current format unless
+    /// `format` is given, and gets a full mip chain if it had mipmaps before. Data is swizzled
+    /// for the asset file's platform. Streamed textures have their data rewritten in the
+    /// resource file, moving any data stored after it.
+    pub fn replace_texture(
+        &mut self,
+        path_id: i64,
+        image: &DynamicImage,
+        format: Option<TextureFormat>,
+    ) -> Result<()> {
+        self.inject_texture(path_id, |texture, swizzle| {
+            let format = format.unwrap_or(texture.texture_format);
+            texture.encode(image, format, swizzle, texture.mip_count > 1)
+        })
+    }
+
+    /// Update the Texture2D with the given path ID and store the data `update` returns,
+    /// which should be laid out for the asset file's platform.
+    pub(crate) fn inject_texture(
+        &mut self,
+        path_id: i64,
+        update: impl FnOnce(&mut Texture2D, TextureSwizzle) -> Result<Vec<u8>>,
+    ) -> Result<()> {
+        let (texture, swizzle) = self
+            .files
+            .values_mut()
+            .find_map(|file| match file {
+                BundleFile::Assets(asset_file) => {
+                    let swizzle = TextureSwizzle::from_platform(asset_file.platform());
+                    let asset = asset_file.get_asset_by_path_id_mut(path_id)?;
+                    Some((asset, swizzle))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("bundle does not contain an asset with path ID {}", path_id))?;
+        let Asset::Texture2D(texture, _) = texture else {
+            bail!("asset with path ID {} is not a Texture2D", path_id)
+        };
+        let data = update(texture, swizzle)?;
+        if texture.stream_data.path.0.is_empty() {
+            texture.image_data = UArray { items: data };
+            return Ok(());
+        }
+
+        let path = texture.stream_data.path.0.clone();
+        let offset = texture.stream_data.offset;
+        let old_size = texture.stream_data.size as u64;
+        texture.stream_data.size = data.len() as u32;
+        // Pad so data after this texture keeps its alignment.
+        let padded_size = old_size as i64 + (data.len() as i64 - old_size as i64 + 15) / 16 * 16;
+        let delta = padded_size - old_size as i64;
+
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        let Some(BundleFile::Raw(resource)) = self.files.get_mut(file_name) else {
+            bail!("bundle does not contain resource file '{}'", file_name)
+        };
+        let start = offset as usize;
+        let end = start + old_size as usize;
+        if end > resource.len() {
+            bail!("stream data for texture is outside the resource file");
+        }
+        let mut replacement = data;
+        replacement.resize(padded_size as usize, 0);
+        resource.splice(start..end, replacement);
+
+        for file in self.files.values_mut() {
+            let BundleFile::Assets(asset_file) = file else {
+                continue;
+            };
+            for asset in &mut asset_file.assets {
+                let Some(stream_data) = asset.stream_data_mut() else {
+                    continue;
+                };
+                if stream_data.path.0 == path && stream_data.offset > offset {
+                    stream_data.offset = (stream_data.offset as i64 + delta) as u64;
+                }
+            }
+        }
+        Ok(())
     }
 }
@@ -9,7 +9,11 @@ use indexmap::IndexMap;
 use itertools::Itertools;
 use lzma_rs::decompress::UnpackedSize;
 
-use crate::{Asset, AssetFile, MessageMap, MonoBehavior, TerrainData, TextAsset};
+use crate::roundtrip::{diff_bytes, with_file};
+use crate::{
+    Asset, AssetFile, MessageMap, MonoBehavior, RoundtripMismatch, StreamingInfo, TerrainData,
+    TextAsset,
+};
 
 #[cfg(feature = "msbt_script")]
 use crate::{
@@ -24,12 +28,16 @@ pub enum CompressionType {
     Uncompressed,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Bundle {
     pub(crate) files: IndexMap<String, BundleFile>,
 }
 
 impl Bundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     pub fn load<T: AsRef<Path>>(path: T) -> Result<Self> {
         Self::from_slice(&std::fs::read(path)?)
     }
@@ -47,6 +55,76 @@ impl Bundle {
     }
 
     pub fn from_slice(raw_bundle: &[u8]) -> Result<Self> {
+        let (nodes, blob) = Self::read_nodes(raw_bundle)?;
+        let mut files = IndexMap::new();
+        for node in nodes {
+            let start = node.offset as usize;
+            let end = (node.offset + node.size) as usize;
+            // FAILSAFE: Some nodes appear to be of size 0, so we skip them (users manually toying with resS?)
+            if end - start == 0 {
+                continue;
+            }
+            if end > blob.len() || start >= blob.len() {
+                bail!("corrupted file offset/size for node '{}'", node.path);
+            }
+            files.insert(
+                node.path.to_string(),
+                match node.file_type {
+                    BundleFileType::Raw => BundleFile::Raw(blob[start..end].to_vec()),
+                    BundleFileType::Assets => {
+                        let mut cursor = Cursor::new(&blob[start..end]);
+                        BundleFile::Assets(AssetFile::read_le(&mut cursor)?)
+                    }
+                },
+            );
+        }
+        Ok(Self { files })
+    }
+
+    /// Read a serialized bundle, write it back and compare every file against the original.
+    pub fn roundtrip_check(raw_bundle: &[u8]) -> Result<Option<RoundtripMismatch>> {
+        let (nodes, blob) = Self::read_nodes(raw_bundle)?;
+        let serialized = Self::from_slice(raw_bundle)?.serialize()?;
+        let (new_nodes, new_blob) = Self::read_nodes(&serialized)?;
+        for node in nodes.iter().filter(|node| node.size != 0) {
+            let path = node.path.to_string();
+            let data = blob
+                .get(node.offset as usize..(node.offset + node.size) as usize)
+                .ok_or_else(|| anyhow!("corrupted file offset/size for node '{}'", path))?;
+            if let BundleFileType::Assets = node.file_type {
+                if let Some(mismatch) = AssetFile::roundtrip_check_bytes(data)? {
+                    return Ok(Some(with_file(mismatch, &path)));
+                }
+            }
+            let new_data = new_nodes
+                .iter()
+                .find(|new_node| new_node.path == node.path)
+                .and_then(|new_node| {
+                    new_blob.get(new_node.offset as usize..(new_node.offset + new_node.size) as usize)
+                });
+            let Some(new_data) = new_data else {
+                return Ok(Some(RoundtripMismatch {
+                    file: Some(path),
+                    path_id: None,
+                    field: String::from("node"),
+                    expected: String::from("present"),
+                    actual: String::from("missing"),
+                }));
+            };
+            if let Some((offset, expected, actual)) = diff_bytes(data, new_data) {
+                return Ok(Some(RoundtripMismatch {
+                    file: Some(path),
+                    path_id: None,
+                    field: format!("byte 0x{:X}", offset),
+                    expected,
+                    actual,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_nodes(raw_bundle: &[u8]) -> Result<(Vec<Node>, Vec<u8>)> {
         let mut cursor = Cursor::new(raw_bundle);
         let meta_data = Self::read_header_and_meta_data(&mut cursor)
             .context("Failed to read bundle meta data")?;
@@ -84,30 +162,7 @@ impl Bundle {
                 _ => bail!("unsupported compression type '{}'", block.flags & 0x3F),
             };
         }
-
-        let mut files = IndexMap::new();
-        for node in meta_data.nodes {
-            let start = node.offset as usize;
-            let end = (node.offset + node.size) as usize;
-            // FAILSAFE: Some nodes appear to be of size 0, so we skip them (users manually toying with resS?)
-            if end - start == 0 {
-                continue;
-            }
-            if end > blob.len() || start >= blob.len() {
-                bail!("corrupted file offset/size for node '{}'", node.path);
-            }
-            files.insert(
-                node.path.to_string(),
-                match node.file_type {
-                    BundleFileType::Raw => BundleFile::Raw(blob[start..end].to_vec()),
-                    BundleFileType::Assets => {
-                        let mut cursor = Cursor::new(&blob[start..end]);
-                        BundleFile::Assets(AssetFile::read_le(&mut cursor)?)
-                    }
-                },
-            );
-        }
-        Ok(Self { files })
+        Ok((meta_data.nodes, blob))
     }
 
     fn read_header_and_meta_data<T>(reader: &mut T) -> Result<MetaData>
@@ -249,6 +304,10 @@ impl Bundle {
         self.files.get_mut(path)
     }
 
+    pub fn insert(&mut self, path: String, file: BundleFile) -> Option<BundleFile> {
+        self.files.insert(path, file)
+    }
+
     pub fn rename(&mut self, original_file_name: &str, new_file_name: String) -> Result<()> {
         if let Some(contents) = self.files.shift_remove(original_file_name) {
             self.files.insert(new_file_name, contents);
@@ -269,6 +328,20 @@ impl Bundle {
     pub fn files(&self) -> impl Iterator<Item = (&String, &BundleFile)> {
         self.files.iter()
     }
+
+    /// The contents of the resource file a stream path points to.
+    pub(crate) fn stream_resource(&self, stream_data: &StreamingInfo) -> Result<&[u8]> {
+        // Stream paths look like "archive:/CAB-.../CAB-....resS".
+        let path = &stream_data.path.0;
+        if path.is_empty() {
+            return Ok(&[]);
+        }
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        match self.get(file_name) {
+            Some(BundleFile::Raw(resource)) => Ok(resource),
+            _ => bail!("bundle does not contain resource file '{}'", file_name),
+        }
+    }
 }
 
 #[binrw(assert(format_version = 7), assert(magic = "UnityFS"))]
@@ -0,0 +1,258 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::{AssetFileType, AssetFileTypeTree, TypeTreeNodeInfo, MONO_BEHAVIOR_CLASS_ID};
+
+const MONO_BEHAVIOR_HEADER: [&str; 4] = ["m_GameObject", "m_Enabled", "m_Script", "m_Name"];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static",
+    "struct", "super", "trait", "true", "try", "typeof", "unsafe", "use", "virtual", "where",
+    "while", "yield",
+];
+
+impl AssetFileType {
+    /// Generate `#[binrw]` structs for this type, named `name`.
+    /// For MonoBehaviours, only the script's fields are generated so the result can be used
+    /// as the data of a [crate::MonoBehavior].
+    pub fn to_rust_source(&self, name: &str) -> Result<String> {
+        let root = self
+            .type_tree
+            .to_nodes()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("type {} has no type tree", self.class_id))?;
+        let has_header = root
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .take(MONO_BEHAVIOR_HEADER.len())
+            .eq(MONO_BEHAVIOR_HEADER);
+        if self.class_id == MONO_BEHAVIOR_CLASS_ID && has_header {
+            let data = TypeTreeNodeInfo {
+                children: root.children[MONO_BEHAVIOR_HEADER.len()..].to_vec(),
+                ..root
+            };
+            let mut generator = Generator::default();
+            generator.add_struct(name, &data);
+            Ok(format!(
+                "// Read as MonoBehavior<{}>.\n{}",
+                name,
+                generator.finish()
+            ))
+        } else {
+            let mut generator = Generator::default();
+            generator.add_struct(name, &root);
+            Ok(generator.finish())
+        }
+    }
+}
+
+impl AssetFileTypeTree {
+    /// Generate `#[binrw]` structs for this type tree, named `name`.
+    pub fn to_rust_source(&self, name: &str) -> Result<String> {
+        let root = self
+            .to_nodes()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("type tree is empty"))?;
+        let mut generator = Generator::default();
+        generator.add_struct(name, &root);
+        Ok(generator.finish())
+    }
+}
+
+#[derive(Default)]
+struct Generator {
+    // Struct name to (source, layout signature), kept in the order they were first seen.
+    structs: IndexMap<String, (String, String)>,
+}
+
+impl Generator {
+    fn finish(self) -> String {
+        self.structs
+            .into_values()
+            .map(|(source, _)| source)
+            .join("\n")
+    }
+
+    fn add_struct(&mut self, name: &str, node: &TypeTreeNodeInfo) -> String {
+        let signature = signature(node);
+        let mut struct_name = name.to_string();
+        let mut suffix = 2;
+        while let Some((_, existing)) = self.structs.get(&struct_name) {
+            if *existing == signature {
+                return struct_name;
+            }
+            struct_name = format!("{}{}", name, suffix);
+            suffix += 1;
+        }
+        // Reserve the name before recursing so self-referencing types terminate.
+        self.structs
+            .insert(struct_name.clone(), (String::new(), signature));
+
+        let mut source = String::new();
+        writeln!(source, "#[binrw]").unwrap();
+        writeln!(source, "#[derive(Debug, Clone)]").unwrap();
+        writeln!(source, "pub struct {} {{", struct_name).unwrap();
+        let mut used_names: Vec<String> = vec![];
+        for child in &node.children {
+            let rust_type = self.rust_type(child);
+            let mut field_name = field_name(&child.name);
+            if used_names.contains(&field_name) {
+                field_name = format!("{}_{}", field_name, used_names.len());
+            }
+            used_names.push(field_name.clone());
+            if needs_align_after(child, &rust_type) {
+                writeln!(source, "    #[brw(align_after = 4)]").unwrap();
+            }
+            writeln!(source, "    pub {}: {},", field_name, rust_type).unwrap();
+        }
+        writeln!(source, "}}").unwrap();
+        self.structs[&struct_name].0 = source;
+        struct_name
+    }
+
+    fn rust_type(&mut self, node: &TypeTreeNodeInfo) -> String {
+        if let Some(primitive) = primitive_type(&node.type_name) {
+            return primitive.to_string();
+        }
+        match node.type_name.as_str() {
+            "string" => return String::from("UString"),
+            "TypelessData" => return String::from("UArray<u8>"),
+            "Hash128" | "GUID" if node.byte_size == 16 => return String::from("i128"),
+            "Vector2f" | "float2" => return String::from("Vector2f"),
+            "Vector3f" | "float3" => return String::from("Vector3f"),
+            "Vector4f" | "float4" => return String::from("Vector4f"),
+            "Quaternionf" => return String::from("Quaternionf"),
+            "Matrix4x4f" => return String::from("Matrix4x4f"),
+            "ColorRGBA" if node.byte_size == 16 => return String::from("ColorRGBA"),
+            "AABB" => return String::from("AABB"),
+            "Rectf" => return String::from("RectF"),
+            "StreamingInfo" => return String::from("StreamingInfo"),
+            "GLTextureSettings" => return String::from("GlTextureSettings"),
+            "pair" if node.children.len() == 2 => {
+                return format!(
+                    "({}, {})",
+                    self.rust_type(&node.children[0]),
+                    self.rust_type(&node.children[1])
+                )
+            }
+            name if name.starts_with("PPtr<") => return String::from("PPtr"),
+            _ => {}
+        }
+        if let Some(array) = node.inner_array().or(node.is_array().then_some(node)) {
+            return match array.children.get(1) {
+                Some(element) => format!("UArray<{}>", self.rust_type(element)),
+                None => String::from("UArray<u8>"),
+            };
+        }
+        if node.children.is_empty() {
+            // An unknown primitive. Fall back to raw bytes of the right size.
+            return match node.byte_size {
+                1 => String::from("u8"),
+                2 => String::from("u16"),
+                4 => String::from("u32"),
+                8 => String::from("u64"),
+                size => format!("[u8; {}]", size.max(0)),
+            };
+        }
+        self.add_struct(&type_name(&node.type_name), node)
+    }
+}
+
+fn signature(node: &TypeTreeNodeInfo) -> String {
+    let children = node
+        .children
+        .iter()
+        .map(|child| format!("{}:{}", child.name, signature(child)))
+        .join(",");
+    format!("{}({})", node.type_name, children)
+}
+
+fn primitive_type(type_name: &str) -> Option<&'static str> {
+    Some(match type_name {
+        "bool" | "UInt8" | "char" => "u8",
+        "SInt8" => "i8",
+        "SInt16" | "short" => "i16",
+        "UInt16" | "unsigned short" => "u16",
+        "SInt32" | "int" => "i32",
+        "UInt32" | "unsigned int" | "Type*" => "u32",
+        "SInt64" | "long long" => "i64",
+        "UInt64" | "unsigned long long" | "FileSize" => "u64",
+        "float" => "f32",
+        "double" => "f64",
+        _ => return None,
+    })
+}
+
+// UString aligns on its own, everything else needs the attribute.
+fn needs_align_after(node: &TypeTreeNodeInfo, rust_type: &str) -> bool {
+    let aligned = node.is_aligned()
+        || node
+            .inner_array()
+            .map(|a| a.is_aligned())
+            .unwrap_or_default();
+    aligned && rust_type != "UString"
+}
+
+fn type_name(unity_name: &str) -> String {
+    let name: String = unity_name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("Type{}", name)
+    } else {
+        name
+    }
+}
+
+/// Convert a Unity field name (ex. "m_LocalAABB", "image data", "m_MeshMetrics[0]") to snake case.
+fn field_name(unity_name: &str) -> String {
+    let name = unity_name.strip_prefix("m_").unwrap_or(unity_name);
+    let chars = name.chars().collect_vec();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && i > 0 {
+                let prev = chars[i - 1];
+                let next_is_lower = chars
+                    .get(i + 1)
+                    .map(|n| n.is_ascii_lowercase())
+                    .unwrap_or_default();
+                if prev.is_ascii_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_ascii_uppercase() && next_is_lower)
+                {
+                    out.push('_');
+                }
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('_') && !out.is_empty() {
+            out.push('_');
+        }
+    }
+    let out = out.trim_end_matches('_').to_string();
+    if out == "type" {
+        String::from("ty")
+    } else if KEYWORDS.contains(&out.as_str()) {
+        format!("m_{}", out)
+    } else if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{}", out)
+    } else {
+        out
+    }
+}
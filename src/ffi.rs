@@ -2,10 +2,10 @@ use std::cell::RefCell;
 use std::ffi::{CStr, CString};
 
 use anyhow::Result;
-use image::{DynamicImage, GenericImageView, RgbaImage};
+use image::DynamicImage;
 use indexmap::IndexMap;
 
-use crate::{AtlasBundle, MessageBundle, SpriteAtlasWrapper, TextBundle};
+use crate::{AtlasBundle, MessageBundle, PaletteSprite, SpriteAtlasWrapper, TextBundle};
 
 thread_local!(static ERROR_MESSAGE: RefCell<Option<String>> = RefCell::new(None));
 
@@ -220,21 +220,10 @@ pub unsafe extern "C" fn sprite_atlas_get_unit_sprite(
 ) -> FfiImage {
     let palette_key = CStr::from_ptr(palette_key).to_string_lossy();
     let index_key = CStr::from_ptr(index_key).to_string_lossy();
-    let palette = palette.get_sprite(&palette_key);
-    let index = index.get_sprite(&index_key);
-    match (palette, index) {
-        (Some(palette), Some(index)) => Some(DynamicImage::ImageRgba8(RgbaImage::from_fn(
-            index.width(),
-            index.height(),
-            |x, y| {
-                palette
-                    .get_pixel(index.get_pixel(x, y).0[0] as u32 * 2, 0)
-                    .to_owned()
-            },
-        )))
-        .into(),
-        _ => None.into(),
-    }
+    PaletteSprite::from_atlases(index, &index_key, palette, &palette_key)
+        .and_then(|sprite| sprite.render(0))
+        .map(|image| Some(DynamicImage::ImageRgba8(image)))
+        .into()
 }
 
 #[no_mangle]
@@ -310,6 +299,16 @@ impl From<Option<DynamicImage>> for FfiImage {
     }
 }
 
+// Errors return an empty image, like a missing sprite, and set the error message.
+impl From<Result<Option<DynamicImage>>> for FfiImage {
+    fn from(value: Result<Option<DynamicImage>>) -> Self {
+        match FfiResult::from(value) {
+            FfiResult::Ok(image) => image.into(),
+            FfiResult::Err => None.into(),
+        }
+    }
+}
+
 #[repr(u32)]
 pub enum ImageFormat {
     Rgba8 = 0,
@@ -0,0 +1,421 @@
+use crate::{
+    AssetFileType, AssetFileTypeTree, TypeTreeNodeInfo as Node, ANIMATOR_HASH,
+    ANIMATOR_OVERRIDE_CONTROLLER_HASH, ASSET_BUNDLE_HASH, CUBEMAP_HASH, EMPTY_MONO_BEHAVIOR_HASH,
+    GAME_OBJECT_HASH, MATERIAL_HASH, MESH_FILTER_HASH, MONO_SCRIPT_HASH, TEXTURE_2D_ARRAY_HASH,
+    TEXTURE_2D_HASH, TEXTURE_3D_HASH, TEXT_ASSET_HASH, TRANSFORM_HASH,
+};
+
+type KnownType = (u32, i128, fn() -> Node);
+
+// Trees mirror Unity 2020.3 and hash to the constants used by the game's files. MonoBehaviour is
+// hashed by its editor layout instead, see AssetFileType::from_script. Cubemap, Texture3D and
+// Texture2DArray weren't in the files we checked, so their constants are only computed from these
+// trees. Files are matched by class ID, so a game using another revision still finds its type.
+const KNOWN_TYPES: &[KnownType] = &[
+    (1, GAME_OBJECT_HASH, game_object),
+    (4, TRANSFORM_HASH, transform),
+    (21, MATERIAL_HASH, material),
+    (28, TEXTURE_2D_HASH, texture_2d),
+    (33, MESH_FILTER_HASH, mesh_filter),
+    (49, TEXT_ASSET_HASH, text_asset),
+    (89, CUBEMAP_HASH, cubemap),
+    (95, ANIMATOR_HASH, animator),
+    (114, EMPTY_MONO_BEHAVIOR_HASH, mono_behavior),
+    (115, MONO_SCRIPT_HASH, mono_script),
+    (117, TEXTURE_3D_HASH, texture_3d),
+    (142, ASSET_BUNDLE_HASH, asset_bundle),
+    (187, TEXTURE_2D_ARRAY_HASH, texture_2d_array),
+    (
+        221,
+        ANIMATOR_OVERRIDE_CONTROLLER_HASH,
+        animator_override_controller,
+    ),
+];
+
+impl AssetFileType {
+    /// Look up a bundled type by hash. See [AssetFileType::known_types] for what's available.
+    pub fn known(type_hash: i128) -> Option<Self> {
+        KNOWN_TYPES
+            .iter()
+            .find(|(_, hash, _)| *hash == type_hash)
+            .map(|(class_id, hash, tree)| known_type(*class_id, *hash, tree))
+    }
+
+    /// Look up a bundled type by class ID.
+    pub fn known_class(class_id: u32) -> Option<Self> {
+        KNOWN_TYPES
+            .iter()
+            .find(|(id, _, _)| *id == class_id)
+            .map(|(class_id, hash, tree)| known_type(*class_id, *hash, tree))
+    }
+
+    /// Every bundled type: GameObject, Transform, Material, Texture2D, MeshFilter, TextAsset,
+    /// Cubemap, Animator, MonoBehaviour (no script fields), MonoScript, Texture3D, AssetBundle,
+    /// Texture2DArray and AnimatorOverrideController.
+    pub fn known_types() -> Vec<Self> {
+        KNOWN_TYPES
+            .iter()
+            .map(|(class_id, hash, tree)| known_type(*class_id, *hash, tree))
+            .collect()
+    }
+}
+
+fn known_type(class_id: u32, type_hash: i128, tree: &fn() -> Node) -> AssetFileType {
+    AssetFileType {
+        type_tree: AssetFileTypeTree::from_nodes(&[tree()]),
+        ..AssetFileType::new(class_id, type_hash)
+    }
+}
+
+fn base(type_name: &str, children: Vec<Node>) -> Node {
+    Node::structure(type_name, "Base", children)
+}
+
+fn int(name: &str) -> Node {
+    Node::primitive("int", name)
+}
+
+fn uint(name: &str) -> Node {
+    Node::primitive("unsigned int", name)
+}
+
+fn float(name: &str) -> Node {
+    Node::primitive("float", name)
+}
+
+fn boolean(name: &str) -> Node {
+    Node::primitive("bool", name)
+}
+
+fn vector2f(name: &str) -> Node {
+    Node::structure("Vector2f", name, vec![float("x"), float("y")])
+}
+
+fn vector3f(name: &str) -> Node {
+    Node::structure("Vector3f", name, vec![float("x"), float("y"), float("z")])
+}
+
+fn quaternionf(name: &str) -> Node {
+    Node::structure(
+        "Quaternionf",
+        name,
+        vec![float("x"), float("y"), float("z"), float("w")],
+    )
+}
+
+fn color_rgba(name: &str) -> Node {
+    Node::structure(
+        "ColorRGBA",
+        name,
+        vec![float("r"), float("g"), float("b"), float("a")],
+    )
+}
+
+fn asset_info(name: &str) -> Node {
+    Node::structure(
+        "AssetInfo",
+        name,
+        vec![
+            int("preloadIndex"),
+            int("preloadSize"),
+            Node::pptr("Object", "asset"),
+        ],
+    )
+}
+
+fn game_object() -> Node {
+    base(
+        "GameObject",
+        vec![
+            Node::vector(
+                "m_Component",
+                Node::structure(
+                    "ComponentPair",
+                    "data",
+                    vec![Node::pptr("Component", "component")],
+                ),
+            ),
+            uint("m_Layer"),
+            Node::string("m_Name"),
+            Node::primitive("UInt16", "m_Tag"),
+            boolean("m_IsActive"),
+        ],
+    )
+    .with_version(6)
+}
+
+fn transform() -> Node {
+    base(
+        "Transform",
+        vec![
+            Node::pptr("GameObject", "m_GameObject"),
+            quaternionf("m_LocalRotation"),
+            vector3f("m_LocalPosition"),
+            vector3f("m_LocalScale"),
+            Node::vector("m_Children", Node::pptr("Transform", "data")),
+            Node::pptr("Transform", "m_Father"),
+        ],
+    )
+}
+
+fn material() -> Node {
+    let tex_env = Node::structure(
+        "UnityTexEnv",
+        "second",
+        vec![
+            Node::pptr("Texture", "m_Texture"),
+            vector2f("m_Scale"),
+            vector2f("m_Offset"),
+        ],
+    );
+    let property_sheet = Node::structure(
+        "UnityPropertySheet",
+        "m_SavedProperties",
+        vec![
+            Node::map("m_TexEnvs", Node::string("first"), tex_env),
+            Node::map("m_Floats", Node::string("first"), float("second")),
+            Node::map("m_Colors", Node::string("first"), color_rgba("second")),
+        ],
+    )
+    .with_version(3);
+    base(
+        "Material",
+        vec![
+            Node::string("m_Name"),
+            Node::pptr("Shader", "m_Shader"),
+            Node::string("m_ShaderKeywords"),
+            uint("m_LightmapFlags"),
+            boolean("m_EnableInstancingVariants"),
+            boolean("m_DoubleSidedGI").aligned(),
+            int("m_CustomRenderQueue"),
+            Node::map(
+                "stringTagMap",
+                Node::string("first"),
+                Node::string("second"),
+            ),
+            Node::vector("disabledShaderPasses", Node::string("data")),
+            property_sheet,
+            Node::vector(
+                "m_BuildTextureStacks",
+                Node::structure(
+                    "BuildTextureStackReference",
+                    "data",
+                    vec![Node::string("groupName"), Node::string("itemName")],
+                ),
+            ),
+        ],
+    )
+    .with_version(6)
+}
+
+fn texture_2d() -> Node {
+    base("Texture2D", texture_2d_fields()).with_version(2)
+}
+
+fn texture_2d_fields() -> Vec<Node> {
+    vec![
+        Node::string("m_Name"),
+        int("m_ForcedFallbackFormat"),
+        boolean("m_DownscaleFallback"),
+        boolean("m_IsAlphaChannelOptional").aligned(),
+        int("m_Width"),
+        int("m_Height"),
+        uint("m_CompleteImageSize"),
+        int("m_MipsStripped"),
+        int("m_TextureFormat"),
+        int("m_MipCount"),
+        boolean("m_IsReadable"),
+        boolean("m_IsPreProcessed"),
+        boolean("m_IgnoreMasterTextureLimit"),
+        boolean("m_StreamingMipmaps").aligned(),
+        int("m_StreamingMipmapsPriority").aligned(),
+        int("m_ImageCount"),
+        int("m_TextureDimension"),
+        texture_settings(),
+        int("m_LightmapFormat"),
+        int("m_ColorSpace"),
+        Node::vector("m_PlatformBlob", Node::primitive("UInt8", "data")),
+        Node::typeless_data("image data").aligned(),
+        streaming_info(),
+    ]
+}
+
+fn texture_settings() -> Node {
+    Node::structure(
+        "GLTextureSettings",
+        "m_TextureSettings",
+        vec![
+            int("m_FilterMode"),
+            int("m_Aniso"),
+            float("m_MipBias"),
+            int("m_WrapU"),
+            int("m_WrapV"),
+            int("m_WrapW"),
+        ],
+    )
+    .with_version(2)
+}
+
+fn streaming_info() -> Node {
+    Node::structure(
+        "StreamingInfo",
+        "m_StreamData",
+        vec![
+            Node::primitive("UInt64", "offset"),
+            uint("size"),
+            Node::string("path"),
+        ],
+    )
+    .with_version(2)
+}
+
+fn cubemap() -> Node {
+    let mut fields = texture_2d_fields();
+    fields.push(Node::vector(
+        "m_SourceTextures",
+        Node::pptr("Texture2D", "data"),
+    ));
+    base("Cubemap", fields).with_version(2)
+}
+
+// Texture2DArray and Texture3D have the same fields.
+fn texture_slices(type_name: &str) -> Node {
+    base(
+        type_name,
+        vec![
+            Node::string("m_Name"),
+            int("m_ForcedFallbackFormat"),
+            boolean("m_DownscaleFallback"),
+            boolean("m_IsAlphaChannelOptional").aligned(),
+            int("m_ColorSpace"),
+            int("m_Format"),
+            int("m_Width"),
+            int("m_Height"),
+            int("m_Depth"),
+            int("m_MipCount"),
+            uint("m_DataSize"),
+            texture_settings(),
+            int("m_UsageMode"),
+            boolean("m_IsReadable").aligned(),
+            Node::typeless_data("image data").aligned(),
+            streaming_info(),
+        ],
+    )
+}
+
+fn texture_2d_array() -> Node {
+    texture_slices("Texture2DArray")
+}
+
+fn texture_3d() -> Node {
+    texture_slices("Texture3D")
+}
+
+fn mesh_filter() -> Node {
+    base(
+        "MeshFilter",
+        vec![
+            Node::pptr("GameObject", "m_GameObject"),
+            Node::pptr("Mesh", "m_Mesh"),
+        ],
+    )
+}
+
+fn text_asset() -> Node {
+    base(
+        "TextAsset",
+        vec![Node::string("m_Name"), Node::string("m_Script")],
+    )
+}
+
+fn animator() -> Node {
+    base(
+        "Animator",
+        vec![
+            Node::pptr("GameObject", "m_GameObject"),
+            Node::primitive("UInt8", "m_Enabled").aligned(),
+            Node::pptr("Avatar", "m_Avatar"),
+            Node::pptr("RuntimeAnimatorController", "m_Controller"),
+            int("m_CullingMode"),
+            int("m_UpdateMode"),
+            boolean("m_ApplyRootMotion"),
+            boolean("m_LinearVelocityBlending").aligned(),
+            boolean("m_HasTransformHierarchy"),
+            boolean("m_AllowConstantClipSamplingOptimization"),
+            boolean("m_KeepAnimatorControllerStateOnDisable").aligned(),
+        ],
+    )
+    .with_version(3)
+}
+
+fn mono_behavior() -> Node {
+    base(
+        "MonoBehaviour",
+        vec![
+            Node::pptr("GameObject", "m_GameObject"),
+            Node::primitive("UInt8", "m_Enabled").aligned(),
+            Node::pptr("MonoScript", "m_Script"),
+            Node::string("m_Name"),
+        ],
+    )
+}
+
+fn mono_script() -> Node {
+    base(
+        "MonoScript",
+        vec![
+            Node::string("m_Name"),
+            int("m_ExecutionOrder"),
+            Node::hash128("m_PropertiesHash"),
+            Node::string("m_ClassName"),
+            Node::string("m_Namespace"),
+            Node::string("m_AssemblyName"),
+        ],
+    )
+    .with_version(5)
+}
+
+fn asset_bundle() -> Node {
+    base(
+        "AssetBundle",
+        vec![
+            Node::string("m_Name"),
+            Node::vector("m_PreloadTable", Node::pptr("Object", "data")),
+            Node::map("m_Container", Node::string("first"), asset_info("second")),
+            asset_info("m_MainAsset"),
+            uint("m_RuntimeCompatibility"),
+            Node::string("m_AssetBundleName"),
+            Node::vector("m_Dependencies", Node::string("data")),
+            boolean("m_IsStreamedSceneAssetBundle").aligned(),
+            int("m_ExplicitDataLayout"),
+            int("m_PathFlags"),
+            Node::map(
+                "m_SceneHashes",
+                Node::string("first"),
+                Node::string("second"),
+            ),
+        ],
+    )
+    .with_version(3)
+}
+
+fn animator_override_controller() -> Node {
+    base(
+        "AnimatorOverrideController",
+        vec![
+            Node::string("m_Name"),
+            Node::pptr("RuntimeAnimatorController", "m_Controller"),
+            Node::vector(
+                "m_Clips",
+                Node::structure(
+                    "AnimationClipOverride",
+                    "data",
+                    vec![
+                        Node::pptr("AnimationClip", "m_OriginalClip"),
+                        Node::pptr("AnimationClip", "m_OverrideClip"),
+                    ],
+                ),
+            ),
+        ],
+    )
+}
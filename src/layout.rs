@@ -0,0 +1,1215 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use anyhow::Result;
+
+use crate::{
+    AngleLimits, AnimationClip, AnimationClipBindingConstant, AnimationClipOverride,
+    AnimationEvent, Animator, AnimatorController, AnimatorOverrideController, AssetBundle,
+    AssetFile, AssetFileType, AssetInfo, Avatar, AvatarConstant, AvatarHuman, Blend1dDataConstant,
+    Blend2dDataConstant, BlendDirectDataConstant, BlendShapeData, BlendShapeVertex,
+    BlendTreeConstant, BlendTreeNodeConstant, ChannelInfo, Clip, ClipMuscleConstant, ColorRGBA,
+    CompressedAnimationCurve, CompressedMesh, ConditionConstant, ConstantClip, ControllerConstant,
+    Cubemap, DenseClip, FloatCurve, FloatPropertySheetPair, GameObject, GenericBinding,
+    GlTextureSettings, HandPose, HumanBone, HumanDescription, HumanGoal, HumanPose, HumanPoseMask,
+    LayerConstant, LengthLimitProperty, Material, Matrix4x4f, Mesh, MeshBlendShape,
+    MeshBlendShapeChannel, MeshFilter, MeshRenderer, MinMaxAABB, MonoBehavior, MonoScript,
+    MotionNeighborList, PPtr, PPtrCurve, PackedBitVector, PackedBitVector2, PackedFloatVector,
+    PackedIntVector, QuaternionAnimationCurve, QuaternionCurve, QuaternionCurveKeyframe,
+    Quaternionf, RectF, RenderDataKey, SecondarySpriteTexture, SelectorStateConstant,
+    SelectorTransitionConstant, Skeleton, SkeletonAxes, SkeletonBone, SkeletonBoneLimit,
+    SkeletonLimit, SkeletonMask, SkeletonMaskElement, SkeletonNode, SkeletonPose,
+    SkeletonTransform, SkinnedMeshRenderer, SpringBone, SpringBoneProperties,
+    SpringColliderProperty, SpringJob, Sprite, SpriteAtlas, SpriteAtlasData, SpriteBone,
+    SpriteRenderData, StateConstant, StateKey, StateMachineBehaviourVectorDescription,
+    StateMachineConstant, StateRange, StaticBatchInfo, StreamedClip, StreamingInfo, SubMesh,
+    TerrainData, TerrainLayerData, TerrainOverlapData, TexEnv, TextAsset, Texture2D,
+    Texture2DArray, Texture3D, TextureArrayData, TextureFormat, TosPair, Transform,
+    TransitionConstant, TypeTreeNodeInfo, UArray, UString, UnityPropertySheet, UnityVersion,
+    ValueArray, ValueArrayConstant, ValueConstant, ValueDelta, Vector2f, Vector3Curve, Vector3f,
+    Vector4f, VertexData, XForm, AABB, EMPTY_MONO_BEHAVIOR_HASH, SPRING_BONE_MONO_BEHAVIOR_HASH,
+    SPRING_JOB_MONO_BEHAVIOR_HASH, TERRAIN_MONO_BEHAVIOR_TYPE_HASH,
+};
+
+/// Shape of a hand-written asset struct, used to check it against a file's type tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout {
+    Int(i32),
+    Float(i32),
+    /// Fixed size data such as a Hash128 or GUID, regardless of how the type tree splits it up.
+    Blob(i32),
+    String,
+    PPtr,
+    Array(Box<Layout>),
+    Struct(Vec<LayoutField>),
+    /// Anything from here on is kept as raw bytes and not checked.
+    Opaque,
+    /// `older` before `since` and `newer` from then on. Either is accepted when the file's
+    /// version isn't known.
+    Versioned {
+        since: UnityVersion,
+        older: Box<Layout>,
+        newer: Box<Layout>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutField {
+    pub name: &'static str,
+    /// Type tree name when it can't be derived from the Rust name (ex. "m_TexEnvs" for "text_envs").
+    pub unity_name: Option<&'static str>,
+    pub layout: Layout,
+    /// Set for fields that only exist in some Unity versions. They are skipped when missing.
+    pub optional: bool,
+    /// First Unity version that serializes the field. Like `optional` when the version isn't known.
+    pub since: Option<UnityVersion>,
+}
+
+impl LayoutField {
+    pub fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    pub fn since(self, major: u16, minor: u16) -> Self {
+        Self {
+            since: Some(UnityVersion::new(major, minor, 0)),
+            ..self
+        }
+    }
+
+    fn matches(&self, node: &TypeTreeNodeInfo) -> bool {
+        if self.name.is_empty() {
+            return true;
+        }
+        match self.unity_name {
+            Some(name) => name == node.name,
+            None => normalize(self.name) == normalize(&node.name),
+        }
+    }
+}
+
+pub trait TypeTreeLayout {
+    fn layout() -> Layout;
+}
+
+/// A difference between a hand-written struct and the type tree in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    pub type_name: String,
+    /// Type tree field path (ex. "m_SubMeshes[].localAABB").
+    pub path: String,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for LayoutMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}: expected {}, found {}",
+            self.type_name, self.path, self.expected, self.found
+        )
+    }
+}
+
+impl AssetFileType {
+    /// Compare `T` against this type's tree. Returns every mismatch found.
+    /// Fields that depend on the Unity version may be present or not.
+    pub fn check_layout<T: TypeTreeLayout>(&self) -> Result<Vec<LayoutMismatch>> {
+        self.check_against(&T::layout(), None)
+    }
+
+    /// Compare `T` against this type's tree as written by `version`.
+    pub fn check_layout_for_version<T: TypeTreeLayout>(
+        &self,
+        version: UnityVersion,
+    ) -> Result<Vec<LayoutMismatch>> {
+        self.check_against(&T::layout(), Some(version))
+    }
+
+    fn check_against(
+        &self,
+        layout: &Layout,
+        version: Option<UnityVersion>,
+    ) -> Result<Vec<LayoutMismatch>> {
+        let mut mismatches = vec![];
+        if let Some(root) = self.type_tree.to_nodes()?.first() {
+            compare(layout, root, "", version, &mut mismatches);
+            for mismatch in &mut mismatches {
+                mismatch.type_name = root.type_name.clone();
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+impl AssetFile {
+    /// Check every type the crate parses into a typed asset against the file's type trees.
+    /// Built in classes are matched by class ID, so types whose hash changed are checked too.
+    /// Version dependent fields are checked against the file's Unity version.
+    pub fn check_layouts(&self) -> Result<Vec<LayoutMismatch>> {
+        let mut mismatches = vec![];
+        for ty in &self.types {
+            if let Some(layout) = layout_for_type(ty) {
+                mismatches.extend(ty.check_against(&layout, Some(self.unity_version()))?);
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+fn layout_for_type(ty: &AssetFileType) -> Option<Layout> {
+    let layout = match ty.type_hash {
+        TERRAIN_MONO_BEHAVIOR_TYPE_HASH => MonoBehavior::<TerrainData>::layout(),
+        EMPTY_MONO_BEHAVIOR_HASH => MonoBehavior::<()>::layout(),
+        SPRING_JOB_MONO_BEHAVIOR_HASH => MonoBehavior::<SpringJob>::layout(),
+        SPRING_BONE_MONO_BEHAVIOR_HASH => MonoBehavior::<SpringBone>::layout(),
+        _ => match ty.class_id {
+            1 => GameObject::layout(),
+            4 => Transform::layout(),
+            21 => Material::layout(),
+            23 => MeshRenderer::layout(),
+            28 => Texture2D::layout(),
+            33 => MeshFilter::layout(),
+            43 => Mesh::layout(),
+            49 => TextAsset::layout(),
+            74 => AnimationClip::layout(),
+            89 => Cubemap::layout(),
+            90 => Avatar::layout(),
+            91 => AnimatorController::layout(),
+            95 => Animator::layout(),
+            115 => MonoScript::layout(),
+            117 => Texture3D::layout(),
+            137 => SkinnedMeshRenderer::layout(),
+            142 => AssetBundle::layout(),
+            187 => Texture2DArray::layout(),
+            213 => Sprite::layout(),
+            221 => AnimatorOverrideController::layout(),
+            687078895 => SpriteAtlas::layout(),
+            _ => return None,
+        },
+    };
+    Some(layout)
+}
+
+fn normalize(name: &str) -> String {
+    let name = name.strip_prefix("m_").unwrap_or(name);
+    name.chars()
+        .filter(|c| !matches!(c, '_' | ' ' | '[' | ']'))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn is_primitive(node: &TypeTreeNodeInfo) -> bool {
+    node.children.is_empty()
+}
+
+// Plain structs are the only nodes that hand-written layouts may flatten or skip through.
+fn is_plain_struct(node: &TypeTreeNodeInfo) -> bool {
+    !node.children.is_empty()
+        && node.inner_array().is_none()
+        && !matches!(node.type_name.as_str(), "string" | "map")
+        && !node.type_name.starts_with("PPtr<")
+}
+
+fn describe_node(node: &TypeTreeNodeInfo) -> String {
+    format!(
+        "{} '{}' ({} bytes)",
+        node.type_name, node.name, node.byte_size
+    )
+}
+
+fn describe_layout(layout: &Layout) -> String {
+    match layout {
+        Layout::Int(size) => format!("{} byte integer", size),
+        Layout::Float(size) => format!("{} byte float", size),
+        Layout::Blob(size) => format!("{} bytes of data", size),
+        Layout::String => String::from("string"),
+        Layout::PPtr => String::from("PPtr"),
+        Layout::Array(_) => String::from("array"),
+        Layout::Struct(_) => String::from("struct"),
+        Layout::Opaque => String::from("anything"),
+        Layout::Versioned { older, newer, .. } => {
+            format!("{} or {}", describe_layout(older), describe_layout(newer))
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn compare(
+    layout: &Layout,
+    node: &TypeTreeNodeInfo,
+    path: &str,
+    version: Option<UnityVersion>,
+    out: &mut Vec<LayoutMismatch>,
+) {
+    let mismatch = |out: &mut Vec<LayoutMismatch>| {
+        out.push(LayoutMismatch {
+            type_name: String::new(),
+            path: path.to_string(),
+            expected: describe_layout(layout),
+            found: describe_node(node),
+        })
+    };
+    // Wrappers such as ComponentPair or VariableBoneCountWeights hold a single field.
+    if let [child] = node.children.as_slice() {
+        let unwraps = !matches!(layout, Layout::Struct(_) | Layout::Blob(_) | Layout::Opaque);
+        if unwraps && is_plain_struct(node) {
+            compare(layout, child, &join(path, &child.name), version, out);
+            return;
+        }
+    }
+    match layout {
+        Layout::Opaque => {}
+        Layout::Versioned {
+            since,
+            older,
+            newer,
+        } => match version {
+            Some(version) if version < *since => compare(older, node, path, Some(version), out),
+            Some(version) => compare(newer, node, path, Some(version), out),
+            None => {
+                let mut newer_mismatches = vec![];
+                compare(newer, node, path, None, &mut newer_mismatches);
+                let mut older_mismatches = vec![];
+                compare(older, node, path, None, &mut older_mismatches);
+                if !newer_mismatches.is_empty() && !older_mismatches.is_empty() {
+                    out.extend(newer_mismatches);
+                }
+            }
+        },
+        Layout::Int(size) | Layout::Float(size) => {
+            let is_float = matches!(node.type_name.as_str(), "float" | "double");
+            if !is_primitive(node)
+                || node.byte_size != *size
+                || is_float != matches!(layout, Layout::Float(_))
+            {
+                mismatch(out);
+            }
+        }
+        Layout::Blob(size) => {
+            if node.byte_size != *size {
+                mismatch(out);
+            }
+        }
+        Layout::String => {
+            if node.type_name != "string" {
+                mismatch(out);
+            }
+        }
+        Layout::PPtr => {
+            if !node.type_name.starts_with("PPtr<") {
+                mismatch(out);
+            }
+        }
+        Layout::Array(element) => {
+            if let Some(array) = node.inner_array().or(node.is_array().then_some(node)) {
+                if let Some(data) = array.children.get(1) {
+                    compare(element, data, &format!("{}[]", path), version, out);
+                }
+            } else if node.type_name == "TypelessData" && **element == Layout::Int(1) {
+                // Byte arrays and TypelessData share a layout.
+            } else {
+                mismatch(out);
+            }
+        }
+        Layout::Struct(fields) => {
+            if fields.is_empty() && node.children.is_empty() {
+                return;
+            }
+            if !is_plain_struct(node) {
+                mismatch(out);
+                return;
+            }
+            // OffsetPtr and similar wrappers only have a "data" child.
+            if let [child] = node.children.as_slice() {
+                let first_matches = fields.first().map(|f| f.matches(child)).unwrap_or(true);
+                if !first_matches && is_plain_struct(child) {
+                    compare(layout, child, &join(path, &child.name), version, out);
+                    return;
+                }
+            }
+            compare_fields(fields, node, path, version, out);
+        }
+    }
+}
+
+fn compare_fields(
+    fields: &[LayoutField],
+    node: &TypeTreeNodeInfo,
+    path: &str,
+    version: Option<UnityVersion>,
+    out: &mut Vec<LayoutMismatch>,
+) {
+    let mut nodes: VecDeque<(&TypeTreeNodeInfo, String)> = node
+        .children
+        .iter()
+        .map(|child| (child, join(path, &child.name)))
+        .collect();
+    for field in fields {
+        let optional = match (field.since, version) {
+            // Not serialized yet, so the next node belongs to a later field.
+            (Some(since), Some(version)) if version < since => continue,
+            (Some(_), Some(_)) => false,
+            (Some(_), None) => true,
+            (None, _) => field.optional,
+        };
+        let expected_name = field.unity_name.unwrap_or(field.name);
+        loop {
+            let Some((child, child_path)) = nodes.pop_front() else {
+                if optional {
+                    break;
+                }
+                out.push(LayoutMismatch {
+                    type_name: String::new(),
+                    path: join(path, expected_name),
+                    expected: format!("field '{}'", expected_name),
+                    found: String::from("end of struct"),
+                });
+                return;
+            };
+            if field.matches(child) {
+                compare(&field.layout, child, &child_path, version, out);
+                break;
+            }
+            if optional {
+                nodes.push_front((child, child_path));
+                break;
+            }
+            // Hand-written structs sometimes inline a nested struct's fields.
+            if is_plain_struct(child) {
+                for (i, grandchild) in child.children.iter().enumerate() {
+                    nodes.insert(i, (grandchild, join(&child_path, &grandchild.name)));
+                }
+                continue;
+            }
+            out.push(LayoutMismatch {
+                type_name: String::new(),
+                path: child_path,
+                expected: format!("field '{}'", expected_name),
+                found: describe_node(child),
+            });
+            return;
+        }
+        if field.layout == Layout::Opaque {
+            return;
+        }
+    }
+    if let Some((child, child_path)) = nodes.pop_front() {
+        out.push(LayoutMismatch {
+            type_name: String::new(),
+            path: child_path,
+            expected: String::from("end of struct"),
+            found: describe_node(child),
+        });
+    }
+}
+
+#[doc(hidden)]
+pub fn layout_field<S, T: TypeTreeLayout>(
+    name: &'static str,
+    unity_name: Option<&'static str>,
+    _: fn(&S) -> &T,
+) -> LayoutField {
+    LayoutField {
+        name,
+        unity_name,
+        layout: T::layout(),
+        optional: false,
+        since: None,
+    }
+}
+
+/// Implement [TypeTreeLayout] for a struct by listing its fields in serialized order.
+/// Use `field as "m_Name"` when the type tree name can't be derived from the field name and
+/// `#[since(major, minor)] field` for fields added in a known Unity version and `#[optional] field`
+/// for any other fields that are only serialized by some Unity versions.
+#[macro_export]
+macro_rules! impl_type_tree_layout {
+    ($($ty:ty {
+        $($(#[$modifier:ident $(($($arg:expr),*))?])? $field:ident $(as $unity_name:literal)?),*
+        $(,)?
+    })*) => {
+        $(
+            impl $crate::TypeTreeLayout for $ty {
+                fn layout() -> $crate::Layout {
+                    $crate::Layout::Struct(vec![
+                        $($crate::layout_field(
+                            stringify!($field),
+                            None$(.or(Some($unity_name)))?,
+                            |s: &$ty| &s.$field,
+                        )$(.$modifier($($($arg),*)?))?,)*
+                    ])
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_primitive_layout {
+    ($($ty:ty => $layout:expr;)*) => {
+        $(
+            impl TypeTreeLayout for $ty {
+                fn layout() -> Layout {
+                    $layout
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive_layout! {
+    u8 => Layout::Int(1);
+    i8 => Layout::Int(1);
+    u16 => Layout::Int(2);
+    i16 => Layout::Int(2);
+    u32 => Layout::Int(4);
+    i32 => Layout::Int(4);
+    u64 => Layout::Int(8);
+    i64 => Layout::Int(8);
+    f32 => Layout::Float(4);
+    f64 => Layout::Float(8);
+    i128 => Layout::Blob(16);
+    TextureFormat => Layout::Int(4);
+    UString => Layout::String;
+    PPtr => Layout::PPtr;
+    Vec<u8> => Layout::Opaque;
+    () => Layout::Struct(vec![]);
+}
+
+impl<T: TypeTreeLayout + std::fmt::Debug> TypeTreeLayout for UArray<T> {
+    fn layout() -> Layout {
+        Layout::Array(Box::new(T::layout()))
+    }
+}
+
+// Tuples are matched by position since Unity doesn't always name the fields first and second.
+impl<K: TypeTreeLayout, V: TypeTreeLayout> TypeTreeLayout for (K, V) {
+    fn layout() -> Layout {
+        Layout::Struct(vec![
+            LayoutField {
+                name: "",
+                unity_name: None,
+                layout: K::layout(),
+                optional: false,
+                since: None,
+            },
+            LayoutField {
+                name: "",
+                unity_name: None,
+                layout: V::layout(),
+                optional: false,
+                since: None,
+            },
+        ])
+    }
+}
+
+// The script's own fields follow the MonoBehaviour header in the same struct.
+impl<T: TypeTreeLayout + std::fmt::Debug> TypeTreeLayout for MonoBehavior<T> {
+    fn layout() -> Layout {
+        let mut fields = vec![
+            layout_field("game_object", None, |s: &Self| &s.game_object),
+            layout_field("enabled", None, |s: &Self| &s.enabled),
+            layout_field("script", None, |s: &Self| &s.script),
+            layout_field("name", None, |s: &Self| &s.name),
+        ];
+        if let Layout::Struct(data_fields) = T::layout() {
+            fields.extend(data_fields);
+        }
+        Layout::Struct(fields)
+    }
+}
+
+// Cubemap's own fields follow the Texture2D ones in the same struct.
+impl TypeTreeLayout for Cubemap {
+    fn layout() -> Layout {
+        let mut fields = vec![];
+        if let Layout::Struct(texture_fields) = Texture2D::layout() {
+            fields.extend(texture_fields);
+        }
+        fields.push(layout_field("source_textures", None, |s: &Self| {
+            &s.source_textures
+        }));
+        Layout::Struct(fields)
+    }
+}
+
+// The offset is 32 bits before 2020.1.
+impl TypeTreeLayout for StreamingInfo {
+    fn layout() -> Layout {
+        let mut offset = layout_field("offset", None, |s: &Self| &s.offset);
+        offset.layout = Layout::Versioned {
+            since: UnityVersion::new(2020, 1, 0),
+            older: Box::new(Layout::Int(4)),
+            newer: Box::new(Layout::Int(8)),
+        };
+        Layout::Struct(vec![
+            offset,
+            layout_field("size", None, |s: &Self| &s.size),
+            layout_field("path", None, |s: &Self| &s.path),
+        ])
+    }
+}
+
+impl TypeTreeLayout for Texture2DArray {
+    fn layout() -> Layout {
+        TextureArrayData::layout()
+    }
+}
+
+impl TypeTreeLayout for Texture3D {
+    fn layout() -> Layout {
+        TextureArrayData::layout()
+    }
+}
+
+impl_type_tree_layout! {
+    AssetBundle {
+        name,
+        preloads as "m_PreloadTable",
+        container_map as "m_Container",
+        main_asset,
+        runtime_compatibility,
+        asset_bundle_name,
+        dependencies,
+        is_streamed_asset_bundle as "m_IsStreamedSceneAssetBundle",
+        explicit_data_layout,
+        path_flags,
+        scene_hashes,
+    }
+    AssetInfo { preload_index, preload_size, asset }
+    GameObject { component, layer, name, tag, is_active }
+    Transform { game_object, local_rotation, local_position, local_scale, children, father }
+    Animator {
+        game_object,
+        enabled,
+        avatar,
+        controller,
+        culling_mode,
+        update_mode,
+        apply_root_motion,
+        linear_velocity_blending,
+        has_transform_hierarchy,
+        allow_constant_clip_sampling_optimization,
+        keep_animator_controller_state_on_disable,
+    }
+    TextAsset { name, data as "m_Script" }
+    MonoScript { name, execution_order, properties_hash, class_name, namespace, assembly_name }
+    TerrainData { x, z, width, height, layers, overlaps, terrains }
+    TerrainLayerData { x, y, w, h, group, attr }
+    TerrainOverlapData { x, y, attr }
+    Texture2D {
+        name,
+        forced_fallback_format,
+        downscale_fallback,
+        #[since(2020, 2)]
+        is_alpha_channel_optional,
+        width,
+        height,
+        complete_image_size,
+        #[since(2020, 1)]
+        mips_stripped,
+        texture_format,
+        mip_count,
+        is_readable,
+        #[since(2020, 1)]
+        is_pre_processed,
+        #[since(2019, 3)]
+        ignore_master_texture_limit,
+        streaming_mipmaps,
+        streaming_mipmaps_priority,
+        image_count,
+        texture_dimension,
+        texture_settings,
+        lightmap_format,
+        color_space,
+        #[since(2020, 2)]
+        platform_blob,
+        image_data,
+        stream_data,
+    }
+    TextureArrayData {
+        name,
+        forced_fallback_format,
+        downscale_fallback,
+        #[since(2020, 2)]
+        is_alpha_channel_optional,
+        color_space,
+        format,
+        width,
+        height,
+        depth,
+        mip_count,
+        data_size,
+        texture_settings,
+        #[since(2020, 2)]
+        usage_mode,
+        is_readable,
+        image_data,
+        stream_data,
+    }
+    GlTextureSettings { filter_mode, aniso, mip_bias, wrap_u, wrap_v, wrap_w }
+    SpriteAtlas {
+        name,
+        packed_sprites,
+        sprite_names_to_index as "m_PackedSpriteNamesToIndex",
+        render_data_map,
+        tag,
+        is_variant,
+    }
+    RenderDataKey { guid as "first", second }
+    SpriteAtlasData {
+        texture,
+        alpha_texture,
+        texture_rect,
+        texture_rect_offset,
+        atlas_rect_offset,
+        uv_transform,
+        downscale_multiplier,
+        settings_raw,
+        secondary_textures,
+    }
+    Sprite {
+        name,
+        rect,
+        offset,
+        border,
+        pixels_to_units,
+        pivot,
+        extrude,
+        is_polygon,
+        render_data_key,
+        atlas_tags,
+        sprite_atlas,
+        sprite_render_data as "m_RD",
+        physics_shape,
+        bones,
+    }
+    RectF { x, y, w as "width", h as "height" }
+    Vector2f { x, y }
+    Vector3f { x, y, z }
+    Vector4f { x, y, z, w }
+    SpriteRenderData {
+        texture,
+        alpha_texture,
+        secondary_textures,
+        sub_meshes,
+        index_buffer,
+        vertex_data,
+        bind_pose,
+        texture_rect,
+        texture_rect_offset,
+        atlas_rect_offset,
+        settings_raw,
+        uv_transform,
+        downscale_multiplier,
+    }
+    SecondarySpriteTexture { texture, name }
+    SubMesh {
+        first_byte,
+        index_count,
+        topology,
+        base_vertex,
+        first_vertex,
+        vertex_count,
+        aabb as "localAABB",
+    }
+    AABB { center, extent }
+    VertexData { vertex_count, channels, data as "m_DataSize" }
+    ChannelInfo { stream, offset, format, dimension }
+    Matrix4x4f {
+        e00, e01, e02, e03, e10, e11, e12, e13, e20, e21, e22, e23, e30, e31, e32, e33,
+    }
+    SpriteBone { name, position, rotation, length, parent_id }
+    Quaternionf { x, y, z, w }
+    Mesh {
+        name,
+        sub_meshes,
+        shapes,
+        bind_pose,
+        bone_name_hashes,
+        root_bone_name_hash,
+        bones_aabb,
+        variable_bone_count_weights,
+        mesh_compression,
+        is_readable,
+        keep_vertices,
+        keep_indices,
+        index_format,
+        index_buffer,
+        vertex_data,
+        compressed_mesh,
+        local_aabb,
+        mesh_usage_flags,
+        baked_convex_collision_mesh,
+        baked_triangle_collision_mesh,
+        mesh_metrics_0,
+        mesh_metrics_1,
+        stream_data,
+    }
+    BlendShapeData { vertices, shapes, channels, full_weights }
+    BlendShapeVertex { vertex, normal, tangent, index }
+    MeshBlendShape { first_vertex, vertex_count, has_normals, has_tangents }
+    MeshBlendShapeChannel { name, name_hash, frame_index, frame_count }
+    MinMaxAABB { min, max }
+    CompressedMesh {
+        vertices,
+        uv,
+        normals,
+        tangents,
+        weights,
+        normal_signs,
+        tangent_signs,
+        float_colors,
+        bone_indices,
+        triangles,
+        uv_info,
+    }
+    PackedBitVector { num_items, range, start, data, bit_size }
+    PackedBitVector2 { num_items, data, bit_size }
+    Avatar { name, avatar_size, avatar, tos, human_description }
+    TosPair { first, second }
+    AvatarConstant {
+        skeleton as "m_AvatarSkeleton",
+        avatar_skeleton_pose,
+        default_pose,
+        skeleton_name_id_array,
+        human,
+        human_skeleton_index_array,
+        human_skeleton_reverse_index_array,
+        root_motion_bone_index,
+        root_motion_bone_x,
+        root_motion_skeleton,
+        root_motion_skeleton_pose,
+        root_motion_skeleton_index_array,
+    }
+    Skeleton { node, id, axes as "m_AxesArray" }
+    SkeletonNode { parent_id, axes_id }
+    SkeletonAxes { pre_q, post_q, sgn, limit, length, ty as "m_Type" }
+    SkeletonLimit { min, max }
+    SkeletonPose { transform as "m_X" }
+    SkeletonTransform { transform as "t", quaternion as "q", scale as "s" }
+    AvatarHuman {
+        root_x,
+        skeleton,
+        skeleton_pose,
+        left_hand,
+        right_hand,
+        human_bone_index,
+        human_bone_mass,
+        scale,
+        arm_twist,
+        forearm_twist,
+        upper_left_twist as "m_UpperLegTwist",
+        leg_twist,
+        arm_stretch,
+        leg_stretch,
+        feet_spacing,
+        has_left_hand,
+        has_right_hand,
+        has_tdof,
+    }
+    HumanDescription {
+        human,
+        skeleton,
+        arm_twist,
+        forearm_twist,
+        upper_leg_twist,
+        leg_twist,
+        arm_stretch,
+        leg_stretch,
+        feet_spacing,
+        global_scale,
+        root_motion_bone_name,
+        has_translation_dof,
+        has_extra_root,
+        skeleton_has_parents,
+    }
+    HumanBone { bone_name, human_name, limit }
+    SkeletonBoneLimit { min, max, value, length, modified }
+    SkeletonBone { name, parent_name, position, rotation, scale }
+    Material {
+        name,
+        shader,
+        shader_keywords,
+        lightmap_flags,
+        enable_instancing_variants,
+        double_sided_gi,
+        custom_render_queue,
+        string_tag_map,
+        disabled_shader_passes,
+        saved_properties,
+        build_texture_stacks,
+    }
+    UnityPropertySheet { text_envs as "m_TexEnvs", floats, colors }
+    TexEnv { texture, scale, offset }
+    FloatPropertySheetPair { key as "first", value as "second" }
+    ColorRGBA { r, g, b, a }
+    MeshFilter { game_object, mesh }
+    MeshRenderer {
+        game_object,
+        enabled,
+        cast_shadows,
+        receive_shadows,
+        dynamic_occludee,
+        #[since(2021, 1)]
+        static_shadow_caster,
+        motion_vectors,
+        light_probe_usage,
+        reflection_probe_usage,
+        #[since(2019, 3)]
+        ray_tracing_mode,
+        #[since(2020, 1)]
+        ray_tracing_procedural as "m_RayTraceProcedural",
+        rendering_layer_mask,
+        renderer_priority,
+        lightmap_index,
+        lightmap_index_dynamic,
+        lightmap_tiling_offset,
+        lightmap_tiling_offset_dynamic,
+        materials,
+        static_batch_info,
+        static_batch_root,
+        probe_anchor,
+        light_probe_volume_override,
+        sorting_layer_id,
+        sorting_layer,
+        sorting_order,
+        additional_vertex_streams,
+        enlighten_vertex_stream,
+    }
+    StaticBatchInfo { first_sub_mesh, sub_mesh_count }
+    SkinnedMeshRenderer {
+        game_object,
+        enabled,
+        cast_shadows,
+        receive_shadows,
+        dynamic_occludee,
+        #[since(2021, 1)]
+        static_shadow_caster,
+        motion_vectors,
+        light_probe_usage,
+        reflection_probe_usage,
+        #[since(2019, 3)]
+        ray_tracing_mode,
+        #[since(2020, 1)]
+        ray_trace_procedural,
+        rendering_layer_mask,
+        renderer_priority,
+        lightmap_index,
+        lightmap_index_dynamic,
+        lightmap_tiling_offset,
+        lightmap_tiling_offset_dynamic,
+        materials,
+        first_sub_mesh,
+        sub_mesh_count,
+        static_batch_root,
+        probe_anchor,
+        light_probe_volume_override,
+        sorting_layer_id,
+        sorting_layer,
+        sorting_order,
+        quality,
+        update_when_offscreen,
+        skinned_motion_vectors,
+        mesh,
+        bones,
+        blend_shape_weights,
+        root_bone,
+        aabb,
+        dirty_aabb,
+    }
+    SpringJob {
+        optimize_transform,
+        is_paused,
+        simulation_frame_rate,
+        dynamic_ratio,
+        gravity,
+        bounce,
+        friction,
+        time,
+        enable_angle_limits,
+        enable_collision,
+        enable_length_limtis,
+        collide_width_ground,
+        ground_height,
+        wind_disabled,
+        wind_influence,
+        wind_power,
+        wind_dir,
+        distance_rate,
+        automatic_reset,
+        reset_distance,
+        reset_angle,
+        sorted_bones,
+        job_colliders,
+        job_properties,
+        init_local_rotations,
+        job_col_properties,
+        job_length_properties,
+    }
+    SpringBoneProperties {
+        stiffness_force,
+        drag_force,
+        spring_force,
+        wind_influence,
+        angular_stiffness,
+        y_angle_limits,
+        z_angle_limits,
+        radius,
+        spring_length,
+        bone_axis,
+        local_position,
+        initial_local_rotation,
+        parent_index,
+        pivot_index,
+        pivot_local_matrix,
+    }
+    AngleLimits { active, min, max }
+    SpringColliderProperty { ty as "type", radius, width, height }
+    LengthLimitProperty { target_index, target }
+    SpringBone {
+        index,
+        enabled_job_system,
+        job_colliders,
+        valid_children,
+        stiffness_force,
+        drag_force,
+        spring_force,
+        wind_influence,
+        pivot_node,
+        angular_stiffness,
+        y_angle_limits,
+        z_angle_limits,
+        length_limit_targets,
+        radius,
+        sphere_colliders,
+        capsule_colliders,
+        panel_colliders,
+    }
+    AnimationClip {
+        name,
+        legacy,
+        compressed,
+        use_high_quality_curves as "m_UseHighQualityCurve",
+        rotation_curves,
+        compressed_rotation_curves,
+        euler_curves,
+        position_curves,
+        scale_curves,
+        float_curves,
+        pptr_curves,
+        sample_rate,
+        wrap_mode,
+        local_bounds as "m_Bounds",
+        muscle_clip_size,
+        muscle_clip,
+        clip_binding_constant,
+        has_generic_root_transform,
+        has_motion_float_curves,
+        events,
+    }
+    ClipMuscleConstant {
+        delta_pose,
+        start_x,
+        stop_x,
+        left_foot_start_x,
+        right_foot_start_x,
+        average_speed,
+        clip,
+        start_time,
+        stop_time,
+        orientation_offset_y,
+        level,
+        cycle_offset,
+        average_angular_speed,
+        index_array,
+        value_array_delta,
+        value_array_reference_pose,
+        mirror,
+        loop_time,
+        loop_blend,
+        loop_blend_orientation,
+        loop_blend_position_y,
+        loop_blend_position_xz,
+        start_at_origin,
+        keep_original_orientation,
+        keep_original_position_y,
+        keep_original_position_xz,
+        height_from_feet,
+    }
+    QuaternionCurve { curve, path }
+    QuaternionAnimationCurve { curve, pre_infinity, post_infinity, rotation_order }
+    Vector3Curve { curve, pre_infinity, post_infinity, rotation_order }
+    FloatCurve { curve, pre_infinity, post_infinity, rotation_order }
+    PPtrCurve { curve, pre_infinity, post_infinity, rotation_order }
+    QuaternionCurveKeyframe {
+        time,
+        value,
+        in_slope,
+        out_slope,
+        weighted_mode,
+        in_weight,
+        out_weight,
+    }
+    CompressedAnimationCurve { path, times, values, slopes, pre_infinity, post_infinity }
+    PackedIntVector { num_items, data, bit_size }
+    PackedFloatVector { num_items, range, start, data, bit_size }
+    XForm { t, q, scale as "s" }
+    HumanPose {
+        root_x,
+        look_at_position,
+        look_at_weight,
+        goal_array,
+        left_hand_pose,
+        right_hand_pose,
+        dof_array,
+        t_dof_array,
+    }
+    HumanGoal { x, weight_t, weight_r, hint_t, hint_weight_t }
+    HandPose { grab_x, do_f_array, m_override as "m_Override", close_open, in_out, grab }
+    StreamedClip { data, curve_count }
+    DenseClip { frame_count, curve_count, sample_rate, begin_time, sample_array }
+    ConstantClip { data }
+    Clip { streamed_clip, dense_clip, constant_clip }
+    ValueDelta { start, stop }
+    GenericBinding { path, attribute, script, type_id, custom_type, is_pptr_curve, is_int_curve }
+    AnimationClipBindingConstant {
+        generic_bindings,
+        pptr_curve_mappings as "pptrCurveMapping",
+    }
+    AnimationEvent {
+        time,
+        function_name,
+        data,
+        object_reference_parameter,
+        float_parameter,
+        int_parameter,
+        message_options,
+    }
+    AnimatorOverrideController { name, controller, clips }
+    AnimationClipOverride { original_clip, override_clip }
+    AnimatorController {
+        name,
+        controller_size,
+        controller,
+        tos,
+        animation_clips,
+        state_machine_behaviour_vector_description,
+        state_machine_behaviours,
+        multi_threaded_state_machine,
+    }
+    ControllerConstant { layer_array, state_machine_array, values, default_values }
+    LayerConstant {
+        state_machine_index,
+        state_machine_motion_set_index,
+        body_mask,
+        skeleton_mask,
+        binding,
+        layer_blending_mode,
+        default_weight,
+        ik_pass,
+        synced_layer_affects_timing,
+    }
+    HumanPoseMask { word0, word1, word2 }
+    SkeletonMask { data }
+    SkeletonMaskElement { path_hash, weight }
+    StateMachineConstant {
+        state_constant_array,
+        any_state_transition_constant_array,
+        selector_state_constant_array,
+        default_state,
+        motion_set_count,
+    }
+    StateConstant {
+        transition_constant_array,
+        blend_tree_constant_index_array,
+        blend_tree_constant_array,
+        name_id,
+        path_id,
+        full_path_id,
+        tag_id,
+        speed_param_id,
+        mirror_param_id,
+        cycle_offset_param_id,
+        time_param_id,
+        speed,
+        cycle_offset,
+        ik_on_feet,
+        write_default_values,
+        m_loop as "m_Loop",
+        mirror,
+    }
+    TransitionConstant {
+        condition_constant_array,
+        destination_state,
+        full_path_id,
+        id,
+        user_id,
+        transition_duration,
+        transition_offset,
+        exit_time,
+        has_exit_time,
+        has_fixed_duration,
+        interruption_source,
+        ordered_interruption,
+        can_transition_to_self,
+    }
+    ConditionConstant { condition_mode, event_id, event_threshold, exit_time }
+    SelectorStateConstant { transition_constant_array, full_path_id, is_entry }
+    SelectorTransitionConstant { destination, condition_constant_array }
+    BlendTreeConstant { node_array }
+    BlendTreeNodeConstant {
+        blend_type,
+        blend_event_id,
+        blend_event_y_id,
+        child_indices,
+        blend_1d_data,
+        blend_2d_data,
+        blend_direct_data,
+        clip_id,
+        duration,
+        cycle_offset,
+        mirror,
+    }
+    Blend1dDataConstant { child_threshold_array }
+    Blend2dDataConstant {
+        child_position_array,
+        child_magnitude_array,
+        child_pair_vector_array,
+        child_pair_avg_mag_inv_array,
+        child_neighbor_list_array,
+    }
+    MotionNeighborList { neighbor_array }
+    BlendDirectDataConstant {
+        child_blend_event_id_array,
+        normalized_blend_values,
+    }
+    ValueArrayConstant { value_array }
+    ValueConstant { id, m_type as "m_Type", index }
+    ValueArray {
+        position_values,
+        quaternion_values,
+        scale_values,
+        float_values,
+        int_values,
+        bool_values,
+    }
+    StateMachineBehaviourVectorDescription {
+        state_machine_behaviour_ranges,
+        state_machine_behaviour_indices,
+    }
+    StateKey { state_id, layer_index }
+    StateRange { start_index, count }
+}
@@ -1,8 +1,22 @@
 mod asset;
+mod asset_serde;
 
 mod book;
 mod bundle;
+mod codegen;
+mod known_types;
+mod layout;
 mod msbt;
+mod references;
+mod registry;
+mod roundtrip;
+mod scene;
+mod scripts;
+mod transfer;
+mod type_tree;
+mod type_tree_builder;
+mod type_tree_serde;
+mod version;
 
 pub use anyhow as error;
 pub use binrw;
@@ -13,14 +27,47 @@ pub use asset::*;
 
 pub use book::*;
 pub use bundle::*;
+pub use layout::{layout_field, Layout, LayoutField, LayoutMismatch, TypeTreeLayout};
 pub use msbt::MessageMap;
+pub use references::*;
+pub use registry::{AssetRegistry, CustomAsset, CustomAssetData, ReadSeek, WriteSeek};
+pub use roundtrip::RoundtripMismatch;
+pub use scene::{Component, SceneGraph, SceneNode};
+pub use scripts::*;
+pub use transfer::*;
+pub use type_tree::{TypeTreeNodeInfo, TypeTreeValue};
+pub use version::UnityVersion;
 
 #[cfg(feature = "atlas")]
 mod atlas;
 
+#[cfg(feature = "atlas")]
+mod palette;
+
+#[cfg(feature = "atlas")]
+mod texture;
+
+#[cfg(feature = "atlas")]
+mod texture_container;
+
+#[cfg(feature = "atlas")]
+mod texture_decode;
+
+#[cfg(feature = "atlas")]
+mod texture_encode;
+
 #[cfg(feature = "atlas")]
 pub use atlas::*;
 
+#[cfg(feature = "atlas")]
+pub use palette::PaletteSprite;
+
+#[cfg(feature = "atlas")]
+pub use texture::{ChannelMode, TextureSwizzle, CUBEMAP_FACES};
+
+#[cfg(feature = "atlas")]
+pub use texture_container::TextureContainer;
+
 #[cfg(feature = "atlas")]
 pub use image;
 
@@ -0,0 +1,198 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::SpriteAtlasWrapper;
+
+/// A unit sprite split into an index sprite, whose red channel picks a palette entry for each
+/// pixel, and a palette sprite. Each palette entry is two pixels wide and each row of the
+/// palette is a separate color scheme.
+#[derive(Debug, Clone)]
+pub struct PaletteSprite {
+    pub index: RgbaImage,
+    pub palette: RgbaImage,
+}
+
+impl PaletteSprite {
+    pub fn new(index: &DynamicImage, palette: &DynamicImage) -> Self {
+        Self {
+            index: index.to_rgba8(),
+            palette: palette.to_rgba8(),
+        }
+    }
+
+    pub fn from_atlases(
+        index_atlas: &SpriteAtlasWrapper,
+        index_name: &str,
+        palette_atlas: &SpriteAtlasWrapper,
+        palette_name: &str,
+    ) -> Result<Self> {
+        let index = index_atlas
+            .get_sprite(index_name)?
+            .ok_or_else(|| anyhow!("atlas does not contain index sprite '{}'", index_name))?;
+        let palette = palette_atlas
+            .get_sprite(palette_name)?
+            .ok_or_else(|| anyhow!("atlas does not contain palette sprite '{}'", palette_name))?;
+        Ok(Self::new(&index, &palette))
+    }
+
+    /// The number of entries in each palette row.
+    pub fn palette_len(&self) -> usize {
+        self.palette.width() as usize / 2
+    }
+
+    pub fn palette_rows(&self) -> u32 {
+        self.palette.height()
+    }
+
+    pub fn palette_row(&self, row: u32) -> Result<Vec<Rgba<u8>>> {
+        self.check_row(row)?;
+        Ok((0..self.palette_len())
+            .map(|entry| *self.palette.get_pixel(entry as u32 * 2, row))
+            .collect())
+    }
+
+    /// Color the index sprite with one row of the palette.
+    pub fn render(&self, row: u32) -> Result<RgbaImage> {
+        let palette = self.palette_row(row)?;
+        let mut output = RgbaImage::new(self.index.width(), self.index.height());
+        for (pixel, index) in output.pixels_mut().zip(self.index.pixels()) {
+            let index = index.0[0] as usize;
+            *pixel = *palette.get(index).ok_or_else(|| {
+                anyhow!(
+                    "index {} is outside the palette ({} entries)",
+                    index,
+                    palette.len()
+                )
+            })?;
+        }
+        Ok(output)
+    }
+
+    /// Replace one palette row, keeping the index sprite. Extra palette entries are left as
+    /// they are.
+    pub fn set_palette_row(&mut self, row: u32, colors: &[Rgba<u8>]) -> Result<()> {
+        self.check_row(row)?;
+        if colors.len() > self.palette_len() {
+            bail!(
+                "palette has {} entries but {} colors were given",
+                self.palette_len(),
+                colors.len()
+            );
+        }
+        for (entry, color) in colors.iter().enumerate() {
+            self.palette.put_pixel(entry as u32 * 2, row, *color);
+            self.palette.put_pixel(entry as u32 * 2 + 1, row, *color);
+        }
+        Ok(())
+    }
+
+    /// Rebuild the index sprite and palette row `row` from an edited render. Colors are
+    /// reduced to the palette size, keeping the most common ones. Other rows are remapped so
+    /// they still color the same regions.
+    ///
+    /// Returns the colors that didn't fit, which were merged into the nearest kept color.
+    pub fn quantize(&mut self, image: &RgbaImage, row: u32) -> Result<Vec<Rgba<u8>>> {
+        self.check_row(row)?;
+        if image.dimensions() != self.index.dimensions() {
+            bail!(
+                "index sprite is {}x{} but the image is {}x{}",
+                self.index.width(),
+                self.index.height(),
+                image.width(),
+                image.height()
+            );
+        }
+        let mut colors = Self::extract_palette(image);
+        // Indices are stored in an 8 bit channel.
+        let merged = colors.split_off(self.palette_len().min(256).min(colors.len()));
+        if colors.is_empty() {
+            bail!("palette sprite has no entries");
+        }
+
+        // Count which old index each new index replaces so the other rows can follow it.
+        let mut replaced: Vec<HashMap<u8, usize>> = vec![HashMap::new(); colors.len()];
+        for (pixel, old) in image.pixels().zip(self.index.pixels_mut()) {
+            let new = nearest_color(&colors, pixel) as u8;
+            *replaced[new as usize].entry(old.0[0]).or_default() += 1;
+            old.0[0] = new;
+        }
+
+        let old_palette = self.palette.clone();
+        for other_row in (0..self.palette_rows()).filter(|r| *r != row) {
+            for (new, counts) in replaced.iter().enumerate() {
+                let Some((old, _)) = counts.iter().max_by_key(|(old, count)| (**count, *old))
+                else {
+                    continue;
+                };
+                if *old as usize >= self.palette_len() {
+                    continue;
+                }
+                let color = *old_palette.get_pixel(*old as u32 * 2, other_row);
+                self.palette.put_pixel(new as u32 * 2, other_row, color);
+                self.palette.put_pixel(new as u32 * 2 + 1, other_row, color);
+            }
+        }
+        self.set_palette_row(row, &colors)?;
+        Ok(merged)
+    }
+
+    /// Write both sprites back into their atlases. Use [`crate::AtlasBundle::replace_textures`]
+    /// to store the atlases in their bundles.
+    pub fn inject(
+        &self,
+        index_atlas: &mut SpriteAtlasWrapper,
+        index_name: &str,
+        palette_atlas: &mut SpriteAtlasWrapper,
+        palette_name: &str,
+    ) -> Result<()> {
+        index_atlas.set_sprite(index_name, &DynamicImage::ImageRgba8(self.index.clone()))?;
+        palette_atlas.set_sprite(
+            palette_name,
+            &DynamicImage::ImageRgba8(self.palette.clone()),
+        )
+    }
+
+    /// The distinct colors of an image, most common first. Ties keep the order colors first
+    /// appear in.
+    pub fn extract_palette(image: &RgbaImage) -> Vec<Rgba<u8>> {
+        let mut counts: Vec<(Rgba<u8>, usize)> = vec![];
+        let mut positions = HashMap::new();
+        for pixel in image.pixels() {
+            let position = *positions.entry(pixel.0).or_insert_with(|| {
+                counts.push((*pixel, 0));
+                counts.len() - 1
+            });
+            counts[position].1 += 1;
+        }
+        // Stable, so ties stay in order of appearance.
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+        counts.into_iter().map(|(color, _)| color).collect()
+    }
+
+    fn check_row(&self, row: u32) -> Result<()> {
+        if row >= self.palette_rows() {
+            bail!(
+                "palette has {} rows but row {} was requested",
+                self.palette_rows(),
+                row
+            );
+        }
+        Ok(())
+    }
+}
+
+fn nearest_color(colors: &[Rgba<u8>], color: &Rgba<u8>) -> usize {
+    (0..colors.len())
+        .min_by_key(|i| {
+            colors[*i]
+                .0
+                .iter()
+                .zip(color.0)
+                .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        })
+        .unwrap()
+}
@@ -0,0 +1,589 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+
+use crate::{
+    AnimationClip, AnimationClipBindingConstant, AnimationClipOverride, AnimationEvent, Animator,
+    AnimatorController, AnimatorOverrideController, Asset, AssetBundle, AssetFile, AssetInfo,
+    Cubemap, GameObject, GenericBinding, Material, MeshFilter, MeshRenderer, MonoBehavior, PPtr,
+    PPtrCurve, RenderDataKey, SecondarySpriteTexture, SkinnedMeshRenderer, SpringBone, SpringJob,
+    Sprite, SpriteAtlas, SpriteAtlasData, SpriteRenderData, TerrainData, TexEnv, Transform,
+    TypeTreeValue, UArray, UString, UnityPropertySheet, Unparsed,
+};
+
+/// Lists every outgoing PPtr held by an asset or one of its fields.
+pub trait AssetReferences {
+    fn references(&self) -> Vec<&PPtr>;
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr>;
+}
+
+impl AssetReferences for PPtr {
+    fn references(&self) -> Vec<&PPtr> {
+        vec![self]
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        vec![self]
+    }
+}
+
+impl<T> AssetReferences for UArray<T>
+where
+    T: AssetReferences + std::fmt::Debug,
+{
+    fn references(&self) -> Vec<&PPtr> {
+        self.items
+            .iter()
+            .flat_map(|item| item.references())
+            .collect()
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        self.items
+            .iter_mut()
+            .flat_map(|item| item.references_mut())
+            .collect()
+    }
+}
+
+impl<K, T: AssetReferences> AssetReferences for (K, T)
+where
+    K: NoReferences,
+{
+    fn references(&self) -> Vec<&PPtr> {
+        self.1.references()
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        self.1.references_mut()
+    }
+}
+
+/// Marker for keys of paired arrays that never hold a PPtr.
+pub trait NoReferences {}
+
+impl NoReferences for UString {}
+
+impl NoReferences for RenderDataKey {}
+
+macro_rules! impl_asset_references {
+    ($($ty:ty => [$($field:ident),*];)*) => {
+        $(
+            impl AssetReferences for $ty {
+                #[allow(unused_mut)]
+                fn references(&self) -> Vec<&PPtr> {
+                    let mut references = vec![];
+                    $(references.extend(self.$field.references());)*
+                    references
+                }
+
+                #[allow(unused_mut)]
+                fn references_mut(&mut self) -> Vec<&mut PPtr> {
+                    let mut references = vec![];
+                    $(references.extend(self.$field.references_mut());)*
+                    references
+                }
+            }
+        )*
+    };
+}
+
+impl_asset_references! {
+    () => [];
+    TerrainData => [];
+    AssetBundle => [preloads, container_map, main_asset];
+    AssetInfo => [asset];
+    GameObject => [component];
+    Transform => [game_object, children, father];
+    Animator => [game_object, avatar, controller];
+    SpriteAtlas => [packed_sprites, render_data_map];
+    SpriteAtlasData => [texture, alpha_texture, secondary_textures];
+    SecondarySpriteTexture => [texture];
+    Sprite => [sprite_atlas, sprite_render_data];
+    SpriteRenderData => [texture, alpha_texture, secondary_textures];
+    Material => [shader, saved_properties];
+    UnityPropertySheet => [text_envs];
+    TexEnv => [texture];
+    MeshFilter => [game_object, mesh];
+    MeshRenderer => [
+        game_object,
+        materials,
+        static_batch_root,
+        probe_anchor,
+        light_probe_volume_override,
+        additional_vertex_streams,
+        enlighten_vertex_stream
+    ];
+    SkinnedMeshRenderer => [
+        game_object,
+        materials,
+        static_batch_root,
+        probe_anchor,
+        light_probe_volume_override,
+        mesh,
+        bones,
+        root_bone
+    ];
+    SpringJob => [sorted_bones, job_colliders];
+    SpringBone => [
+        job_colliders,
+        valid_children,
+        pivot_node,
+        length_limit_targets,
+        sphere_colliders,
+        capsule_colliders,
+        panel_colliders
+    ];
+    AnimationClip => [pptr_curves, clip_binding_constant, events];
+    PPtrCurve => [curve];
+    AnimationClipBindingConstant => [generic_bindings, pptr_curve_mappings];
+    GenericBinding => [script];
+    AnimationEvent => [object_reference_parameter];
+    AnimatorOverrideController => [controller, clips];
+    Cubemap => [source_textures];
+    AnimationClipOverride => [original_clip, override_clip];
+    AnimatorController => [animation_clips, state_machine_behaviours];
+}
+
+impl<T> AssetReferences for MonoBehavior<T>
+where
+    T: AssetReferences + std::fmt::Debug,
+{
+    fn references(&self) -> Vec<&PPtr> {
+        let mut references = vec![&self.game_object, &self.script];
+        references.extend(self.data.references());
+        references
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        let mut references = vec![&mut self.game_object, &mut self.script];
+        references.extend(self.data.references_mut());
+        references
+    }
+}
+
+impl AssetReferences for Asset {
+    fn references(&self) -> Vec<&PPtr> {
+        match self {
+            Asset::Bundle(asset) => asset.references(),
+            Asset::Terrain(asset) => asset.references(),
+            Asset::SpriteAtlas(asset) => asset.references(),
+            Asset::Sprite(asset) => asset.references(),
+            Asset::EmptyMonoBehavior(asset) => asset.references(),
+            Asset::GameObject(asset) => asset.references(),
+            Asset::Animator(asset) => asset.references(),
+            Asset::MeshFilter(asset) => asset.references(),
+            Asset::MeshRenderer(asset) => asset.references(),
+            Asset::Transform(asset) => asset.references(),
+            Asset::Material(asset) => asset.references(),
+            Asset::SkinnedMeshRenderer(asset) => asset.references(),
+            Asset::SpringJob(asset) => asset.references(),
+            Asset::SpringBone(asset) => asset.references(),
+            Asset::AnimationClip(asset) => asset.references(),
+            Asset::AnimatorOverrideController(asset) => asset.references(),
+            Asset::AnimatorController(asset) => asset.references(),
+            Asset::Cubemap(asset) => asset.references(),
+            Asset::Custom(asset) => asset.references(),
+            Asset::Text(_)
+            | Asset::Script(_)
+            | Asset::Texture2D(_, _)
+            | Asset::Texture2DArray(_)
+            | Asset::Texture3D(_)
+            | Asset::Mesh(_)
+            | Asset::Avatar(_)
+            | Asset::Unparsed(_) => vec![],
+        }
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        match self {
+            Asset::Bundle(asset) => asset.references_mut(),
+            Asset::Terrain(asset) => asset.references_mut(),
+            Asset::SpriteAtlas(asset) => asset.references_mut(),
+            Asset::Sprite(asset) => asset.references_mut(),
+            Asset::EmptyMonoBehavior(asset) => asset.references_mut(),
+            Asset::GameObject(asset) => asset.references_mut(),
+            Asset::Animator(asset) => asset.references_mut(),
+            Asset::MeshFilter(asset) => asset.references_mut(),
+            Asset::MeshRenderer(asset) => asset.references_mut(),
+            Asset::Transform(asset) => asset.references_mut(),
+            Asset::Material(asset) => asset.references_mut(),
+            Asset::SkinnedMeshRenderer(asset) => asset.references_mut(),
+            Asset::SpringJob(asset) => asset.references_mut(),
+            Asset::SpringBone(asset) => asset.references_mut(),
+            Asset::AnimationClip(asset) => asset.references_mut(),
+            Asset::AnimatorOverrideController(asset) => asset.references_mut(),
+            Asset::AnimatorController(asset) => asset.references_mut(),
+            Asset::Cubemap(asset) => asset.references_mut(),
+            Asset::Custom(asset) => asset.references_mut(),
+            Asset::Text(_)
+            | Asset::Script(_)
+            | Asset::Texture2D(_, _)
+            | Asset::Texture2DArray(_)
+            | Asset::Texture3D(_)
+            | Asset::Mesh(_)
+            | Asset::Avatar(_)
+            | Asset::Unparsed(_) => vec![],
+        }
+    }
+}
+
+/// Local (file_id 0) reference edges between the assets of a single AssetFile.
+///
+/// The roots are the file's AssetBundles, whose container entries are followed. Files without an
+/// AssetBundle are rooted at their top level GameObjects, the ones whose Transform has no parent,
+/// so an unreferenced Mesh, Material or Texture is an orphan. Use [`ReferenceGraph::with_roots`]
+/// to pick the roots instead.
+///
+/// Unparsed assets are read through their type tree to find their references. Unparsed assets
+/// without a type tree are opaque. They may point at anything, so once one is reachable
+/// everything is.
+#[derive(Debug, Default)]
+pub struct ReferenceGraph {
+    path_ids: Vec<i64>,
+    outgoing: HashMap<i64, Vec<PPtr>>,
+    incoming: HashMap<i64, Vec<i64>>,
+    roots: Vec<i64>,
+    root_edges: HashMap<i64, Vec<i64>>,
+    opaque: HashSet<i64>,
+}
+
+impl ReferenceGraph {
+    pub fn new(asset_file: &AssetFile) -> Self {
+        let mut graph = Self::default();
+        let mut game_objects = vec![];
+        let mut parented = HashSet::new();
+        for (path_id, asset) in asset_file.iter_with_path_ids() {
+            graph.path_ids.push(path_id);
+            let references = match asset {
+                Asset::Unparsed(unparsed) => match unparsed_value(asset_file, unparsed) {
+                    Ok(value) => {
+                        // RectTransforms and other unparsed transforms.
+                        let pptr = |field| value.get(field).and_then(value_pptr);
+                        if let (Some(game_object), Some(father)) =
+                            (pptr("m_GameObject"), pptr("m_Father"))
+                        {
+                            if father.file_id == 0 && father.path_id != 0 {
+                                parented.insert(game_object.path_id);
+                            }
+                        }
+                        let mut references = vec![];
+                        collect_pptrs(&value, &mut references);
+                        references
+                    }
+                    Err(_) => {
+                        graph.opaque.insert(path_id);
+                        vec![]
+                    }
+                },
+                _ => asset.references().into_iter().cloned().collect(),
+            };
+            match asset {
+                Asset::GameObject(_) => game_objects.push(path_id),
+                Asset::Transform(transform)
+                    if transform.father.file_id == 0 && transform.father.path_id != 0 =>
+                {
+                    parented.insert(transform.game_object.path_id);
+                }
+                _ => {}
+            }
+            for pptr in &references {
+                if pptr.file_id == 0 && pptr.path_id != 0 {
+                    graph
+                        .incoming
+                        .entry(pptr.path_id)
+                        .or_default()
+                        .push(path_id);
+                }
+            }
+            graph.outgoing.insert(path_id, references);
+
+            // Preloads list everything a bundle depends on, including objects that are no
+            // longer used. Only follow the container entries when looking for live assets.
+            if let Asset::Bundle(bundle) = asset {
+                graph.roots.push(path_id);
+                graph.root_edges.insert(
+                    path_id,
+                    bundle
+                        .container_map
+                        .iter()
+                        .map(|(_, info)| &info.asset)
+                        .chain(std::iter::once(&bundle.main_asset.asset))
+                        .filter(|pptr| pptr.file_id == 0 && pptr.path_id != 0)
+                        .map(|pptr| pptr.path_id)
+                        .collect(),
+                );
+            }
+        }
+        if graph.roots.is_empty() {
+            graph.roots = game_objects
+                .into_iter()
+                .filter(|path_id| !parented.contains(path_id))
+                .collect();
+        }
+        graph
+    }
+
+    /// Like [`ReferenceGraph::new`], but rooted at `roots`.
+    pub fn with_roots(asset_file: &AssetFile, roots: &[i64]) -> Self {
+        let mut graph = Self::new(asset_file);
+        graph.roots = roots.to_vec();
+        graph
+    }
+
+    pub fn path_ids(&self) -> &[i64] {
+        &self.path_ids
+    }
+
+    pub fn roots(&self) -> &[i64] {
+        &self.roots
+    }
+
+    pub fn references(&self, path_id: i64) -> &[PPtr] {
+        self.outgoing
+            .get(&path_id)
+            .map(|references| references.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn referenced_by(&self, path_id: i64) -> &[i64] {
+        self.incoming
+            .get(&path_id)
+            .map(|referrers| referrers.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Assets whose references couldn't be listed.
+    pub fn opaque(&self) -> &HashSet<i64> {
+        &self.opaque
+    }
+
+    pub fn reachable(&self) -> HashSet<i64> {
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<i64> = self.roots.iter().copied().collect();
+        while let Some(path_id) = queue.pop_front() {
+            if !visited.insert(path_id) || !self.outgoing.contains_key(&path_id) {
+                continue;
+            }
+            if let Some(edges) = self.root_edges.get(&path_id) {
+                queue.extend(edges);
+            } else {
+                queue.extend(
+                    self.references(path_id)
+                        .iter()
+                        .filter(|pptr| pptr.file_id == 0 && pptr.path_id != 0)
+                        .map(|pptr| pptr.path_id),
+                );
+            }
+        }
+        if visited.iter().any(|path_id| self.opaque.contains(path_id)) {
+            return self.path_ids.iter().copied().collect();
+        }
+        visited
+    }
+
+    pub fn orphans(&self) -> Vec<i64> {
+        let reachable = self.reachable();
+        self.path_ids
+            .iter()
+            .filter(|path_id| !reachable.contains(path_id))
+            .copied()
+            .collect()
+    }
+}
+
+impl AssetFile {
+    pub fn reference_graph(&self) -> ReferenceGraph {
+        ReferenceGraph::new(self)
+    }
+
+    /// Remove every asset that can't be reached from the file's roots and drop
+    /// the removed objects from AssetBundle preload tables. Returns the removed path IDs.
+    /// See [`ReferenceGraph`] for which assets are roots. Nothing is removed while an opaque
+    /// asset is reachable.
+    pub fn gc(&mut self) -> Vec<i64> {
+        let orphans = self.reference_graph().orphans();
+        self.remove_orphans(&orphans);
+        orphans
+    }
+
+    /// Like [`AssetFile::gc`], but keep only what can be reached from `roots`.
+    pub fn gc_from(&mut self, roots: &[i64]) -> Vec<i64> {
+        let orphans = ReferenceGraph::with_roots(self, roots).orphans();
+        self.remove_orphans(&orphans);
+        orphans
+    }
+
+    fn remove_orphans(&mut self, orphans: &[i64]) {
+        for path_id in orphans {
+            self.remove_asset(*path_id);
+        }
+        let removed: HashSet<i64> = orphans.iter().copied().collect();
+        for asset in &mut self.assets {
+            if let Asset::Bundle(bundle) = asset {
+                remove_preloads(bundle, &removed);
+            }
+        }
+    }
+
+    /// List `path_id` in the AssetBundle container under `name`, preloading `dependencies`,
+    /// so [`AssetFile::gc`] treats it as a root. Files without an AssetBundle are unchanged.
+    pub fn add_container_entry(&mut self, name: &str, path_id: i64, dependencies: &[i64]) {
+        let Some(bundle) = self.assets.iter_mut().find_map(|asset| match asset {
+            Asset::Bundle(bundle) => Some(bundle),
+            _ => None,
+        }) else {
+            return;
+        };
+        let preload_index = bundle.preloads.items.len() as u32;
+        bundle
+            .preloads
+            .items
+            .extend(dependencies.iter().map(|path_id| PPtr {
+                file_id: 0,
+                path_id: *path_id,
+            }));
+        bundle.container_map.items.push((
+            UString(name.to_owned()),
+            AssetInfo {
+                preload_index,
+                preload_size: dependencies.len() as u32,
+                asset: PPtr {
+                    file_id: 0,
+                    path_id,
+                },
+            },
+        ));
+    }
+}
+
+fn remove_preloads(bundle: &mut AssetBundle, removed: &HashSet<i64>) {
+    let keep = bundle
+        .preloads
+        .iter()
+        .map(|pptr| !(pptr.file_id == 0 && removed.contains(&pptr.path_id)))
+        .collect::<Vec<_>>();
+    if keep.iter().all(|keep| *keep) {
+        return;
+    }
+
+    // new_index[i] is the number of kept preloads before i, so ranges can be remapped
+    // even when they overlap.
+    let mut new_index = Vec::with_capacity(keep.len() + 1);
+    let mut kept = 0;
+    for keep in &keep {
+        new_index.push(kept);
+        if *keep {
+            kept += 1;
+        }
+    }
+    new_index.push(kept);
+
+    let remap = |info: &mut AssetInfo| {
+        let start = (info.preload_index as usize).min(keep.len());
+        let end = (start + info.preload_size as usize).min(keep.len());
+        info.preload_index = new_index[start] as u32;
+        info.preload_size = (new_index[end] - new_index[start]) as u32;
+    };
+    for (_, info) in bundle.container_map.iter_mut() {
+        remap(info);
+    }
+    remap(&mut bundle.main_asset);
+
+    let mut keep = keep.into_iter();
+    bundle.preloads.retain(|_| keep.next().unwrap_or(true));
+}
+
+/// Read an unparsed asset through the file's type tree for it.
+pub(crate) fn unparsed_value(asset_file: &AssetFile, unparsed: &Unparsed) -> Result<TypeTreeValue> {
+    let ty = asset_file
+        .get_type(unparsed.type_hash)
+        .filter(|ty| !ty.type_tree.nodes.is_empty())
+        .ok_or_else(|| anyhow!("no type tree for hash '{}'", unparsed.type_hash))?;
+    unparsed.read_value(&ty.type_tree)
+}
+
+fn value_pptr(value: &TypeTreeValue) -> Option<PPtr> {
+    match value {
+        TypeTreeValue::Struct(fields) => pptr_fields(fields),
+        _ => None,
+    }
+}
+
+fn pptr_fields(fields: &IndexMap<String, TypeTreeValue>) -> Option<PPtr> {
+    match (
+        fields.len(),
+        fields.get("m_FileID").and_then(|value| value.as_i64()),
+        fields.get("m_PathID").and_then(|value| value.as_i64()),
+    ) {
+        (2, Some(file_id), Some(path_id)) => Some(PPtr {
+            file_id: file_id as i32,
+            path_id,
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn collect_pptrs(value: &TypeTreeValue, references: &mut Vec<PPtr>) {
+    match value {
+        TypeTreeValue::Struct(fields) => {
+            if let Some(pptr) = pptr_fields(fields) {
+                references.push(pptr);
+                return;
+            }
+            for field in fields.values() {
+                collect_pptrs(field, references);
+            }
+        }
+        TypeTreeValue::Array(items) => {
+            for item in items {
+                collect_pptrs(item, references);
+            }
+        }
+        TypeTreeValue::Map(entries) => {
+            for (key, value) in entries {
+                collect_pptrs(key, references);
+                collect_pptrs(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace every PPtr in `value` with the result of `remap`.
+pub(crate) fn remap_pptrs(
+    value: &mut TypeTreeValue,
+    remap: &mut dyn FnMut(&PPtr) -> Result<PPtr>,
+) -> Result<()> {
+    match value {
+        TypeTreeValue::Struct(fields) => {
+            if let Some(pptr) = pptr_fields(fields) {
+                let pptr = remap(&pptr)?;
+                fields.insert(
+                    String::from("m_FileID"),
+                    TypeTreeValue::Int(pptr.file_id as i64),
+                );
+                fields.insert(String::from("m_PathID"), TypeTreeValue::Int(pptr.path_id));
+                return Ok(());
+            }
+            for field in fields.values_mut() {
+                remap_pptrs(field, remap)?;
+            }
+        }
+        TypeTreeValue::Array(items) => {
+            for item in items {
+                remap_pptrs(item, remap)?;
+            }
+        }
+        TypeTreeValue::Map(entries) => {
+            for (key, value) in entries {
+                remap_pptrs(key, remap)?;
+                remap_pptrs(value, remap)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, Endian};
+
+use crate::{Asset, AssetFile, AssetReferences, Bundle, BundleFile, PPtr, MONO_BEHAVIOR_CLASS_ID};
+
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+pub trait WriteSeek: Write + Seek {}
+
+impl<T: Write + Seek> WriteSeek for T {}
+
+/// Object safe view of a type registered with an [AssetRegistry]. Implemented for every type
+/// that can be registered. Types list their PPtrs through [AssetReferences] so reference
+/// graphs and imports can follow them.
+pub trait CustomAssetData: AssetReferences + std::fmt::Debug + Send + Sync {
+    fn write_dyn(&self, writer: &mut dyn WriteSeek, endian: Endian) -> BinResult<()>;
+
+    fn clone_box(&self) -> Box<dyn CustomAssetData>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T> CustomAssetData for T
+where
+    T: for<'a> BinWrite<Args<'a> = ()>
+        + AssetReferences
+        + std::fmt::Debug
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    fn write_dyn(&self, mut writer: &mut dyn WriteSeek, endian: Endian) -> BinResult<()> {
+        self.write_options(&mut writer, endian, ())
+    }
+
+    fn clone_box(&self) -> Box<dyn CustomAssetData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct CustomAsset {
+    pub type_hash: i128,
+    pub data: Box<dyn CustomAssetData>,
+    /// The script of a MonoBehaviour, read from its header when it was parsed.
+    pub script: Option<PPtr>,
+}
+
+impl CustomAsset {
+    pub fn new<T: CustomAssetData + 'static>(type_hash: i128, data: T) -> Self {
+        Self {
+            type_hash,
+            data: Box::new(data),
+            script: None,
+        }
+    }
+
+    pub fn with_script(mut self, script: PPtr) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.data.as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.data.as_any_mut().downcast_mut()
+    }
+}
+
+impl Clone for CustomAsset {
+    fn clone(&self) -> Self {
+        Self {
+            type_hash: self.type_hash,
+            data: self.data.clone_box(),
+            script: self.script.clone(),
+        }
+    }
+}
+
+impl AssetReferences for CustomAsset {
+    fn references(&self) -> Vec<&PPtr> {
+        self.data.references()
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        self.data.references_mut()
+    }
+}
+
+impl BinWrite for CustomAsset {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _: Self::Args<'_>,
+    ) -> BinResult<()> {
+        self.data.write_dyn(writer, endian)
+    }
+}
+
+type ReadFn = fn(&mut dyn ReadSeek, Endian) -> BinResult<Box<dyn CustomAssetData>>;
+
+/// Types to parse objects as when the crate leaves them [`crate::Unparsed`]. Read files with
+/// [`AssetFile::read_with_registry`] or [`Bundle::from_slice_with_registry`], or apply it to a
+/// file that was already read with [`AssetRegistry::apply`]. Parsed objects are written back
+/// from their registered type.
+#[derive(Debug, Default, Clone)]
+pub struct AssetRegistry {
+    by_type_hash: HashMap<i128, ReadFn>,
+    by_class_name: HashMap<String, ReadFn>,
+}
+
+impl AssetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse every object with the given type hash as `T`.
+    pub fn register_asset_type<T>(&mut self, type_hash: i128)
+    where
+        T: for<'a> BinRead<Args<'a> = ()> + CustomAssetData + 'static,
+    {
+        self.by_type_hash.insert(type_hash, read_custom::<T>);
+    }
+
+    /// Parse every MonoBehaviour whose script has the given class name as `T`.
+    /// The name can be qualified with the script's namespace (ex. "Game.SpringCollider").
+    /// Only scripts that live in the same AssetFile as the MonoBehaviour can be matched.
+    pub fn register_mono_behavior_class<T>(&mut self, class_name: &str)
+    where
+        T: for<'a> BinRead<Args<'a> = ()> + CustomAssetData + 'static,
+    {
+        self.by_class_name
+            .insert(class_name.to_string(), read_custom::<T>);
+    }
+
+    pub fn unregister_asset_type(&mut self, type_hash: i128) {
+        self.by_type_hash.remove(&type_hash);
+    }
+
+    pub fn unregister_mono_behavior_class(&mut self, class_name: &str) {
+        self.by_class_name.remove(class_name);
+    }
+
+    /// Parse every Unparsed object whose type or MonoBehaviour class was registered.
+    /// Objects that fail to parse, or don't use all of their data, stay Unparsed and
+    /// their path IDs are returned.
+    pub fn apply(&self, asset_file: &mut AssetFile) -> Vec<i64> {
+        // Scripts can appear after their MonoBehaviours, so collect them first.
+        let class_names: HashMap<i64, (String, String)> = asset_file
+            .iter_with_path_ids()
+            .filter_map(|(path_id, asset)| match asset {
+                Asset::Script(script) => Some((
+                    path_id,
+                    (script.namespace.0.clone(), script.class_name.0.clone()),
+                )),
+                _ => None,
+            })
+            .collect();
+        let mono_behavior_hashes: HashSet<i128> = asset_file
+            .types
+            .iter()
+            .filter(|ty| ty.class_id == MONO_BEHAVIOR_CLASS_ID)
+            .map(|ty| ty.type_hash)
+            .collect();
+        let path_ids: Vec<i64> = asset_file
+            .iter_with_path_ids()
+            .map(|(path_id, _)| path_id)
+            .collect();
+
+        let mut failed = vec![];
+        for (path_id, asset) in path_ids.into_iter().zip(asset_file.assets.iter_mut()) {
+            let Asset::Unparsed(unparsed) = asset else {
+                continue;
+            };
+            let is_mono_behavior = mono_behavior_hashes.contains(&unparsed.type_hash);
+            let read = match self.by_type_hash.get(&unparsed.type_hash) {
+                Some(read) => Some(read),
+                None if is_mono_behavior => self.class_reader(&unparsed.blob, &class_names),
+                None => None,
+            };
+            let Some(read) = read else {
+                continue;
+            };
+            let mut cursor = Cursor::new(&unparsed.blob);
+            match read(&mut cursor, Endian::Little) {
+                Ok(data) if cursor.position() == unparsed.blob.len() as u64 => {
+                    let script = is_mono_behavior
+                        .then(|| read_script_pptr(&mut Cursor::new(&unparsed.blob), Endian::Little))
+                        .and_then(|script| script.ok());
+                    *asset = Asset::Custom(CustomAsset {
+                        type_hash: unparsed.type_hash,
+                        data,
+                        script,
+                    });
+                }
+                _ => failed.push(path_id),
+            }
+        }
+        failed
+    }
+
+    fn class_reader(
+        &self,
+        blob: &[u8],
+        class_names: &HashMap<i64, (String, String)>,
+    ) -> Option<&ReadFn> {
+        if self.by_class_name.is_empty() {
+            return None;
+        }
+        let script = read_script_pptr(&mut Cursor::new(blob), Endian::Little).ok()?;
+        if script.file_id != 0 {
+            return None;
+        }
+        let (namespace, class_name) = class_names.get(&script.path_id)?;
+        let qualified_name = format!("{}.{}", namespace, class_name);
+        self.by_class_name
+            .get(&qualified_name)
+            .or_else(|| self.by_class_name.get(class_name))
+    }
+}
+
+impl AssetFile {
+    /// Read a file and parse its objects with `registry`. Objects that fail to parse stay
+    /// Unparsed, like with [`AssetRegistry::apply`].
+    pub fn read_with_registry<R: Read + Seek>(
+        reader: &mut R,
+        registry: &AssetRegistry,
+    ) -> Result<Self> {
+        let mut asset_file: AssetFile = reader.read_le()?;
+        registry.apply(&mut asset_file);
+        Ok(asset_file)
+    }
+}
+
+impl Bundle {
+    pub fn load_with_registry<T: AsRef<Path>>(path: T, registry: &AssetRegistry) -> Result<Self> {
+        Self::from_slice_with_registry(&std::fs::read(path)?, registry)
+    }
+
+    /// Read a bundle and parse the objects of each of its assets files with `registry`.
+    pub fn from_slice_with_registry(raw_bundle: &[u8], registry: &AssetRegistry) -> Result<Self> {
+        let mut bundle = Self::from_slice(raw_bundle)?;
+        for file in bundle.files.values_mut() {
+            if let BundleFile::Assets(asset_file) = file {
+                registry.apply(asset_file);
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+fn read_custom<T>(
+    mut reader: &mut dyn ReadSeek,
+    endian: Endian,
+) -> BinResult<Box<dyn CustomAssetData>>
+where
+    T: for<'a> BinRead<Args<'a> = ()> + CustomAssetData + 'static,
+{
+    T::read_options(&mut reader, endian, ()).map(|data| Box::new(data) as Box<dyn CustomAssetData>)
+}
+
+pub(crate) fn read_script_pptr<R: Read + Seek>(reader: &mut R, endian: Endian) -> BinResult<PPtr> {
+    let _game_object = PPtr::read_options(reader, endian, ())?;
+    let _enabled = u8::read_options(reader, endian, ())?;
+    PPtr::read_options(reader, endian, ())
+}
@@ -0,0 +1,298 @@
+use std::fmt::Display;
+use std::io::Cursor;
+
+use anyhow::Result;
+use binrw::{binread, BinRead, BinWrite, Endian};
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::asset_serde::non_finite;
+use crate::{
+    Asset, AssetFile, AssetFileHeader, AssetFileObject, AssetFileType, AssetReadOptions,
+    UnityVersion, GAME_OBJECT_HASH,
+};
+
+/// The first difference found when writing a file and reading it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundtripMismatch {
+    /// Bundle node the mismatch was found in, if the check ran on a bundle.
+    pub file: Option<String>,
+    /// Path ID of the differing object, or None if the mismatch is outside the object data.
+    pub path_id: Option<i64>,
+    /// Field path (ex. "component[0].path_id") or a byte offset when no field differs.
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for RoundtripMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file)?;
+        }
+        if let Some(path_id) = self.path_id {
+            write!(f, "object {}: ", path_id)?;
+        }
+        write!(
+            f,
+            "{} differs (expected '{}', got '{}')",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+// Just enough of the file to locate each object's original bytes.
+#[binread]
+#[br(little)]
+struct AssetFileLayout {
+    #[brw(big)]
+    header: AssetFileHeader,
+    #[br(temp)]
+    type_count: u32,
+    #[br(count = type_count)]
+    types: Vec<AssetFileType>,
+    #[br(align_after = 4, temp)]
+    object_count: u32,
+    #[br(count = object_count)]
+    objects: Vec<AssetFileObject>,
+}
+
+impl AssetFile {
+    /// Write the file, read it back and compare both the parsed objects and the raw bytes.
+    pub fn roundtrip_check(&self) -> Result<Option<RoundtripMismatch>> {
+        let mut cursor = Cursor::new(vec![]);
+        self.write_le(&mut cursor)?;
+        let raw = cursor.into_inner();
+        let reread = AssetFile::read_le(&mut Cursor::new(&raw))?;
+        if self.assets.len() != reread.assets.len() {
+            return Ok(Some(RoundtripMismatch {
+                file: None,
+                path_id: None,
+                field: String::from("assets.len()"),
+                expected: self.assets.len().to_string(),
+                actual: reread.assets.len().to_string(),
+            }));
+        }
+        for ((path_id, expected), actual) in self.iter_with_path_ids().zip(&reread.assets) {
+            if let Some((field, expected, actual)) = diff_assets(expected, actual) {
+                return Ok(Some(RoundtripMismatch {
+                    file: None,
+                    path_id: Some(path_id),
+                    field,
+                    expected,
+                    actual,
+                }));
+            }
+        }
+        AssetFile::roundtrip_check_bytes(&raw)
+    }
+
+    /// Read a serialized file, write it back and compare against the original bytes.
+    pub fn roundtrip_check_bytes(raw: &[u8]) -> Result<Option<RoundtripMismatch>> {
+        let asset_file = AssetFile::read_le(&mut Cursor::new(raw))?;
+        let layout = AssetFileLayout::read(&mut Cursor::new(raw))?;
+        let data_offset = layout.header.data_offset as usize;
+        let sorted_objects = layout
+            .objects
+            .iter()
+            .sorted_by_key(|obj| obj.offset)
+            .collect_vec();
+        for (obj, asset) in sorted_objects.into_iter().zip(&asset_file.assets) {
+            let start = data_offset + obj.offset as usize;
+            let expected = raw
+                .get(start..start + obj.size as usize)
+                .unwrap_or_default();
+            if let Some(mismatch) = check_object(
+                obj,
+                asset,
+                expected,
+                &layout.types,
+                asset_file.unity_version(),
+            )? {
+                return Ok(Some(mismatch));
+            }
+        }
+
+        let mut cursor = Cursor::new(vec![]);
+        asset_file.write_le(&mut cursor)?;
+        let written = cursor.into_inner();
+        Ok(
+            diff_bytes(raw, &written).map(|(offset, expected, actual)| RoundtripMismatch {
+                file: None,
+                path_id: None,
+                field: if offset < data_offset {
+                    format!("meta data at 0x{:X}", offset)
+                } else {
+                    format!("data at 0x{:X}", offset)
+                },
+                expected,
+                actual,
+            }),
+        )
+    }
+}
+
+fn check_object(
+    obj: &AssetFileObject,
+    asset: &Asset,
+    expected: &[u8],
+    types: &[AssetFileType],
+    version: UnityVersion,
+) -> Result<Option<RoundtripMismatch>> {
+    let mut cursor = Cursor::new(vec![]);
+    asset.write_options(&mut cursor, Endian::Little, (version,))?;
+    let mut actual = cursor.into_inner();
+    // The object table counts trailing padding for everything except GameObjects.
+    if asset.type_hash() != GAME_OBJECT_HASH {
+        while actual.len() % 4 != 0 {
+            actual.push(0);
+        }
+    }
+    let Some((offset, expected_bytes, actual_bytes)) = diff_bytes(expected, &actual) else {
+        return Ok(None);
+    };
+
+    // Parse what was written so the difference can be pinned to a field.
+    let (class_id, type_hash) = types
+        .get(obj.type_id as usize)
+        .map(|ty| (ty.class_id, ty.type_hash))
+        .unwrap_or_else(|| (asset.class_id().unwrap_or_default(), asset.type_hash()));
+    let reread = Asset::read_options(
+        &mut Cursor::new(&actual),
+        Endian::Little,
+        AssetReadOptions {
+            size: actual.len(),
+            class_id,
+            type_hash,
+            pptr: obj.path_id,
+            version,
+        },
+    );
+    let field_mismatch = reread.ok().and_then(|reread| diff_assets(asset, &reread));
+    let (field, expected, actual) = field_mismatch.unwrap_or((
+        format!("byte 0x{:X} (padding or data the parser skipped)", offset),
+        expected_bytes,
+        actual_bytes,
+    ));
+    Ok(Some(RoundtripMismatch {
+        file: None,
+        path_id: Some(obj.path_id as i64),
+        field,
+        expected,
+        actual,
+    }))
+}
+
+pub(crate) fn diff_bytes(expected: &[u8], actual: &[u8]) -> Option<(usize, String, String)> {
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(a, b)| a != b)
+        .or_else(|| (expected.len() != actual.len()).then(|| expected.len().min(actual.len())))?;
+    let show = |bytes: &[u8]| {
+        if offset >= bytes.len() {
+            format!("end of data (len 0x{:X})", bytes.len())
+        } else {
+            bytes[offset..(offset + 8).min(bytes.len())]
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .join(" ")
+        }
+    };
+    Some((offset, show(expected), show(actual)))
+}
+
+/// Serialize both assets and return the path of the first differing field, or None if they
+/// match or can't be serialized (custom assets).
+fn diff_assets(expected: &Asset, actual: &Asset) -> Option<(String, String, String)> {
+    // Going through JSON text keeps integers that don't fit in a Value::Number readable.
+    let to_value = |asset: &Asset| {
+        let text = serde_json::to_string(&non_finite::Finite(asset)).ok()?;
+        match serde_json::from_str(&text).ok()? {
+            // Leave out the variant name so paths start at the asset's own fields.
+            Value::Object(map) if map.len() == 1 => map.into_iter().next().map(|(_, v)| v),
+            value => Some(value),
+        }
+    };
+    let expected = to_value(expected)?;
+    let actual = to_value(actual)?;
+    let mut path = String::new();
+    diff_values(&mut path, Some(&expected), Some(&actual))
+}
+
+fn diff_values(
+    path: &mut String,
+    expected: Option<&Value>,
+    actual: Option<&Value>,
+) -> Option<(String, String, String)> {
+    match (expected, actual) {
+        (Some(Value::Object(expected)), Some(Value::Object(actual))) => {
+            let keys = expected
+                .keys()
+                .chain(actual.keys().filter(|key| !expected.contains_key(*key)));
+            for key in keys {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                if let Some(diff) = diff_values(path, expected.get(key), actual.get(key)) {
+                    return Some(diff);
+                }
+                path.truncate(len);
+            }
+            None
+        }
+        (Some(Value::Array(expected)), Some(Value::Array(actual))) => {
+            for i in 0..expected.len().max(actual.len()) {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                if let Some(diff) = diff_values(path, expected.get(i), actual.get(i)) {
+                    return Some(diff);
+                }
+                path.truncate(len);
+            }
+            None
+        }
+        (expected, actual) if expected != actual => {
+            let (expected, actual) = show_values(expected, actual);
+            Some((path.clone(), expected, actual))
+        }
+        _ => None,
+    }
+}
+
+// Long strings (ex. hex encoded blobs) are shown from the first differing character.
+fn show_values(expected: Option<&Value>, actual: Option<&Value>) -> (String, String) {
+    const MAX_LEN: usize = 32;
+    let show = |value: Option<&Value>| match value {
+        None => String::from("missing"),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+    let (expected, actual) = (show(expected), show(actual));
+    let skip = expected
+        .chars()
+        .zip(actual.chars())
+        .take_while(|(a, b)| a == b)
+        .count()
+        .saturating_sub(MAX_LEN / 2);
+    let window = |text: &str| {
+        let mut shown: String = text.chars().skip(skip).take(MAX_LEN).collect();
+        if skip > 0 {
+            shown.insert_str(0, "...");
+        }
+        if text.chars().count() > skip + MAX_LEN {
+            shown.push_str("...");
+        }
+        shown
+    };
+    (window(&expected), window(&actual))
+}
+
+pub(crate) fn with_file(mismatch: RoundtripMismatch, file: &str) -> RoundtripMismatch {
+    RoundtripMismatch {
+        file: Some(file.to_string()),
+        ..mismatch
+    }
+}
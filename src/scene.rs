@@ -0,0 +1,519 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Mul;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    Animator, Asset, AssetFile, AssetFileType, GameObject, Matrix4x4f, MeshFilter, MeshRenderer,
+    MonoBehavior, PPtr, Quaternionf, SkinnedMeshRenderer, SpringBone, SpringJob, TerrainData,
+    Transform, UArray, UString, Vector3f, GAME_OBJECT_HASH, TRANSFORM_HASH,
+};
+
+/// An asset that can be attached to a GameObject. Registered custom types can implement this
+/// through [crate::CustomAsset::downcast_ref] to be fetched with [SceneNode::component].
+pub trait Component {
+    fn from_asset(asset: &Asset) -> Option<&Self>;
+
+    fn from_asset_mut(asset: &mut Asset) -> Option<&mut Self>;
+}
+
+macro_rules! impl_component {
+    ($($ty:ty => $variant:ident;)*) => {
+        $(
+            impl Component for $ty {
+                fn from_asset(asset: &Asset) -> Option<&Self> {
+                    match asset {
+                        Asset::$variant(component) => Some(component),
+                        _ => None,
+                    }
+                }
+
+                fn from_asset_mut(asset: &mut Asset) -> Option<&mut Self> {
+                    match asset {
+                        Asset::$variant(component) => Some(component),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_component! {
+    Transform => Transform;
+    Animator => Animator;
+    MeshFilter => MeshFilter;
+    MeshRenderer => MeshRenderer;
+    SkinnedMeshRenderer => SkinnedMeshRenderer;
+    MonoBehavior<()> => EmptyMonoBehavior;
+    MonoBehavior<TerrainData> => Terrain;
+    MonoBehavior<SpringJob> => SpringJob;
+    MonoBehavior<SpringBone> => SpringBone;
+}
+
+/// The GameObject hierarchy of an AssetFile. Only local (file_id 0) pointers are followed.
+#[derive(Debug)]
+pub struct SceneGraph<'a> {
+    asset_file: &'a AssetFile,
+    transforms: HashMap<i64, i64>,
+    roots: Vec<i64>,
+}
+
+impl<'a> SceneGraph<'a> {
+    pub fn new(asset_file: &'a AssetFile) -> Self {
+        let mut transforms = HashMap::new();
+        let mut roots = vec![];
+        for (path_id, asset) in asset_file.iter_with_path_ids() {
+            if let Asset::Transform(transform) = asset {
+                if let Some(game_object) = local(&transform.game_object) {
+                    transforms.insert(game_object, path_id);
+                    if local(&transform.father).is_none() {
+                        roots.push(game_object);
+                    }
+                }
+            }
+        }
+        Self {
+            asset_file,
+            transforms,
+            roots,
+        }
+    }
+
+    /// GameObjects without a parent.
+    pub fn roots(&self) -> Vec<SceneNode<'a>> {
+        self.roots
+            .iter()
+            .filter_map(|path_id| self.node(*path_id))
+            .collect()
+    }
+
+    /// Look up a GameObject by path ID. GameObjects without a Transform are not part of the graph.
+    pub fn node(&self, path_id: i64) -> Option<SceneNode<'a>> {
+        let transform_path_id = *self.transforms.get(&path_id)?;
+        match (
+            self.asset_file.get_asset_by_path_id(path_id),
+            self.asset_file.get_asset_by_path_id(transform_path_id),
+        ) {
+            (Some(Asset::GameObject(game_object)), Some(Asset::Transform(transform))) => {
+                Some(SceneNode {
+                    asset_file: self.asset_file,
+                    path_id,
+                    transform_path_id,
+                    game_object,
+                    transform,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Look up a GameObject by its full path (ex. "c_root/c_hips").
+    pub fn find(&self, path: &str) -> Option<SceneNode<'a>> {
+        let mut names = path.split('/');
+        let first = names.next()?;
+        let mut node = self.roots().into_iter().find(|node| node.name() == first)?;
+        for name in names {
+            node = node
+                .children()
+                .into_iter()
+                .find(|node| node.name() == name)?;
+        }
+        Some(node)
+    }
+
+    /// Every GameObject in the graph, parents before their children.
+    pub fn iter(&self) -> Vec<SceneNode<'a>> {
+        let mut nodes = vec![];
+        let mut seen = HashSet::new();
+        let mut stack: Vec<SceneNode> = self.roots().into_iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            // Broken files can list a Transform as a child of itself or of its descendants.
+            if !seen.insert(node.transform_path_id) {
+                continue;
+            }
+            stack.extend(node.children().into_iter().rev());
+            nodes.push(node);
+        }
+        nodes
+    }
+}
+
+/// A GameObject in a [SceneGraph] along with its Transform.
+#[derive(Debug, Clone, Copy)]
+pub struct SceneNode<'a> {
+    asset_file: &'a AssetFile,
+    pub path_id: i64,
+    pub transform_path_id: i64,
+    pub game_object: &'a GameObject,
+    pub transform: &'a Transform,
+}
+
+impl<'a> SceneNode<'a> {
+    pub fn name(&self) -> &'a str {
+        &self.game_object.name.0
+    }
+
+    pub fn parent(&self) -> Option<SceneNode<'a>> {
+        let path_id = local(&self.transform.father)?;
+        match self.asset_file.get_asset_by_path_id(path_id) {
+            Some(Asset::Transform(transform)) => self.with_transform(path_id, transform),
+            _ => None,
+        }
+    }
+
+    pub fn children(&self) -> Vec<SceneNode<'a>> {
+        self.transform
+            .children
+            .iter()
+            .filter_map(local)
+            .filter_map(
+                |path_id| match self.asset_file.get_asset_by_path_id(path_id) {
+                    Some(Asset::Transform(transform)) => self.with_transform(path_id, transform),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Parents of this GameObject, nearest first. Stops where the hierarchy loops back on itself.
+    pub fn ancestors(&self) -> Vec<SceneNode<'a>> {
+        let mut seen = HashSet::from([self.transform_path_id]);
+        let mut ancestors = vec![];
+        let mut current = self.parent();
+        while let Some(node) = current {
+            if !seen.insert(node.transform_path_id) {
+                break;
+            }
+            current = node.parent();
+            ancestors.push(node);
+        }
+        ancestors
+    }
+
+    /// Names from the root down to this GameObject joined with '/' (ex. "c_root/c_hips").
+    pub fn path(&self) -> String {
+        let mut names = vec![self.name()];
+        names.extend(self.ancestors().iter().map(|node| node.name()));
+        names.reverse();
+        names.join("/")
+    }
+
+    /// The first component of the given type.
+    pub fn component<T: Component>(&self) -> Option<&'a T> {
+        self.components().into_iter().next()
+    }
+
+    pub fn components<T: Component>(&self) -> Vec<&'a T> {
+        self.game_object
+            .component
+            .iter()
+            .filter_map(local)
+            .filter_map(|path_id| self.asset_file.get_asset_by_path_id(path_id))
+            .filter_map(T::from_asset)
+            .collect()
+    }
+
+    /// Path ID of the first component of the given type.
+    pub fn component_path_id<T: Component>(&self) -> Option<i64> {
+        self.game_object
+            .component
+            .iter()
+            .filter_map(local)
+            .find(|path_id| {
+                self.asset_file
+                    .get_asset_by_path_id(*path_id)
+                    .and_then(T::from_asset)
+                    .is_some()
+            })
+    }
+
+    pub fn local_matrix(&self) -> Matrix4x4f {
+        Matrix4x4f::from_trs(
+            &self.transform.local_position,
+            &self.transform.local_rotation,
+            &self.transform.local_scale,
+        )
+    }
+
+    pub fn world_matrix(&self) -> Matrix4x4f {
+        self.ancestors()
+            .iter()
+            .fold(self.local_matrix(), |matrix, parent| {
+                &parent.local_matrix() * &matrix
+            })
+    }
+
+    fn with_transform(&self, path_id: i64, transform: &'a Transform) -> Option<SceneNode<'a>> {
+        let game_object_path_id = local(&transform.game_object)?;
+        match self.asset_file.get_asset_by_path_id(game_object_path_id) {
+            Some(Asset::GameObject(game_object)) => Some(SceneNode {
+                asset_file: self.asset_file,
+                path_id: game_object_path_id,
+                transform_path_id: path_id,
+                game_object,
+                transform,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl AssetFile {
+    pub fn scene_graph(&self) -> SceneGraph<'_> {
+        SceneGraph::new(self)
+    }
+
+    /// Move a GameObject under `parent`, or to the root when `parent` is None.
+    /// The local position, rotation and scale are kept, so the object moves along with its new parent.
+    pub fn reparent(&mut self, path_id: i64, parent: Option<i64>) -> Result<()> {
+        let graph = self.scene_graph();
+        let node = graph
+            .node(path_id)
+            .ok_or_else(|| anyhow!("path ID '{}' is not a GameObject with a Transform", path_id))?;
+        let transform_path_id = node.transform_path_id;
+        let old_parent = local(&node.transform.father);
+        let new_parent = match parent {
+            Some(parent) => {
+                let parent = graph.node(parent).ok_or_else(|| {
+                    anyhow!("path ID '{}' is not a GameObject with a Transform", parent)
+                })?;
+                if parent.path_id == path_id
+                    || parent
+                        .ancestors()
+                        .iter()
+                        .any(|node| node.path_id == path_id)
+                {
+                    bail!(
+                        "cannot parent GameObject '{}' to its own descendant",
+                        path_id
+                    );
+                }
+                Some(parent.transform_path_id)
+            }
+            None => None,
+        };
+
+        if let Some(old_parent) = old_parent {
+            if let Some(Asset::Transform(transform)) = self.get_asset_by_path_id_mut(old_parent) {
+                transform
+                    .children
+                    .retain(|child| local(child) != Some(transform_path_id));
+            }
+        }
+        if let Some(new_parent) = new_parent {
+            if let Some(Asset::Transform(transform)) = self.get_asset_by_path_id_mut(new_parent) {
+                transform.children.push(local_pptr(transform_path_id));
+            }
+        }
+        if let Some(Asset::Transform(transform)) = self.get_asset_by_path_id_mut(transform_path_id)
+        {
+            transform.father = new_parent.map(local_pptr).unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    /// Add an active GameObject with an identity Transform under `parent` (or at the root).
+    /// Missing GameObject and Transform types are taken from [AssetFileType::known].
+    /// The object is listed in the AssetBundle container under its scene path (ex. "c_root/c_new")
+    /// so [AssetFile::gc] keeps it. Returns the path ID of the new GameObject.
+    pub fn add_game_object(&mut self, name: &str, parent: Option<i64>) -> Result<i64> {
+        let (layer, parent_transform) = match parent {
+            Some(parent) => {
+                let node = self.scene_graph().node(parent).ok_or_else(|| {
+                    anyhow!("path ID '{}' is not a GameObject with a Transform", parent)
+                })?;
+                (node.game_object.layer, Some(node.transform_path_id))
+            }
+            None => (0, None),
+        };
+        for (class_id, type_hash) in [(1, GAME_OBJECT_HASH), (4, TRANSFORM_HASH)] {
+            if !self.types.iter().any(|ty| ty.class_id == class_id) {
+                let ty = AssetFileType::known(type_hash)
+                    .ok_or_else(|| anyhow!("no known type for hash '{}'", type_hash))?;
+                self.add_type(ty);
+            }
+        }
+
+        let mut next_path_id = self
+            .path_ids
+            .iter()
+            .map(|path_id| *path_id as i64)
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let game_object_path_id = next_path_id;
+        next_path_id += 1;
+        let transform_path_id = next_path_id;
+
+        self.insert_asset(
+            game_object_path_id,
+            Asset::GameObject(GameObject {
+                component: UArray {
+                    items: vec![local_pptr(transform_path_id)],
+                },
+                layer,
+                name: UString(name.to_string()),
+                tag: 0,
+                is_active: 1,
+            }),
+        )?;
+        self.insert_asset(
+            transform_path_id,
+            Asset::Transform(Transform {
+                game_object: local_pptr(game_object_path_id),
+                local_rotation: Quaternionf {
+                    x: 0.,
+                    y: 0.,
+                    z: 0.,
+                    w: 1.,
+                },
+                local_position: Vector3f {
+                    x: 0.,
+                    y: 0.,
+                    z: 0.,
+                },
+                local_scale: Vector3f {
+                    x: 1.,
+                    y: 1.,
+                    z: 1.,
+                },
+                children: UArray::default(),
+                father: parent_transform.map(local_pptr).unwrap_or_default(),
+            }),
+        )?;
+        if let Some(parent_transform) = parent_transform {
+            if let Some(Asset::Transform(transform)) =
+                self.get_asset_by_path_id_mut(parent_transform)
+            {
+                transform.children.push(local_pptr(transform_path_id));
+            }
+        }
+        let container_name = self
+            .scene_graph()
+            .node(game_object_path_id)
+            .map(|node| node.path())
+            .unwrap_or_else(|| name.to_string());
+        self.add_container_entry(
+            &container_name,
+            game_object_path_id,
+            &[game_object_path_id, transform_path_id],
+        );
+        Ok(game_object_path_id)
+    }
+}
+
+impl Matrix4x4f {
+    pub fn identity() -> Self {
+        Self::from_rows([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Scale, then rotate, then translate.
+    pub fn from_trs(translation: &Vector3f, rotation: &Quaternionf, scale: &Vector3f) -> Self {
+        let (x, y, z, w) = (rotation.x, rotation.y, rotation.z, rotation.w);
+        let rotation = [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - w * z),
+                2. * (x * z + w * y),
+            ],
+            [
+                2. * (x * y + w * z),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - w * x),
+            ],
+            [
+                2. * (x * z - w * y),
+                2. * (y * z + w * x),
+                1. - 2. * (x * x + y * y),
+            ],
+        ];
+        let scale = [scale.x, scale.y, scale.z];
+        let translation = [translation.x, translation.y, translation.z];
+        let mut rows = [
+            [0., 0., 0., 0.],
+            [0., 0., 0., 0.],
+            [0., 0., 0., 0.],
+            [0., 0., 0., 1.],
+        ];
+        for row in 0..3 {
+            for column in 0..3 {
+                rows[row][column] = rotation[row][column] * scale[column];
+            }
+            rows[row][3] = translation[row];
+        }
+        Self::from_rows(rows)
+    }
+
+    pub fn from_rows(rows: [[f32; 4]; 4]) -> Self {
+        let [[e00, e01, e02, e03], [e10, e11, e12, e13], [e20, e21, e22, e23], [e30, e31, e32, e33]] =
+            rows;
+        Self {
+            e00,
+            e01,
+            e02,
+            e03,
+            e10,
+            e11,
+            e12,
+            e13,
+            e20,
+            e21,
+            e22,
+            e23,
+            e30,
+            e31,
+            e32,
+            e33,
+        }
+    }
+
+    pub fn rows(&self) -> [[f32; 4]; 4] {
+        [
+            [self.e00, self.e01, self.e02, self.e03],
+            [self.e10, self.e11, self.e12, self.e13],
+            [self.e20, self.e21, self.e22, self.e23],
+            [self.e30, self.e31, self.e32, self.e33],
+        ]
+    }
+
+    /// The translation column, which is the world position for a world matrix.
+    pub fn translation(&self) -> Vector3f {
+        Vector3f {
+            x: self.e03,
+            y: self.e13,
+            z: self.e23,
+        }
+    }
+}
+
+impl Mul for &Matrix4x4f {
+    type Output = Matrix4x4f;
+
+    fn mul(self, rhs: Self) -> Matrix4x4f {
+        let (lhs, rhs) = (self.rows(), rhs.rows());
+        let mut rows = [[0.; 4]; 4];
+        for (row, out) in rows.iter_mut().enumerate() {
+            for (column, value) in out.iter_mut().enumerate() {
+                *value = (0..4).map(|i| lhs[row][i] * rhs[i][column]).sum();
+            }
+        }
+        Matrix4x4f::from_rows(rows)
+    }
+}
+
+fn local(pptr: &PPtr) -> Option<i64> {
+    (pptr.file_id == 0 && pptr.path_id != 0).then_some(pptr.path_id)
+}
+
+fn local_pptr(path_id: i64) -> PPtr {
+    PPtr {
+        file_id: 0,
+        path_id,
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use binrw::Endian;
+
+use crate::registry::read_script_pptr;
+use crate::{Asset, AssetFile, Bundle, BundleFile, MonoScript, PPtr, MONO_BEHAVIOR_CLASS_ID};
+
+impl AssetFile {
+    pub fn is_mono_behavior(&self, asset: &Asset) -> bool {
+        let type_hash = asset.type_hash();
+        self.types
+            .iter()
+            .any(|ty| ty.type_hash == type_hash && ty.class_id == MONO_BEHAVIOR_CLASS_ID)
+    }
+
+    /// Get the script PPtr of a MonoBehaviour, including ones the crate doesn't parse.
+    pub fn mono_behavior_script(&self, asset: &Asset) -> Option<PPtr> {
+        match asset {
+            Asset::Terrain(behavior) => Some(behavior.script.clone()),
+            Asset::EmptyMonoBehavior(behavior) => Some(behavior.script.clone()),
+            Asset::SpringJob(behavior) => Some(behavior.script.clone()),
+            Asset::SpringBone(behavior) => Some(behavior.script.clone()),
+            Asset::Unparsed(unparsed) if self.is_mono_behavior(asset) => {
+                read_script_pptr(&mut Cursor::new(&unparsed.blob), Endian::Little).ok()
+            }
+            Asset::Custom(custom) => custom.script.clone(),
+            _ => None,
+        }
+    }
+
+    /// Look up a script that lives in this file. Use a [ScriptResolver] for external scripts.
+    pub fn get_script(&self, script: &PPtr) -> Option<&MonoScript> {
+        if script.file_id != 0 {
+            return None;
+        }
+        match self.get_asset_by_path_id(script.path_id)? {
+            Asset::Script(script) => Some(script),
+            _ => None,
+        }
+    }
+
+    /// Get the file name of an external (ex. "CAB-..." for "archive:/CAB-.../CAB-...").
+    pub fn external_name(&self, file_id: i32) -> Option<String> {
+        let external = self.externals.get((file_id as usize).checked_sub(1)?)?;
+        let path = external.path.to_string();
+        Some(path.rsplit('/').next().unwrap_or_default().to_string())
+    }
+}
+
+/// Resolves MonoBehaviour scripts across every AssetFile it knows about.
+#[derive(Debug, Default)]
+pub struct ScriptResolver<'a> {
+    files: HashMap<String, &'a AssetFile>,
+}
+
+impl<'a> ScriptResolver<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_bundle(bundle: &'a Bundle) -> Self {
+        let mut resolver = Self::new();
+        resolver.add_bundle(bundle);
+        resolver
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, asset_file: &'a AssetFile) {
+        self.files.insert(name.into(), asset_file);
+    }
+
+    pub fn add_bundle(&mut self, bundle: &'a Bundle) {
+        for (name, file) in bundle.files() {
+            if let BundleFile::Assets(asset_file) = file {
+                self.add_file(name.clone(), asset_file);
+            }
+        }
+    }
+
+    pub fn resolve(&self, asset_file: &'a AssetFile, script: &PPtr) -> Option<&'a MonoScript> {
+        if script.file_id == 0 {
+            return asset_file.get_script(script);
+        }
+        let name = asset_file.external_name(script.file_id)?;
+        let external_file = self.files.get(&name)?;
+        external_file.get_script(&PPtr {
+            file_id: 0,
+            path_id: script.path_id,
+        })
+    }
+
+    pub fn resolve_asset(
+        &self,
+        asset_file: &'a AssetFile,
+        asset: &Asset,
+    ) -> Option<&'a MonoScript> {
+        let script = asset_file.mono_behavior_script(asset)?;
+        self.resolve(asset_file, &script)
+    }
+
+    /// Find every MonoBehaviour in the file whose script has the given class name.
+    /// The name can be bare or qualified with the script's namespace.
+    pub fn find_mono_behaviors(
+        &self,
+        asset_file: &'a AssetFile,
+        class_name: &str,
+    ) -> Vec<(i64, &'a Asset)> {
+        asset_file
+            .iter_with_path_ids()
+            .filter(|(_, asset)| {
+                self.resolve_asset(asset_file, asset)
+                    .map(|script| script.is_class(class_name))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,501 @@
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use image::DynamicImage;
+use tegra_swizzle::surface::BlockDim;
+
+use crate::texture_decode::decode_image;
+use crate::{
+    Asset, Bundle, BundleFile, Cubemap, StreamingInfo, Texture2DArray, Texture3D, TextureArrayData,
+    TextureFormat,
+};
+
+/// Unity's face order for cubemaps.
+pub const CUBEMAP_FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// How a platform arranges texture blocks in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureSwizzle {
+    /// Tegra block linear swizzling, used by Switch builds.
+    BlockLinear,
+    /// Rows of blocks stored in order, used by every other platform.
+    Linear,
+}
+
+impl TextureSwizzle {
+    /// Unity's BuildTarget value for the Switch.
+    pub const SWITCH_PLATFORM: u32 = 38;
+
+    /// The swizzling used by an asset file's target platform.
+    pub fn from_platform(platform: u32) -> Self {
+        if platform == Self::SWITCH_PLATFORM {
+            Self::BlockLinear
+        } else {
+            Self::Linear
+        }
+    }
+}
+
+/// How to present the channels of a decoded image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// Channels are where the GPU samples them. Color channels a format doesn't store are 0
+    /// and a missing alpha channel is 1.
+    Raw,
+    /// Unpack a tangent space normal map the way Unity's shaders do. X is red times alpha, which
+    /// also covers the DXT5nm AG layout, Y is green, and Z is reconstructed into blue. Alpha is 1.
+    Normal,
+}
+
+impl ChannelMode {
+    /// Apply the mode to an image decoded from `format`.
+    pub fn apply(self, format: TextureFormat, image: DynamicImage) -> DynamicImage {
+        if self == ChannelMode::Raw {
+            return image;
+        }
+        // Signed formats already store -1 to 1.
+        let signed = matches!(
+            format,
+            TextureFormat::EAC_R_SIGNED
+                | TextureFormat::EAC_RG_SIGNED
+                | TextureFormat::RHalf
+                | TextureFormat::RGHalf
+                | TextureFormat::RFloat
+                | TextureFormat::RGFloat
+        );
+        let unpack = |[r, g, _, a]: [f32; 4]| {
+            let (x, y) = if signed {
+                (r * a, g)
+            } else {
+                (r * a * 2. - 1., g * 2. - 1.)
+            };
+            let z = (1. - x * x - y * y).max(0.).sqrt();
+            if signed {
+                [x, y, z, 1.]
+            } else {
+                [(x + 1.) / 2., (y + 1.) / 2., (z + 1.) / 2., 1.]
+            }
+        };
+        match image {
+            DynamicImage::ImageRgba8(mut image) => {
+                for pixel in image.pixels_mut() {
+                    let unpacked = unpack(pixel.0.map(|c| c as f32 / 255.));
+                    pixel.0 = unpacked.map(|c| (c * 255.).round() as u8);
+                }
+                DynamicImage::ImageRgba8(image)
+            }
+            image => {
+                let mut image = image.into_rgba32f();
+                for pixel in image.pixels_mut() {
+                    pixel.0 = unpack(pixel.0);
+                }
+                DynamicImage::ImageRgba32F(image)
+            }
+        }
+    }
+}
+
+/// A texture surface with its array layers and mip levels.
+pub(crate) struct Surface<'a> {
+    pub format: TextureFormat,
+    pub swizzle: TextureSwizzle,
+    pub data: &'a [u8],
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub layer_count: usize,
+    pub mip_count: usize,
+}
+
+impl Surface<'_> {
+    fn block_dim(&self) -> Result<(BlockDim, usize)> {
+        let Some((block_width, block_height, bytes_per_pixel)) = self.format.block_info() else {
+            bail!(
+                "texture format '{:?}' can't be stored in a surface",
+                self.format
+            )
+        };
+        let block_dim = BlockDim {
+            width: NonZeroUsize::new(block_width).unwrap(),
+            height: NonZeroUsize::new(block_height).unwrap(),
+            depth: NonZeroUsize::new(1).unwrap(),
+        };
+        Ok((block_dim, bytes_per_pixel))
+    }
+
+    /// The size of the swizzled surface, including padding between layers.
+    pub fn swizzled_size(&self) -> Result<usize> {
+        let (block_dim, bytes_per_pixel) = self.block_dim()?;
+        if self.swizzle == TextureSwizzle::Linear {
+            return Ok(self.linear_size());
+        }
+        Ok(tegra_swizzle::surface::swizzled_surface_size(
+            self.width,
+            self.height,
+            self.depth,
+            block_dim,
+            None,
+            bytes_per_pixel,
+            self.mip_count,
+            self.layer_count,
+        ))
+    }
+
+    /// The size of a single depth slice of a layer's mip level once deswizzled.
+    pub fn slice_size(&self, level: usize) -> usize {
+        let (block_width, block_height, bytes_per_pixel) =
+            self.format.block_info().unwrap_or((1, 1, 0));
+        tegra_swizzle::div_round_up((self.width >> level).max(1), block_width)
+            * tegra_swizzle::div_round_up((self.height >> level).max(1), block_height)
+            * bytes_per_pixel
+    }
+
+    /// The size of the surface once deswizzled, without any padding.
+    pub fn linear_size(&self) -> usize {
+        let layer_size: usize = (0..self.mip_count)
+            .map(|level| self.slice_size(level) * (self.depth >> level).max(1))
+            .sum();
+        layer_size * self.layer_count
+    }
+
+    /// Deswizzle the surface into each layer's mip levels, stored one after another.
+    pub fn deswizzle(&self) -> Result<Vec<u8>> {
+        let (block_dim, bytes_per_pixel) = self.block_dim()?;
+        let surface_size = self.swizzled_size()?;
+        if self.data.len() < surface_size {
+            bail!(
+                "surface has {} bytes of image data but needs {}",
+                self.data.len(),
+                surface_size
+            );
+        }
+        if self.swizzle == TextureSwizzle::Linear {
+            return Ok(self.data[..surface_size].to_vec());
+        }
+        Ok(tegra_swizzle::surface::deswizzle_surface(
+            self.width,
+            self.height,
+            self.depth,
+            &self.data[..surface_size],
+            block_dim,
+            None,
+            bytes_per_pixel,
+            self.mip_count,
+            self.layer_count,
+        )?)
+    }
+
+    /// Swizzle deswizzled data laid out like the output of [`Surface::deswizzle`].
+    pub fn swizzle(&self) -> Result<Vec<u8>> {
+        let (block_dim, bytes_per_pixel) = self.block_dim()?;
+        if self.data.len() != self.linear_size() {
+            bail!(
+                "surface has {} bytes of image data but needs {}",
+                self.data.len(),
+                self.linear_size()
+            );
+        }
+        if self.swizzle == TextureSwizzle::Linear {
+            return Ok(self.data.to_vec());
+        }
+        Ok(tegra_swizzle::surface::swizzle_surface(
+            self.width,
+            self.height,
+            self.depth,
+            self.data,
+            block_dim,
+            None,
+            bytes_per_pixel,
+            self.mip_count,
+            self.layer_count,
+        )?)
+    }
+
+    /// Deswizzle and decode the surface. Images are ordered by layer, then mip level, then
+    /// depth slice. Only `only_level` is decoded if given.
+    pub fn decode(&self, only_level: Option<usize>) -> Result<Vec<DynamicImage>> {
+        let input = self.deswizzle()?;
+        let mut images = vec![];
+        let mut offset = 0;
+        for _ in 0..self.layer_count {
+            for level in 0..self.mip_count {
+                let width = (self.width >> level).max(1);
+                let height = (self.height >> level).max(1);
+                let depth = (self.depth >> level).max(1);
+                let slice_size = self.slice_size(level);
+                if only_level.is_none() || only_level == Some(level) {
+                    for slice in 0..depth {
+                        let start = offset + slice * slice_size;
+                        images.push(decode_image(
+                            self.format,
+                            &input[start..start + slice_size],
+                            width,
+                            height,
+                        )?);
+                    }
+                }
+                offset += slice_size * depth;
+            }
+        }
+        Ok(images)
+    }
+}
+
+/// The encoded data for a texture, either stored inline or read from `resource`.
+pub(crate) fn texture_data<'a>(
+    name: &str,
+    inline: &'a [u8],
+    stream_data: &StreamingInfo,
+    resource: &'a [u8],
+) -> Result<&'a [u8]> {
+    if !inline.is_empty() {
+        return Ok(inline);
+    }
+    let start = stream_data.offset as usize;
+    let end = start + stream_data.size as usize;
+    resource.get(start..end).ok_or_else(|| {
+        anyhow!(
+            "stream data for texture '{}' ({}..{}) is outside the resource file ({} bytes)",
+            name,
+            start,
+            end,
+            resource.len()
+        )
+    })
+}
+
+fn graphics_format(name: &str, format: i32) -> Result<TextureFormat> {
+    TextureFormat::from_graphics_format(format).ok_or_else(|| {
+        anyhow!(
+            "texture '{}' has unsupported graphics format {}",
+            name,
+            format
+        )
+    })
+}
+
+/// Save each image as `<name>_<suffix>.png` in `dir`.
+fn save_pngs(
+    dir: &Path,
+    name: &str,
+    images: &[DynamicImage],
+    suffix: impl Fn(usize) -> String,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for (i, image) in images.iter().enumerate() {
+        let path = dir.join(format!("{}_{}.png", name, suffix(i)));
+        image.save(&path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+impl Cubemap {
+    /// Decode the top mip level of each face, in the order given by [`CUBEMAP_FACES`].
+    pub fn decode_faces(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+    ) -> Result<Vec<DynamicImage>> {
+        self.decode_level(resource, swizzle, 0)
+    }
+
+    /// Decode a single mip level of each face.
+    pub fn decode_level(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        level: u32,
+    ) -> Result<Vec<DynamicImage>> {
+        if level >= self.mip_count.max(1) {
+            bail!(
+                "cubemap '{}' has {} mip levels but level {} was requested",
+                self.name.0,
+                self.mip_count,
+                level
+            );
+        }
+        self.surface(resource, swizzle)?
+            .decode(Some(level as usize))
+    }
+
+    /// Save the top mip level of each face as a PNG named after the cubemap and face.
+    pub fn save_faces<P: AsRef<Path>>(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        dir: P,
+    ) -> Result<Vec<PathBuf>> {
+        let faces = self.decode_faces(resource, swizzle)?;
+        save_pngs(dir.as_ref(), &self.name.0, &faces, |i| {
+            CUBEMAP_FACES[i].to_string()
+        })
+    }
+
+    fn surface<'a>(&'a self, resource: &'a [u8], swizzle: TextureSwizzle) -> Result<Surface<'a>> {
+        Ok(Surface {
+            layer_count: 6,
+            ..self.texture.surface(resource, swizzle)?
+        })
+    }
+}
+
+impl TextureArrayData {
+    pub fn image_data<'a>(&'a self, resource: &'a [u8]) -> Result<&'a [u8]> {
+        texture_data(&self.name.0, &self.image_data, &self.stream_data, resource)
+    }
+
+    pub fn texture_format(&self) -> Result<TextureFormat> {
+        graphics_format(&self.name.0, self.format)
+    }
+}
+
+impl Texture2DArray {
+    /// Decode the top mip level of each layer.
+    pub fn decode_layers(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+    ) -> Result<Vec<DynamicImage>> {
+        self.decode_level(resource, swizzle, 0)
+    }
+
+    /// Decode a single mip level of each layer.
+    pub fn decode_level(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        level: u32,
+    ) -> Result<Vec<DynamicImage>> {
+        if level >= self.mip_count.max(1) {
+            bail!(
+                "texture array '{}' has {} mip levels but level {} was requested",
+                self.name.0,
+                self.mip_count,
+                level
+            );
+        }
+        self.surface(resource, swizzle)?
+            .decode(Some(level as usize))
+    }
+
+    /// Save the top mip level of each layer as a PNG named after the texture and layer index.
+    pub fn save_layers<P: AsRef<Path>>(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        dir: P,
+    ) -> Result<Vec<PathBuf>> {
+        let layers = self.decode_layers(resource, swizzle)?;
+        save_pngs(dir.as_ref(), &self.name.0, &layers, |i| i.to_string())
+    }
+
+    fn surface<'a>(&'a self, resource: &'a [u8], swizzle: TextureSwizzle) -> Result<Surface<'a>> {
+        Ok(Surface {
+            format: self.texture_format()?,
+            swizzle,
+            data: self.image_data(resource)?,
+            width: self.width as usize,
+            height: self.height as usize,
+            depth: 1,
+            layer_count: self.depth as usize,
+            mip_count: self.mip_count.max(1) as usize,
+        })
+    }
+}
+
+impl Texture3D {
+    /// Decode the top mip level as one image per depth slice.
+    pub fn decode_slices(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+    ) -> Result<Vec<DynamicImage>> {
+        self.decode_level(resource, swizzle, 0)
+    }
+
+    /// Decode a single mip level as one image per depth slice. Each level has half
+    /// as many slices as the one before it.
+    pub fn decode_level(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        level: u32,
+    ) -> Result<Vec<DynamicImage>> {
+        if level >= self.mip_count.max(1) {
+            bail!(
+                "3D texture '{}' has {} mip levels but level {} was requested",
+                self.name.0,
+                self.mip_count,
+                level
+            );
+        }
+        self.surface(resource, swizzle)?
+            .decode(Some(level as usize))
+    }
+
+    /// Save the top mip level of each depth slice as a PNG named after the texture and slice index.
+    pub fn save_slices<P: AsRef<Path>>(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        dir: P,
+    ) -> Result<Vec<PathBuf>> {
+        let slices = self.decode_slices(resource, swizzle)?;
+        save_pngs(dir.as_ref(), &self.name.0, &slices, |i| i.to_string())
+    }
+
+    fn surface<'a>(&'a self, resource: &'a [u8], swizzle: TextureSwizzle) -> Result<Surface<'a>> {
+        Ok(Surface {
+            format: self.texture_format()?,
+            swizzle,
+            data: self.image_data(resource)?,
+            width: self.width as usize,
+            height: self.height as usize,
+            depth: self.depth as usize,
+            layer_count: 1,
+            mip_count: self.mip_count.max(1) as usize,
+        })
+    }
+}
+
+impl Bundle {
+    /// Find an asset along with the swizzling used by its asset file's platform.
+    pub(crate) fn get_texture_asset(&self, path_id: i64) -> Result<(&Asset, TextureSwizzle)> {
+        self.files()
+            .find_map(|(_, file)| match file {
+                BundleFile::Assets(asset_file) => {
+                    let asset = asset_file.get_asset_by_path_id(path_id)?;
+                    Some((asset, TextureSwizzle::from_platform(asset_file.platform())))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("bundle does not contain an asset with path ID {}", path_id))
+    }
+
+    /// Decode the top mip level of every layer of the texture with the given path ID.
+    /// Texture2D assets have a single layer, cubemaps have one per face and
+    /// 3D textures have one per depth slice.
+    pub fn decode_texture_layers(&self, path_id: i64) -> Result<Vec<DynamicImage>> {
+        let (asset, swizzle) = self.get_texture_asset(path_id)?;
+        match asset {
+            Asset::Texture2D(texture, _) => {
+                Ok(vec![texture.decode(
+                    self.stream_resource(&texture.stream_data)?,
+                    swizzle,
+                )?])
+            }
+            Asset::Cubemap(texture) => {
+                texture.decode_faces(self.stream_resource(&texture.stream_data)?, swizzle)
+            }
+            Asset::Texture2DArray(texture) => {
+                texture.decode_layers(self.stream_resource(&texture.stream_data)?, swizzle)
+            }
+            Asset::Texture3D(texture) => {
+                texture.decode_slices(self.stream_resource(&texture.stream_data)?, swizzle)
+            }
+            _ => bail!("asset with path ID {} is not a texture", path_id),
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use std::io::Cursor;
+
+use anyhow::{anyhow, bail, Result};
+use binrw::{binrw, BinRead, BinWrite};
+
+use crate::texture::{Surface, TextureSwizzle};
+use crate::{Bundle, Texture2D, TextureFormat};
+
+/// A file format that stores compressed texture blocks as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureContainer {
+    /// DirectDraw Surface with a DX10 header. Only supports BC formats.
+    Dds,
+    /// Khronos KTX2. Supports BC, ETC, EAC and ASTC formats.
+    Ktx2,
+}
+
+impl TextureContainer {
+    /// Identify a container from the start of its data.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"DDS ") {
+            Some(Self::Dds)
+        } else if data.starts_with(&KTX2_IDENTIFIER) {
+            Some(Self::Ktx2)
+        } else {
+            None
+        }
+    }
+}
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+const DDS_DIMENSION_TEXTURE2D: u32 = 3;
+
+#[binrw]
+#[brw(little, magic = b"DDS ")]
+#[derive(Debug)]
+struct DdsHeader {
+    #[brw(magic = 124u32)]
+    flags: u32,
+    height: u32,
+    width: u32,
+    pitch_or_linear_size: u32,
+    depth: u32,
+    mip_map_count: u32,
+    reserved: [u32; 11],
+    #[brw(magic = 32u32)]
+    pixel_format_flags: u32,
+    four_cc: [u8; 4],
+    rgb_bit_count: u32,
+    bit_masks: [u32; 4],
+    caps: [u32; 4],
+    reserved2: u32,
+    #[br(if(four_cc == *b"DX10"))]
+    dx10: Option<DdsHeaderDx10>,
+}
+
+#[binrw]
+#[derive(Debug)]
+struct DdsHeaderDx10 {
+    dxgi_format: u32,
+    resource_dimension: u32,
+    misc_flag: u32,
+    array_size: u32,
+    misc_flags2: u32,
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+#[binrw]
+#[brw(little, magic = b"\xABKTX 20\xBB\r\n\x1A\n")]
+#[derive(Debug)]
+struct Ktx2Header {
+    vk_format: u32,
+    type_size: u32,
+    pixel_width: u32,
+    pixel_height: u32,
+    pixel_depth: u32,
+    layer_count: u32,
+    face_count: u32,
+    level_count: u32,
+    supercompression_scheme: u32,
+    dfd_byte_offset: u32,
+    dfd_byte_length: u32,
+    kvd_byte_offset: u32,
+    kvd_byte_length: u32,
+    sgd_byte_offset: u64,
+    sgd_byte_length: u64,
+    #[br(count = level_count.max(1))]
+    levels: Vec<Ktx2Level>,
+}
+
+#[binrw]
+#[derive(Debug)]
+struct Ktx2Level {
+    byte_offset: u64,
+    byte_length: u64,
+    uncompressed_byte_length: u64,
+}
+
+// (format, UNORM, sRGB)
+const DXGI_FORMATS: &[(TextureFormat, u32, Option<u32>)] = &[
+    (TextureFormat::DXT1, 71, Some(72)),
+    (TextureFormat::DXT3, 74, Some(75)),
+    (TextureFormat::DXT5, 77, Some(78)),
+    (TextureFormat::BC4, 80, None),
+    (TextureFormat::BC5, 83, None),
+    (TextureFormat::BC6H, 95, None),
+    (TextureFormat::BC7, 98, Some(99)),
+];
+
+// (format, UNORM, sRGB). Formats that share a VkFormat are read back as the texture's current
+// format when it's one of them, or the first entry otherwise.
+const VK_FORMATS: &[(TextureFormat, u32, Option<u32>)] = &[
+    (TextureFormat::DXT1, 131, Some(132)),
+    (TextureFormat::DXT3, 135, Some(136)),
+    (TextureFormat::DXT5, 137, Some(138)),
+    (TextureFormat::BC4, 139, None),
+    (TextureFormat::BC5, 141, None),
+    (TextureFormat::BC6H, 143, None),
+    (TextureFormat::BC7, 145, Some(146)),
+    (TextureFormat::ETC2_RGB, 147, Some(148)),
+    (TextureFormat::ETC_RGB4, 147, Some(148)),
+    (TextureFormat::ETC2_RGBA1, 149, Some(150)),
+    (TextureFormat::ETC2_RGBA8, 151, Some(152)),
+    (TextureFormat::EAC_R, 153, None),
+    (TextureFormat::EAC_R_SIGNED, 154, None),
+    (TextureFormat::EAC_RG, 155, None),
+    (TextureFormat::EAC_RG_SIGNED, 156, None),
+    (TextureFormat::ASTC_RGBA_4x4, 157, Some(158)),
+    (TextureFormat::ASTC_RGBA_5x5, 161, Some(162)),
+    (TextureFormat::ASTC_RGBA_6x6, 165, Some(166)),
+    (TextureFormat::ASTC_RGBA_8x8, 171, Some(172)),
+    (TextureFormat::ASTC_RGBA_10x10, 179, Some(180)),
+    (TextureFormat::ASTC_RGBA_12x12, 183, Some(184)),
+    (TextureFormat::ASTC_RGB_4x4, 157, Some(158)),
+    (TextureFormat::ASTC_RGB_5x5, 161, Some(162)),
+    (TextureFormat::ASTC_RGB_6x6, 165, Some(166)),
+    (TextureFormat::ASTC_RGB_8x8, 171, Some(172)),
+    (TextureFormat::ASTC_RGB_10x10, 179, Some(180)),
+    (TextureFormat::ASTC_RGB_12x12, 183, Some(184)),
+    (TextureFormat::ASTC_HDR_4x4, 1000066000, None),
+    (TextureFormat::ASTC_HDR_5x5, 1000066002, None),
+    (TextureFormat::ASTC_HDR_6x6, 1000066004, None),
+    (TextureFormat::ASTC_HDR_8x8, 1000066007, None),
+    (TextureFormat::ASTC_HDR_10x10, 1000066011, None),
+    (TextureFormat::ASTC_HDR_12x12, 1000066013, None),
+];
+
+fn find_format(
+    table: &[(TextureFormat, u32, Option<u32>)],
+    format: TextureFormat,
+    srgb: bool,
+) -> Option<u32> {
+    let (_, unorm, srgb_format) = table.iter().find(|(f, _, _)| *f == format)?;
+    Some(if srgb {
+        srgb_format.unwrap_or(*unorm)
+    } else {
+        *unorm
+    })
+}
+
+fn find_texture_format(
+    table: &[(TextureFormat, u32, Option<u32>)],
+    value: u32,
+) -> Option<(TextureFormat, bool)> {
+    table.iter().find_map(|(format, unorm, srgb)| {
+        if *unorm == value {
+            Some((*format, false))
+        } else if *srgb == Some(value) {
+            Some((*format, true))
+        } else {
+            None
+        }
+    })
+}
+
+/// The basic data format descriptor for a block compressed format.
+fn ktx2_dfd(format: TextureFormat, srgb: bool) -> Vec<u8> {
+    const SIGNED: u8 = 0x40;
+    const FLOAT: u8 = 0x80;
+    // Color model and (channel, bit offset, bit length) for each sample.
+    let (model, samples): (u8, &[(u8, u16, u8)]) = match format {
+        TextureFormat::DXT1 => (128, &[(0, 0, 64)]),
+        TextureFormat::DXT3 => (129, &[(15, 0, 64), (0, 64, 64)]),
+        TextureFormat::DXT5 => (130, &[(15, 0, 64), (0, 64, 64)]),
+        TextureFormat::BC4 => (131, &[(0, 0, 64)]),
+        TextureFormat::BC5 => (132, &[(0, 0, 64), (1, 64, 64)]),
+        TextureFormat::BC6H => (133, &[(FLOAT, 0, 128)]),
+        TextureFormat::BC7 => (134, &[(0, 0, 128)]),
+        TextureFormat::ETC2_RGBA8 => (161, &[(15, 0, 64), (2, 64, 64)]),
+        TextureFormat::EAC_R => (161, &[(0, 0, 64)]),
+        TextureFormat::EAC_R_SIGNED => (161, &[(SIGNED, 0, 64)]),
+        TextureFormat::EAC_RG => (161, &[(0, 0, 64), (1, 64, 64)]),
+        TextureFormat::EAC_RG_SIGNED => (161, &[(SIGNED, 0, 64), (SIGNED | 1, 64, 64)]),
+        TextureFormat::ASTC_HDR_4x4
+        | TextureFormat::ASTC_HDR_5x5
+        | TextureFormat::ASTC_HDR_6x6
+        | TextureFormat::ASTC_HDR_8x8
+        | TextureFormat::ASTC_HDR_10x10
+        | TextureFormat::ASTC_HDR_12x12 => (162, &[(FLOAT | SIGNED, 0, 128)]),
+        TextureFormat::ASTC_RGB_4x4
+        | TextureFormat::ASTC_RGB_5x5
+        | TextureFormat::ASTC_RGB_6x6
+        | TextureFormat::ASTC_RGB_8x8
+        | TextureFormat::ASTC_RGB_10x10
+        | TextureFormat::ASTC_RGB_12x12
+        | TextureFormat::ASTC_RGBA_4x4
+        | TextureFormat::ASTC_RGBA_5x5
+        | TextureFormat::ASTC_RGBA_6x6
+        | TextureFormat::ASTC_RGBA_8x8
+        | TextureFormat::ASTC_RGBA_10x10
+        | TextureFormat::ASTC_RGBA_12x12 => (162, &[(0, 0, 128)]),
+        // ETC1 and the remaining ETC2 formats store a single color sample.
+        _ => (161, &[(2, 0, 64)]),
+    };
+    let (block_width, block_height, block_size) = format.block_info().unwrap();
+
+    let block_size_in_bytes = 24 + 16 * samples.len() as u32;
+    let mut dfd = vec![];
+    dfd.extend((4 + block_size_in_bytes).to_le_bytes());
+    // Khronos vendor and basic descriptor type.
+    dfd.extend(0u32.to_le_bytes());
+    dfd.extend((2 | block_size_in_bytes << 16).to_le_bytes());
+    // BT.709 primaries, straight alpha.
+    dfd.extend([model, 1, if srgb { 2 } else { 1 }, 0]);
+    dfd.extend([block_width as u8 - 1, block_height as u8 - 1, 0, 0]);
+    dfd.extend([block_size as u8, 0, 0, 0, 0, 0, 0, 0]);
+    for (channel, bit_offset, bit_length) in samples {
+        dfd.extend(bit_offset.to_le_bytes());
+        dfd.extend([bit_length - 1, *channel]);
+        dfd.extend([0, 0, 0, 0]);
+        let (lower, upper) = if channel & FLOAT != 0 {
+            let lower = if channel & SIGNED != 0 { -1f32 } else { 0f32 };
+            (lower.to_bits(), 1f32.to_bits())
+        } else if channel & SIGNED != 0 {
+            (i32::MIN as u32 + 1, i32::MAX as u32)
+        } else {
+            (0, u32::MAX)
+        };
+        dfd.extend(lower.to_le_bytes());
+        dfd.extend(upper.to_le_bytes());
+    }
+    dfd
+}
+
+impl Texture2D {
+    /// Export the texture's compressed blocks with every mip level, deswizzled but otherwise
+    /// unchanged.
+    pub fn export(
+        &self,
+        resource: &[u8],
+        swizzle: TextureSwizzle,
+        container: TextureContainer,
+    ) -> Result<Vec<u8>> {
+        let srgb = self.color_space == 1;
+        let table = match container {
+            TextureContainer::Dds => DXGI_FORMATS,
+            TextureContainer::Ktx2 => VK_FORMATS,
+        };
+        let Some(container_format) = find_format(table, self.texture_format, srgb) else {
+            bail!(
+                "texture format '{:?}' can't be stored in a {:?} file",
+                self.texture_format,
+                container
+            )
+        };
+        let surface = self.surface(resource, swizzle)?;
+        let data = surface.deswizzle()?;
+
+        let mut output = Cursor::new(vec![]);
+        match container {
+            TextureContainer::Dds => {
+                let mip_count = surface.mip_count as u32;
+                let mut caps = [DDSCAPS_TEXTURE, 0, 0, 0];
+                if mip_count > 1 {
+                    caps[0] |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+                }
+                DdsHeader {
+                    flags: DDSD_CAPS
+                        | DDSD_HEIGHT
+                        | DDSD_WIDTH
+                        | DDSD_PIXELFORMAT
+                        | DDSD_MIPMAPCOUNT
+                        | DDSD_LINEARSIZE,
+                    height: self.height,
+                    width: self.width,
+                    pitch_or_linear_size: surface.slice_size(0) as u32,
+                    depth: 0,
+                    mip_map_count: mip_count,
+                    reserved: [0; 11],
+                    pixel_format_flags: DDPF_FOURCC,
+                    four_cc: *b"DX10",
+                    rgb_bit_count: 0,
+                    bit_masks: [0; 4],
+                    caps,
+                    reserved2: 0,
+                    dx10: Some(DdsHeaderDx10 {
+                        dxgi_format: container_format,
+                        resource_dimension: DDS_DIMENSION_TEXTURE2D,
+                        misc_flag: 0,
+                        array_size: 1,
+                        misc_flags2: 0,
+                    }),
+                }
+                .write(&mut output)?;
+                let mut output = output.into_inner();
+                output.extend(data);
+                Ok(output)
+            }
+            TextureContainer::Ktx2 => {
+                let dfd = ktx2_dfd(self.texture_format, srgb);
+                let level_count = surface.mip_count;
+                let (_, _, block_size) = self.texture_format.block_info().unwrap();
+                let dfd_offset = 80 + 24 * level_count;
+
+                // Levels are stored smallest first, each aligned to the block size.
+                let mut levels = vec![];
+                let mut offset = dfd_offset + dfd.len();
+                let mut level_data = vec![];
+                for level in (0..level_count).rev() {
+                    let start: usize = (0..level).map(|l| surface.slice_size(l)).sum();
+                    let size = surface.slice_size(level);
+                    let padding = (block_size - offset % block_size) % block_size;
+                    level_data.resize(level_data.len() + padding, 0);
+                    offset += padding;
+                    levels.push(Ktx2Level {
+                        byte_offset: offset as u64,
+                        byte_length: size as u64,
+                        uncompressed_byte_length: size as u64,
+                    });
+                    level_data.extend(&data[start..start + size]);
+                    offset += size;
+                }
+                levels.reverse();
+
+                Ktx2Header {
+                    vk_format: container_format,
+                    type_size: 1,
+                    pixel_width: self.width,
+                    pixel_height: self.height,
+                    pixel_depth: 0,
+                    layer_count: 0,
+                    face_count: 1,
+                    level_count: level_count as u32,
+                    supercompression_scheme: 0,
+                    dfd_byte_offset: dfd_offset as u32,
+                    dfd_byte_length: dfd.len() as u32,
+                    kvd_byte_offset: 0,
+                    kvd_byte_length: 0,
+                    sgd_byte_offset: 0,
+                    sgd_byte_length: 0,
+                    levels,
+                }
+                .write(&mut output)?;
+                let mut output = output.into_inner();
+                output.extend(dfd);
+                output.extend(level_data);
+                Ok(output)
+            }
+        }
+    }
+
+    /// Import a DDS or KTX2 file without recompressing it, updating the texture's dimensions,
+    /// format and sizes to match. The current format is kept when the file's format also stands
+    /// for it, such as ETC_RGB4 for ETC2_RGB. Returns the image data laid out for `swizzle`,
+    /// which belongs either in `image_data` or in the texture's resource file.
+    pub fn import(&mut self, data: &[u8], swizzle: TextureSwizzle) -> Result<Vec<u8>> {
+        let container = TextureContainer::detect(data)
+            .ok_or_else(|| anyhow!("data is not a DDS or KTX2 file"))?;
+        let mut reader = Cursor::new(data);
+        let (format, srgb, width, height, mip_count, levels) = match container {
+            TextureContainer::Dds => {
+                let header = DdsHeader::read(&mut reader)?;
+                let (format, srgb) = match &header.dx10 {
+                    Some(dx10) => {
+                        if dx10.array_size > 1 || dx10.resource_dimension != DDS_DIMENSION_TEXTURE2D
+                        {
+                            bail!("only single 2D textures can be imported from DDS files");
+                        }
+                        find_texture_format(DXGI_FORMATS, dx10.dxgi_format).ok_or_else(|| {
+                            anyhow!("unsupported DXGI format {}", dx10.dxgi_format)
+                        })?
+                    }
+                    None => match &header.four_cc {
+                        b"DXT1" => (TextureFormat::DXT1, false),
+                        b"DXT3" => (TextureFormat::DXT3, false),
+                        b"DXT5" => (TextureFormat::DXT5, false),
+                        b"ATI1" | b"BC4U" => (TextureFormat::BC4, false),
+                        b"ATI2" | b"BC5U" => (TextureFormat::BC5, false),
+                        four_cc => bail!(
+                            "unsupported DDS FourCC '{}'",
+                            String::from_utf8_lossy(four_cc)
+                        ),
+                    },
+                };
+                let mip_count = if header.flags & DDSD_MIPMAPCOUNT != 0 {
+                    header.mip_map_count.max(1)
+                } else {
+                    1
+                };
+                let start = reader.position() as usize;
+                let levels = data[start..].to_vec();
+                (format, srgb, header.width, header.height, mip_count, levels)
+            }
+            TextureContainer::Ktx2 => {
+                let header = Ktx2Header::read(&mut reader)?;
+                if header.supercompression_scheme != 0 {
+                    bail!("supercompressed KTX2 files are not supported");
+                }
+                if header.layer_count > 1 || header.face_count != 1 || header.pixel_depth > 1 {
+                    bail!("only single 2D textures can be imported from KTX2 files");
+                }
+                let (format, srgb) = find_texture_format(VK_FORMATS, header.vk_format)
+                    .ok_or_else(|| anyhow!("unsupported VkFormat {}", header.vk_format))?;
+                let mut levels = vec![];
+                for level in &header.levels {
+                    let start = level.byte_offset as usize;
+                    let end = start + level.byte_length as usize;
+                    let Some(level_data) = data.get(start..end) else {
+                        bail!("KTX2 level data ({}..{}) is outside the file", start, end);
+                    };
+                    levels.extend(level_data);
+                }
+                let mip_count = header.levels.len() as u32;
+                (
+                    format,
+                    srgb,
+                    header.pixel_width,
+                    header.pixel_height,
+                    mip_count,
+                    levels,
+                )
+            }
+        };
+
+        let table = match container {
+            TextureContainer::Dds => DXGI_FORMATS,
+            TextureContainer::Ktx2 => VK_FORMATS,
+        };
+        let format = match find_format(table, self.texture_format, srgb) {
+            Some(current) if find_format(table, format, srgb) == Some(current) => {
+                self.texture_format
+            }
+            _ => format,
+        };
+
+        let mut surface = Surface {
+            format,
+            swizzle,
+            data: &levels,
+            width: width as usize,
+            height: height as usize,
+            depth: 1,
+            layer_count: 1,
+            mip_count: mip_count as usize,
+        };
+        let linear_size = surface.linear_size();
+        if levels.len() < linear_size {
+            bail!(
+                "{:?} file has {} bytes of image data but {} mip levels of {}x{} {:?} need {}",
+                container,
+                levels.len(),
+                mip_count,
+                width,
+                height,
+                format,
+                linear_size
+            );
+        }
+        surface.data = &levels[..linear_size];
+        let swizzled = surface.swizzle()?;
+
+        self.width = width;
+        self.height = height;
+        self.texture_format = format;
+        self.mip_count = mip_count;
+        self.complete_image_size = swizzled.len() as u32;
+        // Keep the color space for formats that don't have sRGB variants.
+        if find_format(VK_FORMATS, format, true) != find_format(VK_FORMATS, format, false) {
+            self.color_space = srgb as i32;
+        }
+        Ok(swizzled)
+    }
+}
+
+impl Bundle {
+    /// Export the Texture2D with the given path ID without decoding it.
+    pub fn export_texture(&self, path_id: i64, container: TextureContainer) -> Result<Vec<u8>> {
+        let (texture, swizzle) = self.get_texture_2d(path_id)?;
+        texture.export(
+            self.stream_resource(&texture.stream_data)?,
+            swizzle,
+            container,
+        )
+    }
+
+    /// Replace the Texture2D with the given path ID with the contents of a DDS or KTX2 file.
+    /// Streamed textures have their data rewritten in the resource file, moving any data
+    /// stored after it.
+    pub fn import_texture(&mut self, path_id: i64, data: &[u8]) -> Result<()> {
+        self.inject_texture(path_id, |texture, swizzle| texture.import(data, swizzle))
+    }
+}
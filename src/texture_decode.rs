@@ -0,0 +1,1653 @@
+use anyhow::{anyhow, bail, Result};
+use astc_decode::Footprint;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+
+use crate::TextureFormat;
+
+/// Decode a single unswizzled surface. Formats with more range or precision than 8 bits per
+/// channel decode to RGBA32F and everything else to RGBA8, except ASTC HDR, which astc_decode
+/// only outputs as RGBA8. Channels a format doesn't have are filled in the way Unity
+/// samples them: 0 for color and 1 for alpha.
+pub(crate) fn decode_image(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<DynamicImage> {
+    match format {
+        TextureFormat::Alpha8 => ldr(format, data, width, height, |p, out| {
+            out[0] = [255, 255, 255, p[0]]
+        }),
+        TextureFormat::ARGB4444 => ldr(format, data, width, height, |p, out| {
+            let p = u16::from_le_bytes([p[0], p[1]]);
+            out[0] = [nibble(p, 8), nibble(p, 4), nibble(p, 0), nibble(p, 12)];
+        }),
+        TextureFormat::RGBA4444 => ldr(format, data, width, height, |p, out| {
+            let p = u16::from_le_bytes([p[0], p[1]]);
+            out[0] = [nibble(p, 12), nibble(p, 8), nibble(p, 4), nibble(p, 0)];
+        }),
+        TextureFormat::RGB24 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[0], p[1], p[2], 255]
+        }),
+        TextureFormat::BGR24 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[2], p[1], p[0], 255]
+        }),
+        TextureFormat::RGBA32 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[0], p[1], p[2], p[3]]
+        }),
+        TextureFormat::ARGB32 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[1], p[2], p[3], p[0]]
+        }),
+        TextureFormat::BGRA32 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[2], p[1], p[0], p[3]]
+        }),
+        TextureFormat::RGB565 => ldr(format, data, width, height, |p, out| {
+            out[0] = rgb565(u16::from_le_bytes([p[0], p[1]]))
+        }),
+        TextureFormat::RG16 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[0], p[1], 0, 255]
+        }),
+        TextureFormat::R8 => ldr(format, data, width, height, |p, out| {
+            out[0] = [p[0], 0, 0, 255]
+        }),
+        TextureFormat::R16 => hdr(format, data, width, height, |p, out| {
+            out[0] = [unorm16(p, 0), 0., 0., 1.]
+        }),
+        TextureFormat::RG32 => hdr(format, data, width, height, |p, out| {
+            out[0] = [unorm16(p, 0), unorm16(p, 1), 0., 1.]
+        }),
+        TextureFormat::RGB48 => hdr(format, data, width, height, |p, out| {
+            out[0] = [unorm16(p, 0), unorm16(p, 1), unorm16(p, 2), 1.]
+        }),
+        TextureFormat::RGBA64 => hdr(format, data, width, height, |p, out| {
+            out[0] = [unorm16(p, 0), unorm16(p, 1), unorm16(p, 2), unorm16(p, 3)]
+        }),
+        TextureFormat::RHalf => hdr(format, data, width, height, |p, out| {
+            out[0] = [half(p, 0), 0., 0., 1.]
+        }),
+        TextureFormat::RGHalf => hdr(format, data, width, height, |p, out| {
+            out[0] = [half(p, 0), half(p, 1), 0., 1.]
+        }),
+        TextureFormat::RGBAHalf => hdr(format, data, width, height, |p, out| {
+            out[0] = [half(p, 0), half(p, 1), half(p, 2), half(p, 3)]
+        }),
+        TextureFormat::RFloat => hdr(format, data, width, height, |p, out| {
+            out[0] = [float(p, 0), 0., 0., 1.]
+        }),
+        TextureFormat::RGFloat => hdr(format, data, width, height, |p, out| {
+            out[0] = [float(p, 0), float(p, 1), 0., 1.]
+        }),
+        TextureFormat::RGBFloat => hdr(format, data, width, height, |p, out| {
+            out[0] = [float(p, 0), float(p, 1), float(p, 2), 1.]
+        }),
+        TextureFormat::RGBAFloat => hdr(format, data, width, height, |p, out| {
+            out[0] = [float(p, 0), float(p, 1), float(p, 2), float(p, 3)]
+        }),
+        TextureFormat::ARGBFloat => hdr(format, data, width, height, |p, out| {
+            out[0] = [float(p, 1), float(p, 2), float(p, 3), float(p, 0)]
+        }),
+        TextureFormat::RGB9e5Float => hdr(format, data, width, height, |p, out| {
+            out[0] = rgb9e5(u32::from_le_bytes([p[0], p[1], p[2], p[3]]))
+        }),
+        TextureFormat::YUY2 => ldr(format, data, width, height, |p, out| {
+            out[0] = yuv_to_rgb(p[0], p[1], p[3]);
+            out[1] = yuv_to_rgb(p[2], p[1], p[3]);
+        }),
+        TextureFormat::DXT1 => ldr(format, data, width, height, |block, out| {
+            decode_bc1_block(block, out, false)
+        }),
+        TextureFormat::DXT3 => ldr(format, data, width, height, decode_bc2_block),
+        TextureFormat::DXT5 => ldr(format, data, width, height, decode_bc3_block),
+        TextureFormat::BC4 => ldr(format, data, width, height, |block, out| {
+            for (pixel, r) in out.iter_mut().zip(decode_bc4_values(block)) {
+                *pixel = [r, 0, 0, 255];
+            }
+        }),
+        TextureFormat::BC5 => ldr(format, data, width, height, |block, out| {
+            let r = decode_bc4_values(&block[..8]);
+            let g = decode_bc4_values(&block[8..]);
+            for (i, pixel) in out.iter_mut().enumerate() {
+                *pixel = [r[i], g[i], 0, 255];
+            }
+        }),
+        TextureFormat::BC6H => hdr(format, data, width, height, decode_bc6h_block),
+        TextureFormat::BC7 => ldr(format, data, width, height, decode_bc7_block),
+        TextureFormat::ETC_RGB4 | TextureFormat::ETC_RGB4_3DS => {
+            ldr(format, data, width, height, |block, out| {
+                decode_etc_block(block, out, false, false)
+            })
+        }
+        TextureFormat::ETC2_RGB => ldr(format, data, width, height, |block, out| {
+            decode_etc_block(block, out, true, false)
+        }),
+        TextureFormat::ETC2_RGBA1 => ldr(format, data, width, height, |block, out| {
+            decode_etc_block(block, out, true, true)
+        }),
+        TextureFormat::ETC2_RGBA8 | TextureFormat::ETC_RGBA8_3DS => {
+            ldr(format, data, width, height, |block, out| {
+                decode_etc_block(&block[8..], out, true, false);
+                for (pixel, a) in out.iter_mut().zip(decode_eac_alpha(&block[..8])) {
+                    pixel[3] = a;
+                }
+            })
+        }
+        TextureFormat::EAC_R => hdr(format, data, width, height, |block, out| {
+            for (pixel, r) in out.iter_mut().zip(decode_eac_r11(block, false)) {
+                *pixel = [r, 0., 0., 1.];
+            }
+        }),
+        TextureFormat::EAC_R_SIGNED => hdr(format, data, width, height, |block, out| {
+            for (pixel, r) in out.iter_mut().zip(decode_eac_r11(block, true)) {
+                *pixel = [r, 0., 0., 1.];
+            }
+        }),
+        TextureFormat::EAC_RG => hdr(format, data, width, height, |block, out| {
+            let r = decode_eac_r11(&block[..8], false);
+            let g = decode_eac_r11(&block[8..], false);
+            for (i, pixel) in out.iter_mut().enumerate() {
+                *pixel = [r[i], g[i], 0., 1.];
+            }
+        }),
+        TextureFormat::EAC_RG_SIGNED => hdr(format, data, width, height, |block, out| {
+            let r = decode_eac_r11(&block[..8], true);
+            let g = decode_eac_r11(&block[8..], true);
+            for (i, pixel) in out.iter_mut().enumerate() {
+                *pixel = [r[i], g[i], 0., 1.];
+            }
+        }),
+        TextureFormat::ATC_RGB4 => ldr(format, data, width, height, decode_atc_block),
+        TextureFormat::ATC_RGBA8 => ldr(format, data, width, height, |block, out| {
+            decode_atc_block(&block[8..], out);
+            for (pixel, a) in out.iter_mut().zip(decode_bc4_values(&block[..8])) {
+                pixel[3] = a;
+            }
+        }),
+        TextureFormat::PVRTC_RGB2
+        | TextureFormat::PVRTC_RGBA2
+        | TextureFormat::PVRTC_RGB4
+        | TextureFormat::PVRTC_RGBA4 => {
+            let two_bpp = matches!(
+                format,
+                TextureFormat::PVRTC_RGB2 | TextureFormat::PVRTC_RGBA2
+            );
+            rgba8_image(decode_pvrtc(data, width, height, two_bpp)?, width, height)
+        }
+        TextureFormat::ASTC_RGB_4x4
+        | TextureFormat::ASTC_RGB_5x5
+        | TextureFormat::ASTC_RGB_6x6
+        | TextureFormat::ASTC_RGB_8x8
+        | TextureFormat::ASTC_RGB_10x10
+        | TextureFormat::ASTC_RGB_12x12
+        | TextureFormat::ASTC_RGBA_4x4
+        | TextureFormat::ASTC_RGBA_5x5
+        | TextureFormat::ASTC_RGBA_6x6
+        | TextureFormat::ASTC_RGBA_8x8
+        | TextureFormat::ASTC_RGBA_10x10
+        | TextureFormat::ASTC_RGBA_12x12
+        | TextureFormat::ASTC_HDR_4x4
+        | TextureFormat::ASTC_HDR_5x5
+        | TextureFormat::ASTC_HDR_6x6
+        | TextureFormat::ASTC_HDR_8x8
+        | TextureFormat::ASTC_HDR_10x10
+        | TextureFormat::ASTC_HDR_12x12 => {
+            let data = check_size(format, data, width, height)?;
+            let (block_width, block_height, _) = block_info(format)?;
+            let mut output = vec![[0u8; 4]; width * height];
+            astc_decode::astc_decode(
+                data,
+                width as u32,
+                height as u32,
+                Footprint::new(block_width as u32, block_height as u32),
+                |x, y, color| {
+                    output[x as usize + y as usize * width] = color;
+                },
+            )?;
+            rgba8_image(output, width, height)
+        }
+        TextureFormat::DXT1Crunched
+        | TextureFormat::DXT5Crunched
+        | TextureFormat::ETC_RGB4Crunched
+        | TextureFormat::ETC2_RGBA8Crunched => decode_crunched(data, width, height),
+    }
+}
+
+#[cfg(feature = "crunch")]
+fn decode_crunched(data: &[u8], width: usize, height: usize) -> Result<DynamicImage> {
+    let mut pixels = vec![0u32; width * height];
+    texture2ddecoder::decode_unity_crunch(data, width, height, &mut pixels)
+        .map_err(|err| anyhow!("failed to decode crunched texture: {}", err))?;
+    let output = pixels
+        .into_iter()
+        .map(|pixel| {
+            let [b, g, r, a] = pixel.to_le_bytes();
+            [r, g, b, a]
+        })
+        .collect();
+    rgba8_image(output, width, height)
+}
+
+#[cfg(not(feature = "crunch"))]
+fn decode_crunched(_: &[u8], _: usize, _: usize) -> Result<DynamicImage> {
+    bail!("decoding crunched textures requires the 'crunch' feature")
+}
+
+fn block_info(format: TextureFormat) -> Result<(usize, usize, usize)> {
+    format
+        .block_info()
+        .ok_or_else(|| anyhow!("texture format '{:?}' has no fixed block size", format))
+}
+
+fn check_size(format: TextureFormat, data: &[u8], width: usize, height: usize) -> Result<&[u8]> {
+    let (block_width, block_height, block_size) = block_info(format)?;
+    let size = width.div_ceil(block_width) * height.div_ceil(block_height) * block_size;
+    data.get(..size).ok_or_else(|| {
+        anyhow!(
+            "expected {} bytes of {:?} image data but found {}",
+            size,
+            format,
+            data.len()
+        )
+    })
+}
+
+/// Run `decode_block` over every block and copy the results into a row-major image,
+/// dropping pixels that fall outside the image.
+fn decode_blocks<T: Copy + Default>(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    decode_block: impl Fn(&[u8], &mut [T]),
+) -> Result<Vec<T>> {
+    let data = check_size(format, data, width, height)?;
+    let (block_width, block_height, block_size) = block_info(format)?;
+    let blocks_x = width.div_ceil(block_width);
+    let mut output = vec![T::default(); width * height];
+    let mut pixels = vec![T::default(); block_width * block_height];
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        decode_block(block, &mut pixels);
+        let x0 = i % blocks_x * block_width;
+        let y0 = i / blocks_x * block_height;
+        for (row, y) in (y0..height.min(y0 + block_height)).enumerate() {
+            let columns = width.min(x0 + block_width) - x0;
+            output[y * width + x0..y * width + x0 + columns]
+                .copy_from_slice(&pixels[row * block_width..row * block_width + columns]);
+        }
+    }
+    Ok(output)
+}
+
+fn ldr(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    decode_block: impl Fn(&[u8], &mut [[u8; 4]]),
+) -> Result<DynamicImage> {
+    let output = decode_blocks(format, data, width, height, decode_block)?;
+    rgba8_image(output, width, height)
+}
+
+fn hdr(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    decode_block: impl Fn(&[u8], &mut [[f32; 4]]),
+) -> Result<DynamicImage> {
+    let output = decode_blocks(format, data, width, height, decode_block)?;
+    Rgba32FImage::from_raw(width as u32, height as u32, output.concat())
+        .ok_or_else(|| anyhow!("failed to build image"))
+        .map(DynamicImage::ImageRgba32F)
+}
+
+fn rgba8_image(output: Vec<[u8; 4]>, width: usize, height: usize) -> Result<DynamicImage> {
+    RgbaImage::from_raw(width as u32, height as u32, output.concat())
+        .ok_or_else(|| anyhow!("failed to build image"))
+        .map(DynamicImage::ImageRgba8)
+}
+
+fn nibble(value: u16, shift: u32) -> u8 {
+    (value >> shift & 0xf) as u8 * 17
+}
+
+fn unorm16(data: &[u8], index: usize) -> f32 {
+    u16::from_le_bytes([data[index * 2], data[index * 2 + 1]]) as f32 / 65535.
+}
+
+fn half(data: &[u8], index: usize) -> f32 {
+    f16_to_f32(u16::from_le_bytes([data[index * 2], data[index * 2 + 1]]))
+}
+
+fn float(data: &[u8], index: usize) -> f32 {
+    f32::from_le_bytes(data[index * 4..index * 4 + 4].try_into().unwrap())
+}
+
+fn f16_to_f32(value: u16) -> f32 {
+    let sign = (value >> 15) as u32;
+    let exponent = (value >> 10 & 0x1f) as u32;
+    let mantissa = (value & 0x3ff) as u32;
+    match exponent {
+        0 => {
+            let magnitude = mantissa as f32 / (1 << 24) as f32;
+            if sign == 1 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+        31 => f32::from_bits(sign << 31 | 0x7f80_0000 | mantissa << 13),
+        _ => f32::from_bits(sign << 31 | (exponent + 112) << 23 | mantissa << 13),
+    }
+}
+
+fn rgb9e5(value: u32) -> [f32; 4] {
+    let scale = 2f32.powi((value >> 27) as i32 - 24);
+    [
+        (value & 0x1ff) as f32 * scale,
+        (value >> 9 & 0x1ff) as f32 * scale,
+        (value >> 18 & 0x1ff) as f32 * scale,
+        1.,
+    ]
+}
+
+// BT.601 with studio swing luma.
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 4] {
+    let c = y as i32 - 16;
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+    let clamp = |value: i32| (value >> 8).clamp(0, 255) as u8;
+    [
+        clamp(298 * c + 409 * e + 128),
+        clamp(298 * c - 100 * d - 208 * e + 128),
+        clamp(298 * c + 516 * d + 128),
+        255,
+    ]
+}
+
+fn expand_bits(value: u32, bits: u32) -> u8 {
+    let value = value << (8 - bits);
+    (value | value >> bits) as u8
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let color = color as u32;
+    [
+        expand_bits(color >> 11 & 0x1f, 5),
+        expand_bits(color >> 5 & 0x3f, 6),
+        expand_bits(color & 0x1f, 5),
+        255,
+    ]
+}
+
+fn mix(a: [u8; 4], b: [u8; 4], weight_a: u32, weight_b: u32) -> [u8; 4] {
+    let total = weight_a + weight_b;
+    let channel = |i: usize| ((a[i] as u32 * weight_a + b[i] as u32 * weight_b) / total) as u8;
+    [channel(0), channel(1), channel(2), 255]
+}
+
+fn decode_bc1_block(block: &[u8], out: &mut [[u8; 4]], always_four_colors: bool) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (e0, e1) = (rgb565(c0), rgb565(c1));
+    let colors = if always_four_colors || c0 > c1 {
+        [e0, e1, mix(e0, e1, 2, 1), mix(e0, e1, 1, 2)]
+    } else {
+        [e0, e1, mix(e0, e1, 1, 1), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = colors[(indices >> (2 * i) & 3) as usize];
+    }
+}
+
+fn decode_bc2_block(block: &[u8], out: &mut [[u8; 4]]) {
+    decode_bc1_block(&block[8..], out, true);
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    for (i, pixel) in out.iter_mut().enumerate() {
+        pixel[3] = (alpha >> (4 * i) & 0xf) as u8 * 17;
+    }
+}
+
+fn decode_bc3_block(block: &[u8], out: &mut [[u8; 4]]) {
+    decode_bc1_block(&block[8..], out, true);
+    for (pixel, a) in out.iter_mut().zip(decode_bc4_values(&block[..8])) {
+        pixel[3] = a;
+    }
+}
+
+/// The 8 byte single channel block shared by BC3 alpha, BC4 and BC5.
+fn decode_bc4_values(block: &[u8]) -> [u8; 16] {
+    let a0 = block[0] as u32;
+    let a1 = block[1] as u32;
+    let mut palette = [a0, a1, 0, 0, 0, 0, 0, 255];
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i as usize + 1] = (a0 * (7 - i) + a1 * i) / 7;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i as usize + 1] = (a0 * (5 - i) + a1 * i) / 5;
+        }
+    }
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[(indices >> (3 * i) & 7) as usize] as u8)
+}
+
+struct BitReader {
+    value: u128,
+    position: u32,
+}
+
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        Self {
+            value: u128::from_le_bytes(block[..16].try_into().unwrap()),
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.value >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weight(bits: u32, index: u32) -> u32 {
+    match bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    }
+}
+
+// Subset of each pixel for the two subset partitions, one bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+// Pixels that store one less index bit. The first pixel is always an anchor.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+const ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+fn subset_of(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => (PARTITIONS_2[partition] >> pixel & 1) as usize,
+        3 => PARTITIONS_3[partition][pixel] as usize,
+        _ => 0,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    pixel == 0
+        || match subsets {
+            2 => pixel == ANCHORS_2[partition] as usize,
+            3 => {
+                pixel == ANCHORS_3_SECOND[partition] as usize
+                    || pixel == ANCHORS_3_THIRD[partition] as usize
+            }
+            _ => false,
+        }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const fn bc7_mode(
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    endpoint_bits: (u32, u32),
+    p_bits: (bool, bool),
+    index_bits: (u32, u32),
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits: endpoint_bits.0,
+        alpha_bits: endpoint_bits.1,
+        endpoint_p_bits: p_bits.0,
+        shared_p_bits: p_bits.1,
+        index_bits: index_bits.0,
+        secondary_index_bits: index_bits.1,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, 4, 0, 0, (4, 0), (true, false), (3, 0)),
+    bc7_mode(2, 6, 0, 0, (6, 0), (false, true), (3, 0)),
+    bc7_mode(3, 6, 0, 0, (5, 0), (false, false), (2, 0)),
+    bc7_mode(2, 6, 0, 0, (7, 0), (true, false), (2, 0)),
+    bc7_mode(1, 0, 2, 1, (5, 6), (false, false), (2, 3)),
+    bc7_mode(1, 0, 2, 0, (7, 8), (false, false), (2, 2)),
+    bc7_mode(1, 0, 0, 0, (7, 7), (true, false), (4, 0)),
+    bc7_mode(2, 6, 0, 0, (5, 5), (true, false), (2, 0)),
+];
+
+fn decode_bc7_block(block: &[u8], out: &mut [[u8; 4]]) {
+    // Blocks without a mode bit are reserved and decode to transparent black.
+    let Some(mode_index) = (0..8).find(|i| block[0] >> i & 1 == 1) else {
+        out.fill([0; 4]);
+        return;
+    };
+    let mode = &BC7_MODES[mode_index];
+    let mut bits = BitReader::new(block);
+    bits.read(mode_index as u32 + 1);
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for endpoint in &mut endpoints[..endpoint_count] {
+        endpoint[3] = bits.read(mode.alpha_bits);
+    }
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let mut p_bits = [0; 6];
+        if mode.endpoint_p_bits {
+            for p_bit in &mut p_bits[..endpoint_count] {
+                *p_bit = bits.read(1);
+            }
+        } else {
+            for subset in 0..mode.subsets {
+                let p_bit = bits.read(1);
+                p_bits[subset * 2] = p_bit;
+                p_bits[subset * 2 + 1] = p_bit;
+            }
+        }
+        for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits) {
+            for channel in endpoint.iter_mut() {
+                *channel = *channel << 1 | p_bit;
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+    let endpoints = endpoints.map(|endpoint| {
+        [
+            expand_bits(endpoint[0], color_bits),
+            expand_bits(endpoint[1], color_bits),
+            expand_bits(endpoint[2], color_bits),
+            if alpha_bits > 0 {
+                expand_bits(endpoint[3], alpha_bits)
+            } else {
+                255
+            },
+        ]
+    });
+
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, pixel) as u32;
+        *index = bits.read(mode.index_bits - anchor);
+    }
+    let mut secondary_indices = [0u32; 16];
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - (pixel == 0) as u32);
+        }
+    }
+
+    for (pixel, output) in out.iter_mut().enumerate() {
+        let subset = subset_of(mode.subsets, partition, pixel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let (color, alpha) = match (mode.secondary_index_bits, index_selection) {
+            (0, _) => (
+                (mode.index_bits, indices[pixel]),
+                (mode.index_bits, indices[pixel]),
+            ),
+            (secondary_bits, 0) => (
+                (mode.index_bits, indices[pixel]),
+                (secondary_bits, secondary_indices[pixel]),
+            ),
+            (secondary_bits, _) => (
+                (secondary_bits, secondary_indices[pixel]),
+                (mode.index_bits, indices[pixel]),
+            ),
+        };
+        let interpolate = |channel: usize, (bits, index): (u32, u32)| {
+            let weight = weight(bits, index);
+            (((64 - weight) * e0[channel] as u32 + weight * e1[channel] as u32 + 32) >> 6) as u8
+        };
+        let mut value = [
+            interpolate(0, color),
+            interpolate(1, color),
+            interpolate(2, color),
+            interpolate(3, alpha),
+        ];
+        if rotation > 0 {
+            value.swap(3, rotation as usize - 1);
+        }
+        *output = value;
+    }
+}
+
+// Endpoint fields in the BC6H bit layouts. Each channel has w, x, y and z endpoints.
+const RW: u8 = 0;
+const RX: u8 = 1;
+const RY: u8 = 2;
+const RZ: u8 = 3;
+const GW: u8 = 4;
+const GX: u8 = 5;
+const GY: u8 = 6;
+const GZ: u8 = 7;
+const BW: u8 = 8;
+const BX: u8 = 9;
+const BY: u8 = 10;
+const BZ: u8 = 11;
+const D: u8 = 12;
+
+struct Bc6hMode {
+    mode: u32,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    // Fields and bit ranges after the mode bits, written as in the format documentation.
+    // Ranges like [10:15] are stored with their bits reversed.
+    layout: &'static [(u8, u32, u32)],
+}
+
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode {
+        mode: 0,
+        transformed: true,
+        endpoint_bits: 10,
+        delta_bits: [5, 5, 5],
+        layout: &[
+            (GY, 4, 4),
+            (BY, 4, 4),
+            (BZ, 4, 4),
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 4, 0),
+            (GZ, 4, 4),
+            (GY, 3, 0),
+            (GX, 4, 0),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 4, 0),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 4, 0),
+            (BZ, 2, 2),
+            (RZ, 4, 0),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 1,
+        transformed: true,
+        endpoint_bits: 7,
+        delta_bits: [6, 6, 6],
+        layout: &[
+            (GY, 5, 5),
+            (GZ, 4, 4),
+            (GZ, 5, 5),
+            (RW, 6, 0),
+            (BZ, 0, 0),
+            (BZ, 1, 1),
+            (BY, 4, 4),
+            (GW, 6, 0),
+            (BY, 5, 5),
+            (BZ, 2, 2),
+            (GY, 4, 4),
+            (BW, 6, 0),
+            (BZ, 3, 3),
+            (BZ, 5, 5),
+            (BZ, 4, 4),
+            (RX, 5, 0),
+            (GY, 3, 0),
+            (GX, 5, 0),
+            (GZ, 3, 0),
+            (BX, 5, 0),
+            (BY, 3, 0),
+            (RY, 5, 0),
+            (RZ, 5, 0),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 2,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [5, 4, 4],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 4, 0),
+            (RW, 10, 10),
+            (GY, 3, 0),
+            (GX, 3, 0),
+            (GW, 10, 10),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 3, 0),
+            (BW, 10, 10),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 4, 0),
+            (BZ, 2, 2),
+            (RZ, 4, 0),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 6,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 5, 4],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 3, 0),
+            (RW, 10, 10),
+            (GZ, 4, 4),
+            (GY, 3, 0),
+            (GX, 4, 0),
+            (GW, 10, 10),
+            (GZ, 3, 0),
+            (BX, 3, 0),
+            (BW, 10, 10),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 3, 0),
+            (BZ, 0, 0),
+            (BZ, 2, 2),
+            (RZ, 3, 0),
+            (GY, 4, 4),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 10,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 4, 5],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 3, 0),
+            (RW, 10, 10),
+            (BY, 4, 4),
+            (GY, 3, 0),
+            (GX, 3, 0),
+            (GW, 10, 10),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 4, 0),
+            (BW, 10, 10),
+            (BY, 3, 0),
+            (RY, 3, 0),
+            (BZ, 1, 1),
+            (BZ, 2, 2),
+            (RZ, 3, 0),
+            (BZ, 4, 4),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 14,
+        transformed: true,
+        endpoint_bits: 9,
+        delta_bits: [5, 5, 5],
+        layout: &[
+            (RW, 8, 0),
+            (BY, 4, 4),
+            (GW, 8, 0),
+            (GY, 4, 4),
+            (BW, 8, 0),
+            (BZ, 4, 4),
+            (RX, 4, 0),
+            (GZ, 4, 4),
+            (GY, 3, 0),
+            (GX, 4, 0),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 4, 0),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 4, 0),
+            (BZ, 2, 2),
+            (RZ, 4, 0),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 18,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [6, 5, 5],
+        layout: &[
+            (RW, 7, 0),
+            (GZ, 4, 4),
+            (BY, 4, 4),
+            (GW, 7, 0),
+            (BZ, 2, 2),
+            (GY, 4, 4),
+            (BW, 7, 0),
+            (BZ, 3, 3),
+            (BZ, 4, 4),
+            (RX, 5, 0),
+            (GY, 3, 0),
+            (GX, 4, 0),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 4, 0),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 5, 0),
+            (RZ, 5, 0),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 22,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 6, 5],
+        layout: &[
+            (RW, 7, 0),
+            (BZ, 0, 0),
+            (BY, 4, 4),
+            (GW, 7, 0),
+            (GY, 5, 5),
+            (GY, 4, 4),
+            (BW, 7, 0),
+            (GZ, 5, 5),
+            (BZ, 4, 4),
+            (RX, 4, 0),
+            (GZ, 4, 4),
+            (GY, 3, 0),
+            (GX, 5, 0),
+            (GZ, 3, 0),
+            (BX, 4, 0),
+            (BZ, 1, 1),
+            (BY, 3, 0),
+            (RY, 4, 0),
+            (BZ, 2, 2),
+            (RZ, 4, 0),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 26,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 5, 6],
+        layout: &[
+            (RW, 7, 0),
+            (BZ, 1, 1),
+            (BY, 4, 4),
+            (GW, 7, 0),
+            (BY, 5, 5),
+            (GY, 4, 4),
+            (BW, 7, 0),
+            (BZ, 5, 5),
+            (BZ, 4, 4),
+            (RX, 4, 0),
+            (GZ, 4, 4),
+            (GY, 3, 0),
+            (GX, 4, 0),
+            (BZ, 0, 0),
+            (GZ, 3, 0),
+            (BX, 5, 0),
+            (BY, 3, 0),
+            (RY, 4, 0),
+            (BZ, 2, 2),
+            (RZ, 4, 0),
+            (BZ, 3, 3),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 30,
+        transformed: false,
+        endpoint_bits: 6,
+        delta_bits: [6, 6, 6],
+        layout: &[
+            (RW, 5, 0),
+            (GZ, 4, 4),
+            (BZ, 0, 0),
+            (BZ, 1, 1),
+            (BY, 4, 4),
+            (GW, 5, 0),
+            (GY, 5, 5),
+            (BY, 5, 5),
+            (BZ, 2, 2),
+            (GY, 4, 4),
+            (BW, 5, 0),
+            (GZ, 5, 5),
+            (BZ, 3, 3),
+            (BZ, 5, 5),
+            (BZ, 4, 4),
+            (RX, 5, 0),
+            (GY, 3, 0),
+            (GX, 5, 0),
+            (GZ, 3, 0),
+            (BX, 5, 0),
+            (BY, 3, 0),
+            (RY, 5, 0),
+            (RZ, 5, 0),
+            (D, 4, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 3,
+        transformed: false,
+        endpoint_bits: 10,
+        delta_bits: [10, 10, 10],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 9, 0),
+            (GX, 9, 0),
+            (BX, 9, 0),
+        ],
+    },
+    Bc6hMode {
+        mode: 7,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [9, 9, 9],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 8, 0),
+            (RW, 10, 10),
+            (GX, 8, 0),
+            (GW, 10, 10),
+            (BX, 8, 0),
+            (BW, 10, 10),
+        ],
+    },
+    Bc6hMode {
+        mode: 11,
+        transformed: true,
+        endpoint_bits: 12,
+        delta_bits: [8, 8, 8],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 7, 0),
+            (RW, 10, 11),
+            (GX, 7, 0),
+            (GW, 10, 11),
+            (BX, 7, 0),
+            (BW, 10, 11),
+        ],
+    },
+    Bc6hMode {
+        mode: 15,
+        transformed: true,
+        endpoint_bits: 16,
+        delta_bits: [4, 4, 4],
+        layout: &[
+            (RW, 9, 0),
+            (GW, 9, 0),
+            (BW, 9, 0),
+            (RX, 3, 0),
+            (RW, 10, 15),
+            (GX, 3, 0),
+            (GW, 10, 15),
+            (BX, 3, 0),
+            (BW, 10, 15),
+        ],
+    },
+];
+
+// Unity only uses the unsigned variant (BC6H_UF16).
+fn decode_bc6h_block(block: &[u8], out: &mut [[f32; 4]]) {
+    let mut bits = BitReader::new(block);
+    let mut mode = bits.read(2);
+    if mode > 1 {
+        mode |= bits.read(3) << 2;
+    }
+    // Reserved modes decode to black.
+    let Some(mode) = BC6H_MODES.iter().find(|m| m.mode == mode) else {
+        out.fill([0., 0., 0., 1.]);
+        return;
+    };
+
+    let mut fields = [0u32; 13];
+    for &(field, high, low) in mode.layout {
+        if high >= low {
+            for bit in low..=high {
+                fields[field as usize] |= bits.read(1) << bit;
+            }
+        } else {
+            for bit in (high..=low).rev() {
+                fields[field as usize] |= bits.read(1) << bit;
+            }
+        }
+    }
+    let two_regions = mode.layout.iter().any(|(field, _, _)| *field == D);
+    let endpoint_count = if two_regions { 4 } else { 2 };
+    let mask = (1u32 << mode.endpoint_bits) - 1;
+    let mut endpoints = [[0i32; 3]; 4];
+    for channel in 0..3 {
+        let base = fields[channel * 4];
+        endpoints[0][channel] = base as i32;
+        for endpoint in 1..endpoint_count {
+            let value = fields[channel * 4 + endpoint];
+            endpoints[endpoint][channel] = if mode.transformed {
+                let delta = sign_extend(value, mode.delta_bits[channel]);
+                (base as i32 + delta) as u32 & mask
+            } else {
+                value
+            } as i32;
+        }
+    }
+    let endpoints =
+        endpoints.map(|endpoint| endpoint.map(|value| unquantize_bc6h(value, mode.endpoint_bits)));
+
+    let partition = fields[D as usize] as usize;
+    let index_bits = if two_regions { 3 } else { 4 };
+    for (pixel, output) in out.iter_mut().enumerate() {
+        let region = if two_regions {
+            subset_of(2, partition, pixel)
+        } else {
+            0
+        };
+        let anchor = if two_regions {
+            is_anchor(2, partition, pixel)
+        } else {
+            pixel == 0
+        };
+        let weight = weight(index_bits, bits.read(index_bits - anchor as u32)) as i32;
+        let (e0, e1) = (endpoints[region * 2], endpoints[region * 2 + 1]);
+        let channel = |i: usize| {
+            let value = ((64 - weight) * e0[i] + weight * e1[i] + 32) >> 6;
+            f16_to_f32(((value * 31) >> 6) as u16)
+        };
+        *output = [channel(0), channel(1), channel(2), 1.];
+    }
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+fn unquantize_bc6h(value: i32, bits: u32) -> i32 {
+    if bits >= 15 || value == 0 {
+        value
+    } else if value == (1 << bits) - 1 {
+        0xffff
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+const ETC_MODIFIERS: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+fn bits_of(value: u64, high: u32, low: u32) -> i32 {
+    (value >> low & ((1 << (high - low + 1)) - 1)) as i32
+}
+
+fn offset(color: [i32; 3], amount: i32) -> [u8; 4] {
+    let channel = |value: i32| (value + amount).clamp(0, 255) as u8;
+    [channel(color[0]), channel(color[1]), channel(color[2]), 255]
+}
+
+/// ETC1 and ETC2 color blocks. ETC2 adds the T, H and planar modes through otherwise
+/// invalid differential colors, and punch-through alpha reuses the individual mode bit.
+fn decode_etc_block(block: &[u8], out: &mut [[u8; 4]], etc2: bool, punch_through: bool) {
+    let v = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let differential = punch_through || v >> 33 & 1 == 1;
+    let opaque = !punch_through || v >> 33 & 1 == 1;
+    let flip = v >> 32 & 1 == 1;
+
+    let expand4 = |value: i32| value * 17;
+    let expand5 = |value: i32| value << 3 | value >> 2;
+    let (c1, c2) = if differential {
+        let base = [bits_of(v, 63, 59), bits_of(v, 55, 51), bits_of(v, 47, 43)];
+        let delta = [
+            sign_extend(bits_of(v, 58, 56) as u32, 3),
+            sign_extend(bits_of(v, 50, 48) as u32, 3),
+            sign_extend(bits_of(v, 42, 40) as u32, 3),
+        ];
+        let second = [base[0] + delta[0], base[1] + delta[1], base[2] + delta[2]];
+        if etc2 {
+            if !(0..32).contains(&second[0]) {
+                return decode_etc2_t_or_h(v, out, opaque, false);
+            }
+            if !(0..32).contains(&second[1]) {
+                return decode_etc2_t_or_h(v, out, opaque, true);
+            }
+            if !(0..32).contains(&second[2]) {
+                return decode_etc2_planar(v, out);
+            }
+        }
+        (base.map(expand5), second.map(|value| expand5(value & 0x1f)))
+    } else {
+        (
+            [bits_of(v, 63, 60), bits_of(v, 55, 52), bits_of(v, 47, 44)].map(expand4),
+            [bits_of(v, 59, 56), bits_of(v, 51, 48), bits_of(v, 43, 40)].map(expand4),
+        )
+    };
+    let tables = [bits_of(v, 39, 37) as usize, bits_of(v, 36, 34) as usize];
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let (x, y) = (i % 4, i / 4);
+        let second = if flip { y >= 2 } else { x >= 2 };
+        let index = etc_pixel_index(v, x, y);
+        if !opaque && index == 2 {
+            *pixel = [0, 0, 0, 0];
+            continue;
+        }
+        let modifier = if !opaque && index == 0 {
+            0
+        } else {
+            ETC_MODIFIERS[tables[second as usize]][index]
+        };
+        *pixel = offset(if second { c2 } else { c1 }, modifier);
+    }
+}
+
+// Pixel indices are stored column by column, with the high bits in the upper half.
+fn etc_pixel_index(v: u64, x: usize, y: usize) -> usize {
+    let bit = x * 4 + y;
+    ((v >> (16 + bit) & 1) << 1 | v >> bit & 1) as usize
+}
+
+fn decode_etc2_t_or_h(v: u64, out: &mut [[u8; 4]], opaque: bool, h_mode: bool) {
+    let expand4 = |value: i32| value * 17;
+    let paint = if h_mode {
+        let c1 = [
+            bits_of(v, 62, 59),
+            bits_of(v, 58, 56) << 1 | bits_of(v, 52, 52),
+            bits_of(v, 51, 51) << 3 | bits_of(v, 49, 47),
+        ];
+        let c2 = [bits_of(v, 46, 43), bits_of(v, 42, 39), bits_of(v, 38, 35)];
+        let order = (c1[0] << 8 | c1[1] << 4 | c1[2]) >= (c2[0] << 8 | c2[1] << 4 | c2[2]);
+        let distance = ETC2_DISTANCES
+            [(bits_of(v, 34, 34) << 2 | bits_of(v, 32, 32) << 1 | order as i32) as usize];
+        let (c1, c2) = (c1.map(expand4), c2.map(expand4));
+        [
+            offset(c1, distance),
+            offset(c1, -distance),
+            offset(c2, distance),
+            offset(c2, -distance),
+        ]
+    } else {
+        let c1 = [
+            bits_of(v, 60, 59) << 2 | bits_of(v, 57, 56),
+            bits_of(v, 55, 52),
+            bits_of(v, 51, 48),
+        ]
+        .map(expand4);
+        let c2 = [bits_of(v, 47, 44), bits_of(v, 43, 40), bits_of(v, 39, 36)].map(expand4);
+        let distance = ETC2_DISTANCES[(bits_of(v, 35, 34) << 1 | bits_of(v, 32, 32)) as usize];
+        [
+            offset(c1, 0),
+            offset(c2, distance),
+            offset(c2, 0),
+            offset(c2, -distance),
+        ]
+    };
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let index = etc_pixel_index(v, i % 4, i / 4);
+        *pixel = if !opaque && index == 2 {
+            [0, 0, 0, 0]
+        } else {
+            paint[index]
+        };
+    }
+}
+
+fn decode_etc2_planar(v: u64, out: &mut [[u8; 4]]) {
+    let expand6 = |value: i32| value << 2 | value >> 4;
+    let expand7 = |value: i32| value << 1 | value >> 6;
+    let origin = [
+        expand6(bits_of(v, 62, 57)),
+        expand7(bits_of(v, 56, 56) << 6 | bits_of(v, 54, 49)),
+        expand6(bits_of(v, 48, 48) << 5 | bits_of(v, 44, 43) << 3 | bits_of(v, 41, 39)),
+    ];
+    let horizontal = [
+        expand6(bits_of(v, 38, 34) << 1 | bits_of(v, 32, 32)),
+        expand7(bits_of(v, 31, 25)),
+        expand6(bits_of(v, 24, 19)),
+    ];
+    let vertical = [
+        expand6(bits_of(v, 18, 13)),
+        expand7(bits_of(v, 12, 6)),
+        expand6(bits_of(v, 5, 0)),
+    ];
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+        let channel = |c: usize| {
+            ((x * (horizontal[c] - origin[c]) + y * (vertical[c] - origin[c]) + 4 * origin[c] + 2)
+                >> 2)
+                .clamp(0, 255) as u8
+        };
+        *pixel = [channel(0), channel(1), channel(2), 255];
+    }
+}
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Base value, multiplier and the modifier of every pixel in row-major order.
+fn eac_block(block: &[u8]) -> (u64, i32, [i32; 16]) {
+    let v = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let multiplier = bits_of(v, 55, 52);
+    let table = &EAC_MODIFIERS[bits_of(v, 51, 48) as usize];
+    let modifiers = std::array::from_fn(|i| {
+        let bit = 45 - 3 * ((i % 4) * 4 + i / 4) as u32;
+        table[bits_of(v, bit + 2, bit) as usize]
+    });
+    (v >> 56, multiplier, modifiers)
+}
+
+fn decode_eac_alpha(block: &[u8]) -> [u8; 16] {
+    let (base, multiplier, modifiers) = eac_block(block);
+    modifiers.map(|modifier| (base as i32 + modifier * multiplier).clamp(0, 255) as u8)
+}
+
+fn decode_eac_r11(block: &[u8], signed: bool) -> [f32; 16] {
+    let (base, multiplier, modifiers) = eac_block(block);
+    modifiers.map(|modifier| {
+        let modifier = if multiplier == 0 {
+            modifier
+        } else {
+            modifier * multiplier * 8
+        };
+        if signed {
+            let value = (base as u8 as i8 as i32 * 8 + modifier).clamp(-1023, 1023);
+            value as f32 / 1023.
+        } else {
+            let value = (base as i32 * 8 + 4 + modifier).clamp(0, 2047);
+            value as f32 / 2047.
+        }
+    })
+}
+
+fn decode_atc_block(block: &[u8], out: &mut [[u8; 4]]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]) as u32;
+    let c1 = u16::from_le_bytes([block[2], block[3]]) as u32;
+    let first = [
+        expand_bits(c0 >> 10 & 0x1f, 5) as i32,
+        expand_bits(c0 >> 5 & 0x1f, 5) as i32,
+        expand_bits(c0 & 0x1f, 5) as i32,
+    ];
+    let second = [
+        expand_bits(c1 >> 11 & 0x1f, 5) as i32,
+        expand_bits(c1 >> 5 & 0x3f, 6) as i32,
+        expand_bits(c1 & 0x1f, 5) as i32,
+    ];
+    let colors = if c0 & 0x8000 == 0 {
+        [
+            first,
+            std::array::from_fn(|i| (5 * first[i] + 3 * second[i]) / 8),
+            std::array::from_fn(|i| (3 * first[i] + 5 * second[i]) / 8),
+            second,
+        ]
+    } else {
+        [
+            [0; 3],
+            std::array::from_fn(|i| (first[i] - second[i] / 4).max(0)),
+            first,
+            second,
+        ]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = offset(colors[(indices >> (2 * i) & 3) as usize], 0);
+    }
+}
+
+/// PVRTC blocks are stored in Morton order and each pixel blends the colors of the four
+/// nearest blocks, so this can't go through [decode_blocks].
+fn decode_pvrtc(data: &[u8], width: usize, height: usize, two_bpp: bool) -> Result<Vec<[u8; 4]>> {
+    let block_width = if two_bpp { 8 } else { 4 };
+    let blocks_x = width.div_ceil(block_width).max(2);
+    let blocks_y = height.div_ceil(4).max(2);
+    if data.len() < blocks_x * blocks_y * 8 {
+        bail!(
+            "expected {} bytes of PVRTC image data but found {}",
+            blocks_x * blocks_y * 8,
+            data.len()
+        );
+    }
+    let full_width = blocks_x * block_width;
+    let full_height = blocks_y * 4;
+
+    let mut colors = vec![[[0f32; 4]; 2]; blocks_x * blocks_y];
+    let mut modulation = vec![0i32; full_width * full_height];
+    let mut punch_through = vec![false; full_width * full_height];
+    // 2bpp blocks can leave every other pixel to be interpolated from its neighbors.
+    let mut interpolated = vec![None; full_width * full_height];
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let offset = twiddle(blocks_x, blocks_y, bx, by) * 8;
+            let word = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            let mut bits = word as u32;
+            let color_data = (word >> 32) as u32;
+            colors[by * blocks_x + bx] = [pvrtc_color_a(color_data), pvrtc_color_b(color_data)];
+            let modulation_mode = color_data & 1 == 1;
+            for y in 0..4 {
+                for x in 0..block_width {
+                    let pixel = (by * 4 + y) * full_width + bx * block_width + x;
+                    if !two_bpp {
+                        let value = (bits >> (2 * (y * 4 + x)) & 3) as usize;
+                        if modulation_mode {
+                            modulation[pixel] = [0, 4, 4, 8][value];
+                            punch_through[pixel] = value == 2;
+                        } else {
+                            modulation[pixel] = [0, 3, 5, 8][value];
+                        }
+                    } else if !modulation_mode {
+                        modulation[pixel] = (bits >> (y * 8 + x) & 1) as i32 * 8;
+                    }
+                }
+            }
+            if two_bpp && modulation_mode {
+                let mode = if bits & 1 == 0 {
+                    PvrtcInterpolation::Both
+                } else if bits & (1 << 20) != 0 {
+                    PvrtcInterpolation::Vertical
+                } else {
+                    PvrtcInterpolation::Horizontal
+                };
+                if bits & 1 != 0 {
+                    if bits & (1 << 21) != 0 {
+                        bits |= 1 << 20;
+                    } else {
+                        bits &= !(1 << 20);
+                    }
+                }
+                if bits & 2 != 0 {
+                    bits |= 1;
+                } else {
+                    bits &= !1;
+                }
+                for y in 0..4 {
+                    for x in 0..8 {
+                        let pixel = (by * 4 + y) * full_width + bx * 8 + x;
+                        if (x ^ y) & 1 == 0 {
+                            modulation[pixel] = [0, 3, 5, 8][(bits & 3) as usize];
+                            bits >>= 2;
+                        } else {
+                            interpolated[pixel] = Some(mode);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let stored = modulation.clone();
+    let at = |x: usize, y: isize, dx: isize| {
+        let x = (x as isize + dx).rem_euclid(full_width as isize) as usize;
+        let y = y.rem_euclid(full_height as isize) as usize;
+        stored[y * full_width + x]
+    };
+    for (pixel, mode) in interpolated.iter().enumerate() {
+        let Some(mode) = mode else { continue };
+        let (x, y) = (pixel % full_width, (pixel / full_width) as isize);
+        let horizontal = at(x, y, -1) + at(x, y, 1);
+        let vertical = at(x, y - 1, 0) + at(x, y + 1, 0);
+        modulation[pixel] = match mode {
+            PvrtcInterpolation::Both => (horizontal + vertical + 2) / 4,
+            PvrtcInterpolation::Horizontal => (horizontal + 1) / 2,
+            PvrtcInterpolation::Vertical => (vertical + 1) / 2,
+        };
+    }
+
+    let mut output = vec![[0u8; 4]; width * height];
+    for y in 0..height {
+        // Block colors sit at the center of their block.
+        let (y0, fy) = split(y as isize - 2, 4, blocks_y);
+        for x in 0..width {
+            let (x0, fx) = split(x as isize - block_width as isize / 2, block_width, blocks_x);
+            let x1 = (x0 + 1) % blocks_x;
+            let y1 = (y0 + 1) % blocks_y;
+            let pixel = y * full_width + x;
+            let m = modulation[pixel] as f32 / 8.;
+            let mut value = [0u8; 4];
+            for (channel, out) in value.iter_mut().enumerate() {
+                let blend = |which: usize| {
+                    let top = colors[y0 * blocks_x + x0][which][channel] * (1. - fx)
+                        + colors[y0 * blocks_x + x1][which][channel] * fx;
+                    let bottom = colors[y1 * blocks_x + x0][which][channel] * (1. - fx)
+                        + colors[y1 * blocks_x + x1][which][channel] * fx;
+                    top * (1. - fy) + bottom * fy
+                };
+                *out = (blend(0) * (1. - m) + blend(1) * m).round() as u8;
+            }
+            if punch_through[pixel] {
+                value[3] = 0;
+            }
+            output[y * width + x] = value;
+        }
+    }
+    Ok(output)
+}
+
+#[derive(Clone, Copy)]
+enum PvrtcInterpolation {
+    Both,
+    Horizontal,
+    Vertical,
+}
+
+/// The block to the top or left of a position and how far the position is towards the next one.
+fn split(position: isize, block_size: usize, blocks: usize) -> (usize, f32) {
+    let block = position.div_euclid(block_size as isize);
+    let fraction = position.rem_euclid(block_size as isize) as f32 / block_size as f32;
+    (block.rem_euclid(blocks as isize) as usize, fraction)
+}
+
+fn twiddle(blocks_x: usize, blocks_y: usize, x: usize, y: usize) -> usize {
+    let (min_dimension, mut remainder) = if blocks_y < blocks_x {
+        (blocks_y, x)
+    } else {
+        (blocks_x, y)
+    };
+    let mut twiddled = 0;
+    let mut shift = 0;
+    while 1 << shift < min_dimension {
+        twiddled |= (y >> shift & 1) << (2 * shift);
+        twiddled |= (x >> shift & 1) << (2 * shift + 1);
+        shift += 1;
+    }
+    remainder >>= shift;
+    twiddled | remainder << (2 * shift)
+}
+
+fn pvrtc_color(red: u32, green: u32, blue: u32, alpha: u32) -> [f32; 4] {
+    [
+        expand_bits(red, 5) as f32,
+        expand_bits(green, 5) as f32,
+        expand_bits(blue, 5) as f32,
+        (alpha * 17) as f32,
+    ]
+}
+
+fn pvrtc_color_a(data: u32) -> [f32; 4] {
+    if data & 0x8000 != 0 {
+        let blue = data >> 1 & 0xf;
+        pvrtc_color(
+            data >> 10 & 0x1f,
+            data >> 5 & 0x1f,
+            blue << 1 | blue >> 3,
+            0xf,
+        )
+    } else {
+        let (red, green, blue) = (data >> 8 & 0xf, data >> 4 & 0xf, data >> 1 & 0x7);
+        pvrtc_color(
+            red << 1 | red >> 3,
+            green << 1 | green >> 3,
+            blue << 2 | blue >> 1,
+            (data >> 12 & 0x7) << 1,
+        )
+    }
+}
+
+fn pvrtc_color_b(data: u32) -> [f32; 4] {
+    if data & 0x8000_0000 != 0 {
+        pvrtc_color(data >> 26 & 0x1f, data >> 21 & 0x1f, data >> 16 & 0x1f, 0xf)
+    } else {
+        let (red, green, blue) = (data >> 24 & 0xf, data >> 20 & 0xf, data >> 16 & 0xf);
+        pvrtc_color(
+            red << 1 | red >> 3,
+            green << 1 | green >> 3,
+            blue << 1 | blue >> 3,
+            (data >> 28 & 0x7) << 1,
+        )
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{bail, Result};
+use image::{DynamicImage, ImageBuffer, Pixel, RgbaImage};
+use intel_tex_2::RgbaSurface;
+
+use crate::TextureFormat;
+
+/// Encode an image into a single unswizzled surface of the given format. ASTC is limited to
+/// the 4x4 through 8x8 block sizes ISPC supports.
+pub(crate) fn encode_image(format: TextureFormat, image: &DynamicImage) -> Result<Vec<u8>> {
+    let data = match format {
+        TextureFormat::Alpha8 => encode_pixels(image, |[_, _, _, a]| vec![a]),
+        TextureFormat::R8 => encode_pixels(image, |[r, _, _, _]| vec![r]),
+        TextureFormat::RG16 => encode_pixels(image, |[r, g, _, _]| vec![r, g]),
+        TextureFormat::R16 => image
+            .to_rgba32f()
+            .pixels()
+            .flat_map(|pixel| unorm16(pixel.0[0]).to_le_bytes())
+            .collect(),
+        TextureFormat::RG32 => image
+            .to_rgba32f()
+            .pixels()
+            .flat_map(|pixel| [unorm16(pixel.0[0]), unorm16(pixel.0[1])])
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        TextureFormat::RGB24 => encode_pixels(image, |[r, g, b, _]| vec![r, g, b]),
+        TextureFormat::BGR24 => encode_pixels(image, |[r, g, b, _]| vec![b, g, r]),
+        TextureFormat::RGBA32 => encode_pixels(image, |pixel| pixel.to_vec()),
+        TextureFormat::ARGB32 => encode_pixels(image, |[r, g, b, a]| vec![a, r, g, b]),
+        TextureFormat::BGRA32 => encode_pixels(image, |[r, g, b, a]| vec![b, g, r, a]),
+        TextureFormat::RGB565 => encode_pixels(image, |[r, g, b, _]| {
+            let value = (r as u16 >> 3) << 11 | (g as u16 >> 2) << 5 | b as u16 >> 3;
+            value.to_le_bytes().to_vec()
+        }),
+        TextureFormat::ARGB4444 => encode_pixels(image, |[r, g, b, a]| {
+            let value = nibbles(a, r, g, b);
+            value.to_le_bytes().to_vec()
+        }),
+        TextureFormat::RGBA4444 => encode_pixels(image, |[r, g, b, a]| {
+            let value = nibbles(r, g, b, a);
+            value.to_le_bytes().to_vec()
+        }),
+        TextureFormat::DXT1 => {
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            intel_tex_2::bc1::compress_blocks(&surface(&image))
+        }
+        TextureFormat::DXT3 => {
+            // BC3 color blocks are always in four color mode, so they can be reused for BC2.
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            let mut data = intel_tex_2::bc3::compress_blocks(&surface(&image));
+            for (i, block) in data.chunks_exact_mut(16).enumerate() {
+                let alpha = block_pixels(&image, i).map(|[_, _, _, a]| (a as u64 * 15 + 127) / 255);
+                let alpha = alpha
+                    .iter()
+                    .enumerate()
+                    .fold(0, |bits, (i, a)| bits | a << (4 * i));
+                block[..8].copy_from_slice(&alpha.to_le_bytes());
+            }
+            data
+        }
+        TextureFormat::DXT5 => {
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            intel_tex_2::bc3::compress_blocks(&surface(&image))
+        }
+        TextureFormat::BC4 => {
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            (0..block_count(&image))
+                .flat_map(|i| encode_bc4_block(block_pixels(&image, i).map(|[r, _, _, _]| r)))
+                .collect()
+        }
+        TextureFormat::BC5 => {
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            (0..block_count(&image))
+                .flat_map(|i| {
+                    let pixels = block_pixels(&image, i);
+                    let mut block = encode_bc4_block(pixels.map(|[r, _, _, _]| r)).to_vec();
+                    block.extend(encode_bc4_block(pixels.map(|[_, g, _, _]| g)));
+                    block
+                })
+                .collect()
+        }
+        TextureFormat::BC6H => {
+            let image = pad_to_blocks(&image.to_rgba32f(), 4, 4);
+            let (width, height) = image.dimensions();
+            // The encoder takes RGBA half floats. BC6H is unsigned, so negative values clamp to 0.
+            let halves: Vec<u8> = image
+                .into_raw()
+                .into_iter()
+                .flat_map(|value| f32_to_unsigned_f16(value).to_le_bytes())
+                .collect();
+            let surface = RgbaSurface {
+                data: &halves,
+                width,
+                height,
+                stride: width * 8,
+            };
+            intel_tex_2::bc6h::compress_blocks(&intel_tex_2::bc6h::basic_settings(), &surface)
+        }
+        TextureFormat::BC7 => {
+            let image = pad_to_blocks(&image.to_rgba8(), 4, 4);
+            intel_tex_2::bc7::compress_blocks(
+                &intel_tex_2::bc7::alpha_basic_settings(),
+                &surface(&image),
+            )
+        }
+        TextureFormat::ASTC_RGB_4x4
+        | TextureFormat::ASTC_RGB_5x5
+        | TextureFormat::ASTC_RGB_6x6
+        | TextureFormat::ASTC_RGB_8x8
+        | TextureFormat::ASTC_RGBA_4x4
+        | TextureFormat::ASTC_RGBA_5x5
+        | TextureFormat::ASTC_RGBA_6x6
+        | TextureFormat::ASTC_RGBA_8x8 => {
+            let (block_width, block_height, _) = format.block_info().unwrap();
+            let image = pad_to_blocks(&image.to_rgba8(), block_width as u32, block_height as u32);
+            let settings =
+                intel_tex_2::astc::alpha_fast_settings(block_width as u32, block_height as u32);
+            intel_tex_2::astc::compress_blocks(&settings, &surface(&image))
+        }
+        TextureFormat::ASTC_RGB_10x10
+        | TextureFormat::ASTC_RGB_12x12
+        | TextureFormat::ASTC_RGBA_10x10
+        | TextureFormat::ASTC_RGBA_12x12 => bail!(
+            "encoding to texture format '{:?}' is not supported: ISPC's ASTC encoder only handles blocks up to 8x8",
+            format
+        ),
+        _ => bail!("encoding to texture format '{:?}' is not supported", format),
+    };
+    Ok(data)
+}
+
+fn encode_pixels(image: &DynamicImage, encode_pixel: impl Fn([u8; 4]) -> Vec<u8>) -> Vec<u8> {
+    image
+        .to_rgba8()
+        .pixels()
+        .flat_map(|pixel| encode_pixel(pixel.0))
+        .collect()
+}
+
+fn nibbles(a: u8, b: u8, c: u8, d: u8) -> u16 {
+    [a, b, c, d].iter().fold(0, |value, channel| {
+        (value << 4) | ((*channel as u16 * 15 + 127) / 255)
+    })
+}
+
+fn unorm16(value: f32) -> u16 {
+    (value.clamp(0., 1.) * 65535.).round() as u16
+}
+
+fn surface(image: &RgbaImage) -> RgbaSurface<'_> {
+    RgbaSurface {
+        data: image.as_raw(),
+        width: image.width(),
+        height: image.height(),
+        stride: image.width() * 4,
+    }
+}
+
+/// Extend the image to a whole number of blocks by repeating the last row and column.
+fn pad_to_blocks<P: Pixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    block_width: u32,
+    block_height: u32,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    let (width, height) = image.dimensions();
+    let padded_width = width.div_ceil(block_width) * block_width;
+    let padded_height = height.div_ceil(block_height) * block_height;
+    if (padded_width, padded_height) == (width, height) {
+        return image.clone();
+    }
+    ImageBuffer::from_fn(padded_width, padded_height, |x, y| {
+        *image.get_pixel(x.min(width - 1), y.min(height - 1))
+    })
+}
+
+fn block_count(image: &RgbaImage) -> usize {
+    (image.width() / 4 * image.height() / 4) as usize
+}
+
+fn block_pixels(image: &RgbaImage, block: usize) -> [[u8; 4]; 16] {
+    let blocks_x = (image.width() / 4) as usize;
+    let (x0, y0) = (block % blocks_x * 4, block / blocks_x * 4);
+    std::array::from_fn(|i| image.get_pixel((x0 + i % 4) as u32, (y0 + i / 4) as u32).0)
+}
+
+/// Encode 16 values with the eight value interpolation mode, using the block's range as endpoints.
+fn encode_bc4_block(values: [u8; 16]) -> [u8; 8] {
+    let max = *values.iter().max().unwrap();
+    let min = *values.iter().min().unwrap();
+    let mut block = [max, min, 0, 0, 0, 0, 0, 0];
+    if max == min {
+        return block;
+    }
+    let palette: [u32; 8] = std::array::from_fn(|i| match i {
+        0 => max as u32,
+        1 => min as u32,
+        _ => (max as u32 * (8 - i as u32) + min as u32 * (i as u32 - 1)) / 7,
+    });
+    let indices = values.iter().enumerate().fold(0u64, |bits, (i, value)| {
+        let index = (0..8)
+            .min_by_key(|index| palette[*index].abs_diff(*value as u32))
+            .unwrap() as u64;
+        bits | index << (3 * i)
+    });
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+fn f32_to_unsigned_f16(value: f32) -> u16 {
+    let value = value.clamp(0., 65504.);
+    if value < 2f32.powi(-14) {
+        // Subnormal.
+        return (value * (1 << 24) as f32).round() as u16;
+    }
+    let bits = value.to_bits();
+    let exponent = (bits >> 23) as i32 - 127 + 15;
+    let mantissa = bits >> 13 & 0x3ff;
+    let half = (exponent as u32) << 10 | mantissa;
+    // Round to nearest, letting the carry roll into the exponent.
+    (half + (bits >> 12 & 1)).min(0x7bff) as u16
+}
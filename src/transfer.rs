@@ -0,0 +1,412 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{anyhow, bail, Context, Result};
+use binrw::NullString;
+
+use crate::references::{collect_pptrs, remap_pptrs, unparsed_value};
+use crate::{
+    Asset, AssetExternal, AssetFile, AssetReferences, AssetScript, Bundle, BundleFile, PPtr,
+    TypeTreeValue, UString,
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct ImportOptions {
+    /// Source path IDs to point at an existing object in the target file instead of copying.
+    /// Useful for binding an accessory's bones to the target skeleton.
+    pub redirects: HashMap<i64, PPtr>,
+    /// Source path IDs to leave in the source file and reference through an external.
+    pub external: HashSet<i64>,
+    /// External path of the source file (ex. "archive:/CAB-.../CAB-..."). Required with `external`.
+    pub source_path: Option<String>,
+    /// First path ID handed out to copied assets. Defaults to one past the largest path ID in use.
+    pub first_path_id: Option<i64>,
+    /// Name of the root in the target's AssetBundle container. Defaults to the root's name in
+    /// the source container.
+    pub container_name: Option<String>,
+}
+
+impl AssetFile {
+    /// Copy `root_path_id` and every local asset it references from `other` into this file.
+    /// Returns a map from source path IDs to the path IDs of the copies.
+    ///
+    /// Transform parents are not followed, so importing a GameObject copies its subtree rather
+    /// than the whole hierarchy. Unparsed assets are read and rewritten through their type tree,
+    /// so an unparsed asset without one can't be imported. References to objects that were not copied, redirected or
+    /// linked externally (like the root's parent) are nulled. If this file has an AssetBundle,
+    /// the root is added to its container so [`AssetFile::gc`] keeps it.
+    ///
+    /// Assets that stream their data from a resource file can't be copied on their own, use
+    /// [`Bundle::import_from`] for those.
+    pub fn import_from(
+        &mut self,
+        other: &AssetFile,
+        root_path_id: i64,
+        options: &ImportOptions,
+    ) -> Result<HashMap<i64, i64>> {
+        let closure = import_closure(other, root_path_id, options)?;
+        for path_id in &closure {
+            if let Some(stream_data) = other
+                .get_asset_by_path_id(*path_id)
+                .and_then(|asset| asset.stream_data())
+            {
+                bail!(
+                    "path ID '{}' streams its data from '{}', import it through its bundle instead",
+                    path_id,
+                    stream_data.path.0
+                );
+            }
+        }
+        self.copy_assets(other, root_path_id, &closure, options)
+    }
+
+    fn copy_assets(
+        &mut self,
+        other: &AssetFile,
+        root_path_id: i64,
+        closure: &[i64],
+        options: &ImportOptions,
+    ) -> Result<HashMap<i64, i64>> {
+        let has_container = self
+            .assets
+            .iter()
+            .any(|asset| matches!(asset, Asset::Bundle(_)));
+        let container_name = match options.container_name.clone() {
+            Some(name) => Some(name),
+            None => container_name(other, root_path_id),
+        };
+        if has_container && container_name.is_none() {
+            bail!(
+                "path ID '{}' is not in the source container, so a container name is required",
+                root_path_id
+            );
+        }
+
+        let mut next_path_id = options.first_path_id.unwrap_or_else(|| {
+            self.path_ids
+                .iter()
+                .map(|path_id| *path_id as i64)
+                .max()
+                .unwrap_or_default()
+                + 1
+        });
+        let mut mapping = HashMap::new();
+        for path_id in closure {
+            while self.get_asset_by_path_id(next_path_id).is_some() {
+                next_path_id += 1;
+            }
+            mapping.insert(*path_id, next_path_id);
+            next_path_id += 1;
+        }
+
+        let mut remapper = Remapper {
+            mapping: &mapping,
+            options,
+            other,
+            source_file_id: None,
+            file_ids: HashMap::new(),
+        };
+        for path_id in closure {
+            let mut asset = other
+                .get_asset_by_path_id(*path_id)
+                .cloned()
+                .ok_or_else(|| anyhow!("source file does not contain path ID '{}'", path_id))?;
+            if let Asset::Unparsed(unparsed) = &mut asset {
+                let ty = other.get_type(unparsed.type_hash).ok_or_else(|| {
+                    anyhow!("source file has no type with hash '{}'", unparsed.type_hash)
+                })?;
+                let mut value = unparsed_value(other, unparsed)?;
+                remap_pptrs(&mut value, &mut |pptr| remapper.remap(self, pptr))?;
+                unparsed.write_value(&ty.type_tree, &value)?;
+            }
+            for pptr in asset.references_mut() {
+                *pptr = remapper.remap(self, pptr)?;
+            }
+            self.import_type(other, &asset, &mut remapper)?;
+            self.insert_asset(mapping[path_id], asset)?;
+        }
+
+        if let Some(name) = container_name {
+            let dependencies: Vec<i64> = closure.iter().map(|path_id| mapping[path_id]).collect();
+            self.add_container_entry(&name, mapping[&root_path_id], &dependencies);
+        }
+        Ok(mapping)
+    }
+
+    fn import_type(
+        &mut self,
+        other: &AssetFile,
+        asset: &Asset,
+        remapper: &mut Remapper,
+    ) -> Result<()> {
+        if self.asset_type(asset).is_some() {
+            return Ok(());
+        }
+        let mut ty = other.asset_type(asset).cloned().ok_or_else(|| {
+            anyhow!("source file has no type with hash '{}'", asset.type_hash())
+        })?;
+        if ty.script_type_index >= 0 {
+            let script = other
+                .scripts
+                .get(ty.script_type_index as usize)
+                .ok_or_else(|| {
+                    anyhow!("script index '{}' is out of bounds", ty.script_type_index)
+                })?;
+            let pptr = remapper.remap(
+                self,
+                &PPtr {
+                    file_id: script.file_id as i32,
+                    path_id: script.object_id as i64,
+                },
+            )?;
+            ty.script_type_index = self.scripts.len() as i16;
+            self.scripts.push(AssetScript {
+                file_id: pptr.file_id as u32,
+                object_id: pptr.path_id as u64,
+            });
+        }
+        self.types.push(ty);
+        Ok(())
+    }
+
+    fn find_or_add_external(&mut self, external: AssetExternal) -> i32 {
+        let index = match self.externals.iter().position(|elem| {
+            elem.path == external.path && elem.guid == external.guid && elem.ty == external.ty
+        }) {
+            Some(index) => index,
+            None => {
+                self.externals.push(external);
+                self.externals.len() - 1
+            }
+        };
+        index as i32 + 1
+    }
+}
+
+impl Bundle {
+    /// Import between the assets files of two bundles, like [`AssetFile::import_from`].
+    /// Streamed data of the copied assets is appended to this bundle's resource file.
+    pub fn import_from(
+        &mut self,
+        other: &Bundle,
+        root_path_id: i64,
+        options: &ImportOptions,
+    ) -> Result<HashMap<i64, i64>> {
+        let source = other
+            .files
+            .values()
+            .find_map(|file| match file {
+                BundleFile::Assets(asset_file) => Some(asset_file),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("source bundle does not contain an assets file"))?;
+        let closure = import_closure(source, root_path_id, options)?;
+        let mut streamed = vec![];
+        for path_id in &closure {
+            let Some(stream_data) = source
+                .get_asset_by_path_id(*path_id)
+                .and_then(|asset| asset.stream_data())
+            else {
+                continue;
+            };
+            let start = stream_data.offset as usize;
+            let end = start + stream_data.size as usize;
+            let data = other
+                .stream_resource(stream_data)?
+                .get(start..end)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "stream data for path ID '{}' is outside the resource file",
+                        path_id
+                    )
+                })?;
+            streamed.push((*path_id, data));
+        }
+
+        let (cab, target) = self
+            .files
+            .iter_mut()
+            .find_map(|(name, file)| match file {
+                BundleFile::Assets(asset_file) => Some((name.clone(), asset_file)),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("bundle does not contain an assets file"))?;
+        let mapping = target.copy_assets(source, root_path_id, &closure, options)?;
+        if streamed.is_empty() {
+            return Ok(mapping);
+        }
+
+        let resource_name = self
+            .files
+            .keys()
+            .find(|name| name.ends_with(".resS"))
+            .cloned()
+            .unwrap_or_else(|| format!("{}.resS", cab));
+        let resource_path = format!("archive:/{}/{}", cab, resource_name);
+        let resource = match self
+            .files
+            .entry(resource_name)
+            .or_insert_with(|| BundleFile::Raw(vec![]))
+        {
+            BundleFile::Raw(resource) => resource,
+            BundleFile::Assets(_) => bail!("resource file is an assets file"),
+        };
+        let mut offsets = vec![];
+        for (path_id, data) in streamed {
+            // Keep the same 16 byte alignment Unity uses between streamed blocks.
+            resource.resize(resource.len().next_multiple_of(16), 0);
+            offsets.push((mapping[&path_id], resource.len() as u64));
+            resource.extend_from_slice(data);
+        }
+        let Some(BundleFile::Assets(target)) = self.files.get_mut(&cab) else {
+            bail!("bundle does not contain an assets file");
+        };
+        for (path_id, offset) in offsets {
+            if let Some(stream_data) = target
+                .get_asset_by_path_id_mut(path_id)
+                .and_then(|asset| asset.stream_data_mut())
+            {
+                stream_data.offset = offset;
+                stream_data.path = UString(resource_path.clone());
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+/// The source asset and every local asset it references, in breadth first order.
+fn import_closure(
+    other: &AssetFile,
+    root_path_id: i64,
+    options: &ImportOptions,
+) -> Result<Vec<i64>> {
+    if other.get_asset_by_path_id(root_path_id).is_none() {
+        bail!("source file does not contain path ID '{}'", root_path_id);
+    }
+    if !options.external.is_empty() && options.source_path.is_none() {
+        bail!("a source path is required to reference assets externally");
+    }
+
+    let mut visited = HashSet::new();
+    let mut closure = vec![];
+    let mut queue = VecDeque::from([root_path_id]);
+    while let Some(path_id) = queue.pop_front() {
+        if options.redirects.contains_key(&path_id)
+            || options.external.contains(&path_id)
+            || !visited.insert(path_id)
+        {
+            continue;
+        }
+        if let Some(asset) = other.get_asset_by_path_id(path_id) {
+            closure.push(path_id);
+            let edges = import_edges(other, asset)
+                .with_context(|| format!("failed to follow the references of path ID '{}'", path_id))?;
+            queue.extend(
+                edges
+                    .into_iter()
+                    .filter(|pptr| pptr.file_id == 0 && pptr.path_id != 0)
+                    .map(|pptr| pptr.path_id),
+            );
+        }
+    }
+    Ok(closure)
+}
+
+/// The name `path_id` is listed under in the file's AssetBundle container.
+fn container_name(asset_file: &AssetFile, path_id: i64) -> Option<String> {
+    asset_file.assets.iter().find_map(|asset| match asset {
+        Asset::Bundle(bundle) => bundle
+            .container_map
+            .iter()
+            .find(|(_, info)| info.asset.file_id == 0 && info.asset.path_id == path_id)
+            .map(|(name, _)| name.0.clone()),
+        _ => None,
+    })
+}
+
+fn import_edges(asset_file: &AssetFile, asset: &Asset) -> Result<Vec<PPtr>> {
+    match asset {
+        Asset::Transform(transform) => Ok(std::iter::once(&transform.game_object)
+            .chain(transform.children.iter())
+            .cloned()
+            .collect()),
+        // Covers RectTransform and other unparsed transforms.
+        Asset::Unparsed(unparsed) => {
+            let mut edges = vec![];
+            match unparsed_value(asset_file, unparsed)? {
+                TypeTreeValue::Struct(fields) => {
+                    for (_, value) in fields.iter().filter(|(name, _)| *name != "m_Father") {
+                        collect_pptrs(value, &mut edges);
+                    }
+                }
+                value => collect_pptrs(&value, &mut edges),
+            }
+            Ok(edges)
+        }
+        _ => Ok(asset.references().into_iter().cloned().collect()),
+    }
+}
+
+struct Remapper<'a> {
+    mapping: &'a HashMap<i64, i64>,
+    options: &'a ImportOptions,
+    other: &'a AssetFile,
+    source_file_id: Option<i32>,
+    file_ids: HashMap<i32, i32>,
+}
+
+impl Remapper<'_> {
+    fn remap(&mut self, target: &mut AssetFile, pptr: &PPtr) -> Result<PPtr> {
+        if pptr.path_id == 0 {
+            return Ok(pptr.clone());
+        }
+        if pptr.file_id != 0 {
+            let file_id = match self.file_ids.get(&pptr.file_id) {
+                Some(file_id) => *file_id,
+                None => {
+                    let external = self
+                        .other
+                        .externals
+                        .get(pptr.file_id as usize - 1)
+                        .cloned()
+                        .ok_or_else(|| anyhow!("file ID '{}' is out of bounds", pptr.file_id))?;
+                    let file_id = target.find_or_add_external(external);
+                    self.file_ids.insert(pptr.file_id, file_id);
+                    file_id
+                }
+            };
+            return Ok(PPtr {
+                file_id,
+                path_id: pptr.path_id,
+            });
+        }
+        if let Some(path_id) = self.mapping.get(&pptr.path_id) {
+            Ok(PPtr {
+                file_id: 0,
+                path_id: *path_id,
+            })
+        } else if let Some(redirect) = self.options.redirects.get(&pptr.path_id) {
+            Ok(redirect.clone())
+        } else if self.options.external.contains(&pptr.path_id) {
+            let file_id = match self.source_file_id {
+                Some(file_id) => file_id,
+                None => {
+                    let path = self.options.source_path.clone().unwrap_or_default();
+                    let file_id = target.find_or_add_external(AssetExternal {
+                        unknown: NullString::default(),
+                        guid: 0,
+                        ty: 0,
+                        path: NullString::from(path),
+                    });
+                    self.source_file_id = Some(file_id);
+                    file_id
+                }
+            };
+            Ok(PPtr {
+                file_id,
+                path_id: pptr.path_id,
+            })
+        } else {
+            Ok(PPtr::default())
+        }
+    }
+}
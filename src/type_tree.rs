@@ -0,0 +1,566 @@
+use std::fmt::Display;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use anyhow::{anyhow, bail, Context, Result};
+use binrw::{BinRead, BinWrite, Endian};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{Asset, AssetFile, AssetFileType, AssetFileTypeTree, AssetReadOptions, Unparsed};
+
+const ALIGN_FLAG: i32 = 0x4000;
+
+/// Dynamic view of a serialized object, decoded using its type tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeTreeValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    /// Byte arrays, TypelessData and strings that aren't valid UTF-8.
+    Bytes(Vec<u8>),
+    Array(Vec<TypeTreeValue>),
+    Map(Vec<(TypeTreeValue, TypeTreeValue)>),
+    Struct(IndexMap<String, TypeTreeValue>),
+}
+
+impl TypeTreeValue {
+    pub fn get(&self, field: &str) -> Option<&TypeTreeValue> {
+        match self {
+            TypeTreeValue::Struct(fields) => fields.get(field),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, field: &str) -> Option<&mut TypeTreeValue> {
+        match self {
+            TypeTreeValue::Struct(fields) => fields.get_mut(field),
+            _ => None,
+        }
+    }
+
+    /// Follow a dotted path of field names and array indices (ex. "m_Component.0.component").
+    pub fn get_path(&self, path: &str) -> Option<&TypeTreeValue> {
+        path.split('.').try_fold(self, |value, key| match value {
+            TypeTreeValue::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => value.get(key),
+        })
+    }
+
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut TypeTreeValue> {
+        path.split('.').try_fold(self, |value, key| match value {
+            TypeTreeValue::Array(items) => items.get_mut(key.parse::<usize>().ok()?),
+            _ => value.get_mut(key),
+        })
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TypeTreeValue::Bool(value) => Some(*value),
+            TypeTreeValue::Int(value) => Some(*value != 0),
+            TypeTreeValue::UInt(value) => Some(*value != 0),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            TypeTreeValue::Bool(value) => Some(*value as i64),
+            TypeTreeValue::Int(value) => Some(*value),
+            TypeTreeValue::UInt(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            TypeTreeValue::Bool(value) => Some(*value as u64),
+            TypeTreeValue::Int(value) => Some(*value as u64),
+            TypeTreeValue::UInt(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            TypeTreeValue::Float(value) => Some(*value),
+            TypeTreeValue::Int(value) => Some(*value as f64),
+            TypeTreeValue::UInt(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TypeTreeValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            TypeTreeValue::Bytes(value) => Some(value),
+            TypeTreeValue::String(value) => Some(value.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[TypeTreeValue]> {
+        match self {
+            TypeTreeValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// A type tree node with its strings resolved and children attached.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeTreeNodeInfo {
+    pub name: String,
+    pub type_name: String,
+    pub level: u8,
+    pub version: u16,
+    pub type_flags: u8,
+    pub byte_size: i32,
+    pub index: i32,
+    pub meta_flag: i32,
+    pub children: Vec<TypeTreeNodeInfo>,
+}
+
+impl TypeTreeNodeInfo {
+    pub fn is_array(&self) -> bool {
+        self.type_flags & 1 != 0
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.meta_flag & ALIGN_FLAG != 0
+    }
+
+    fn child(&self, index: usize) -> Result<&TypeTreeNodeInfo> {
+        self.children
+            .get(index)
+            .ok_or_else(|| anyhow!("type '{}' is missing child {}", self.type_name, index))
+    }
+
+    // vector, set, staticvector, etc. are a wrapper around a single Array node.
+    pub(crate) fn inner_array(&self) -> Option<&TypeTreeNodeInfo> {
+        match self.children.as_slice() {
+            [child] if child.is_array() => Some(child),
+            _ => None,
+        }
+    }
+
+    fn write_tree(&self, out: &mut String) {
+        out.push_str(&" ".repeat(self.level as usize * 4));
+        out.push_str(&format!(
+            "{}: {} (size {}, flags 0x{:X}",
+            self.name, self.type_name, self.byte_size, self.meta_flag
+        ));
+        if self.is_array() {
+            out.push_str(", array");
+        }
+        out.push_str(")\n");
+        for child in &self.children {
+            child.write_tree(out);
+        }
+    }
+}
+
+impl Display for TypeTreeNodeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = String::new();
+        self.write_tree(&mut out);
+        write!(f, "{}", out)
+    }
+}
+
+impl AssetFileTypeTree {
+    /// Resolve the flat node list into a tree. Most type trees have a single root.
+    pub fn to_nodes(&self) -> Result<Vec<TypeTreeNodeInfo>> {
+        let mut index = 0;
+        let mut roots = vec![];
+        while index < self.nodes.len() {
+            roots.push(self.build_node(&mut index)?);
+        }
+        Ok(roots)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.to_nodes()?)?)
+    }
+
+    /// Indented, human readable listing of every node.
+    pub fn to_tree_string(&self) -> Result<String> {
+        Ok(self
+            .to_nodes()?
+            .iter()
+            .map(|node| node.to_string())
+            .collect())
+    }
+
+    pub(crate) fn root(&self) -> Result<TypeTreeNodeInfo> {
+        let mut index = 0;
+        self.build_node(&mut index)
+    }
+
+    fn build_node(&self, index: &mut usize) -> Result<TypeTreeNodeInfo> {
+        let node = self
+            .nodes
+            .get(*index)
+            .ok_or_else(|| anyhow!("type tree is empty"))?;
+        *index += 1;
+        let mut children = vec![];
+        while let Some(child) = self.nodes.get(*index) {
+            if child.level <= node.level {
+                break;
+            }
+            children.push(self.build_node(index)?);
+        }
+        Ok(TypeTreeNodeInfo {
+            name: self.get_string(node.name_str_offset)?,
+            type_name: self.get_string(node.type_str_offset)?,
+            level: node.level,
+            version: node.node_version,
+            type_flags: node.type_flags,
+            byte_size: node.byte_size,
+            index: node.index,
+            meta_flag: node.meta_flag,
+            children,
+        })
+    }
+
+    /// Decode a serialized object into a dynamic value.
+    pub fn read_value(&self, data: &[u8]) -> Result<TypeTreeValue> {
+        let root = self.root()?;
+        let mut cursor = Cursor::new(data);
+        read_node(&root, &mut cursor)
+            .with_context(|| format!("failed to read '{}'", root.type_name))
+    }
+
+    /// Encode a dynamic value using this type tree.
+    pub fn write_value(&self, value: &TypeTreeValue) -> Result<Vec<u8>> {
+        let root = self.root()?;
+        let mut cursor = Cursor::new(vec![]);
+        write_node(&root, value, &mut cursor)
+            .with_context(|| format!("failed to write '{}'", root.type_name))?;
+        Ok(cursor.into_inner())
+    }
+}
+
+fn align<S: Seek>(stream: &mut S) -> Result<()> {
+    let position = stream.stream_position()?;
+    if position % 4 != 0 {
+        stream.seek(SeekFrom::Current((4 - position % 4) as i64))?;
+    }
+    Ok(())
+}
+
+fn read_node<R: Read + Seek>(node: &TypeTreeNodeInfo, reader: &mut R) -> Result<TypeTreeValue> {
+    let value = match node.type_name.as_str() {
+        "bool" => TypeTreeValue::Bool(reader.read_u8()? != 0),
+        "SInt8" => TypeTreeValue::Int(reader.read_i8()? as i64),
+        "UInt8" | "char" => TypeTreeValue::UInt(reader.read_u8()? as u64),
+        "SInt16" | "short" => TypeTreeValue::Int(reader.read_i16::<LittleEndian>()? as i64),
+        "UInt16" | "unsigned short" => {
+            TypeTreeValue::UInt(reader.read_u16::<LittleEndian>()? as u64)
+        }
+        "SInt32" | "int" => TypeTreeValue::Int(reader.read_i32::<LittleEndian>()? as i64),
+        "UInt32" | "unsigned int" | "Type*" => {
+            TypeTreeValue::UInt(reader.read_u32::<LittleEndian>()? as u64)
+        }
+        "SInt64" | "long long" => TypeTreeValue::Int(reader.read_i64::<LittleEndian>()?),
+        "UInt64" | "unsigned long long" | "FileSize" => {
+            TypeTreeValue::UInt(reader.read_u64::<LittleEndian>()?)
+        }
+        "float" => TypeTreeValue::Float(reader.read_f32::<LittleEndian>()? as f64),
+        "double" => TypeTreeValue::Float(reader.read_f64::<LittleEndian>()?),
+        "string" => {
+            let array = node.child(0)?;
+            let bytes = read_bytes(reader, array.child(1)?)?;
+            if array.is_aligned() {
+                align(reader)?;
+            }
+            match String::from_utf8(bytes) {
+                Ok(text) => TypeTreeValue::String(text),
+                Err(err) => TypeTreeValue::Bytes(err.into_bytes()),
+            }
+        }
+        "TypelessData" => TypeTreeValue::Bytes(read_bytes(reader, node)?),
+        "map" => {
+            let array = node.child(0)?;
+            let pair = array.child(1)?;
+            let count = read_count(reader, pair)?;
+            let mut entries = Vec::with_capacity(count);
+            for _ in 0..count {
+                let key = read_node(pair.child(0)?, reader)?;
+                let value = read_node(pair.child(1)?, reader)?;
+                entries.push((key, value));
+            }
+            if array.is_aligned() {
+                align(reader)?;
+            }
+            TypeTreeValue::Map(entries)
+        }
+        _ if node.is_array() => read_array(node, reader)?,
+        _ => match node.inner_array() {
+            Some(array) => read_array(array, reader)?,
+            None => {
+                let mut fields = IndexMap::new();
+                for child in &node.children {
+                    let value = read_node(child, reader)
+                        .with_context(|| format!("failed to read field '{}'", child.name))?;
+                    fields.insert(child.name.clone(), value);
+                }
+                TypeTreeValue::Struct(fields)
+            }
+        },
+    };
+    if node.is_aligned() {
+        align(reader)?;
+    }
+    Ok(value)
+}
+
+// Counts come from the data, so check them against what's left before allocating anything.
+fn read_count<R: Read + Seek>(reader: &mut R, element: &TypeTreeNodeInfo) -> Result<usize> {
+    let count = reader.read_i32::<LittleEndian>()?;
+    if count < 0 {
+        bail!("negative length '{}'", count);
+    }
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+    // Variable sized elements still take at least a byte.
+    let min_size = element.byte_size.max(1) as u64;
+    let remaining = end.saturating_sub(position);
+    if count as u64 > remaining / min_size {
+        bail!(
+            "length '{}' is larger than the {} bytes left in the data",
+            count,
+            remaining
+        );
+    }
+    Ok(count as usize)
+}
+
+fn read_bytes<R: Read + Seek>(reader: &mut R, element: &TypeTreeNodeInfo) -> Result<Vec<u8>> {
+    let count = read_count(reader, element)?;
+    let mut buffer = vec![0; count];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_array<R: Read + Seek>(array: &TypeTreeNodeInfo, reader: &mut R) -> Result<TypeTreeValue> {
+    let element = array.child(1)?;
+    let value = if element.type_name == "UInt8" && element.children.is_empty() {
+        TypeTreeValue::Bytes(read_bytes(reader, element)?)
+    } else {
+        let count = read_count(reader, element)?;
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(read_node(element, reader)?);
+        }
+        TypeTreeValue::Array(items)
+    };
+    if array.is_aligned() {
+        align(reader)?;
+    }
+    Ok(value)
+}
+
+fn write_align<W: Write + Seek>(writer: &mut W) -> Result<()> {
+    while writer.stream_position()? % 4 != 0 {
+        writer.write_u8(0)?;
+    }
+    Ok(())
+}
+
+fn expect<T>(node: &TypeTreeNodeInfo, value: Option<T>) -> Result<T> {
+    value.ok_or_else(|| anyhow!("value for '{}' must be a {}", node.name, node.type_name))
+}
+
+fn write_node<W: Write + Seek>(
+    node: &TypeTreeNodeInfo,
+    value: &TypeTreeValue,
+    writer: &mut W,
+) -> Result<()> {
+    match node.type_name.as_str() {
+        "bool" => writer.write_u8(expect(node, value.as_bool())? as u8)?,
+        "SInt8" => writer.write_i8(expect(node, value.as_i64())? as i8)?,
+        "UInt8" | "char" => writer.write_u8(expect(node, value.as_u64())? as u8)?,
+        "SInt16" | "short" => {
+            writer.write_i16::<LittleEndian>(expect(node, value.as_i64())? as i16)?
+        }
+        "UInt16" | "unsigned short" => {
+            writer.write_u16::<LittleEndian>(expect(node, value.as_u64())? as u16)?
+        }
+        "SInt32" | "int" => {
+            writer.write_i32::<LittleEndian>(expect(node, value.as_i64())? as i32)?
+        }
+        "UInt32" | "unsigned int" | "Type*" => {
+            writer.write_u32::<LittleEndian>(expect(node, value.as_u64())? as u32)?
+        }
+        "SInt64" | "long long" => {
+            writer.write_i64::<LittleEndian>(expect(node, value.as_i64())?)?
+        }
+        "UInt64" | "unsigned long long" | "FileSize" => {
+            writer.write_u64::<LittleEndian>(expect(node, value.as_u64())?)?
+        }
+        "float" => writer.write_f32::<LittleEndian>(expect(node, value.as_f64())? as f32)?,
+        "double" => writer.write_f64::<LittleEndian>(expect(node, value.as_f64())?)?,
+        "string" => {
+            write_bytes(writer, expect(node, value.as_bytes())?)?;
+            if node.child(0)?.is_aligned() {
+                write_align(writer)?;
+            }
+        }
+        "TypelessData" => write_bytes(writer, expect(node, value.as_bytes())?)?,
+        "map" => {
+            let TypeTreeValue::Map(entries) = value else {
+                bail!("value for '{}' must be a map", node.name);
+            };
+            let array = node.child(0)?;
+            let pair = array.child(1)?;
+            writer.write_i32::<LittleEndian>(entries.len() as i32)?;
+            for (key, value) in entries {
+                write_node(pair.child(0)?, key, writer)?;
+                write_node(pair.child(1)?, value, writer)?;
+            }
+            if array.is_aligned() {
+                write_align(writer)?;
+            }
+        }
+        _ if node.is_array() => write_array(node, value, writer)?,
+        _ => match node.inner_array() {
+            Some(array) => write_array(array, value, writer)?,
+            None => {
+                let TypeTreeValue::Struct(fields) = value else {
+                    bail!("value for '{}' must be a struct", node.name);
+                };
+                for child in &node.children {
+                    let field = fields
+                        .get(&child.name)
+                        .ok_or_else(|| anyhow!("missing field '{}'", child.name))?;
+                    write_node(child, field, writer)
+                        .with_context(|| format!("failed to write field '{}'", child.name))?;
+                }
+            }
+        },
+    }
+    if node.is_aligned() {
+        write_align(writer)?;
+    }
+    Ok(())
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<()> {
+    writer.write_i32::<LittleEndian>(bytes.len() as i32)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn write_array<W: Write + Seek>(
+    array: &TypeTreeNodeInfo,
+    value: &TypeTreeValue,
+    writer: &mut W,
+) -> Result<()> {
+    let element = array.child(1)?;
+    match value {
+        TypeTreeValue::Bytes(bytes) => write_bytes(writer, bytes)?,
+        TypeTreeValue::Array(items) => {
+            writer.write_i32::<LittleEndian>(items.len() as i32)?;
+            for item in items {
+                write_node(element, item, writer)?;
+            }
+        }
+        _ => bail!("value for '{}' must be an array", array.name),
+    }
+    if array.is_aligned() {
+        write_align(writer)?;
+    }
+    Ok(())
+}
+
+impl Unparsed {
+    pub fn read_value(&self, type_tree: &AssetFileTypeTree) -> Result<TypeTreeValue> {
+        type_tree.read_value(&self.blob)
+    }
+
+    pub fn write_value(
+        &mut self,
+        type_tree: &AssetFileTypeTree,
+        value: &TypeTreeValue,
+    ) -> Result<()> {
+        self.blob = type_tree.write_value(value)?;
+        Ok(())
+    }
+}
+
+impl AssetFile {
+    pub fn get_type(&self, type_hash: i128) -> Option<&AssetFileType> {
+        self.types.iter().find(|ty| ty.type_hash == type_hash)
+    }
+
+    /// Type of an asset in this file. Built in classes fall back to their class ID, since the
+    /// hashes of typed assets are the ones from the game's Unity version.
+    pub fn asset_type(&self, asset: &Asset) -> Option<&AssetFileType> {
+        self.type_index(asset).map(|index| &self.types[index])
+    }
+
+    pub(crate) fn type_index(&self, asset: &Asset) -> Option<usize> {
+        // Built in classes have a single type per file, whichever Unity revision hashed it.
+        if let Some(class_id) = asset.class_id() {
+            return self.types.iter().position(|ty| ty.class_id == class_id);
+        }
+        let type_hash = asset.type_hash();
+        self.types.iter().position(|ty| ty.type_hash == type_hash)
+    }
+
+    /// Decode any asset, typed or not, through its type tree.
+    pub fn read_value(&self, path_id: i64) -> Result<TypeTreeValue> {
+        let asset = self
+            .get_asset_by_path_id(path_id)
+            .ok_or_else(|| anyhow!("file does not contain path ID '{}'", path_id))?;
+        let ty = self
+            .asset_type(asset)
+            .ok_or_else(|| anyhow!("no type for hash '{}'", asset.type_hash()))?;
+        let data = match asset {
+            Asset::Unparsed(unparsed) => unparsed.blob.clone(),
+            _ => {
+                let mut cursor = Cursor::new(vec![]);
+                asset.write_options(&mut cursor, Endian::Little, (self.unity_version(),))?;
+                cursor.into_inner()
+            }
+        };
+        ty.type_tree.read_value(&data)
+    }
+
+    /// Encode a value through the asset's type tree and replace the asset with it.
+    /// Typed assets are parsed again from the new data so they keep their variant.
+    pub fn write_value(&mut self, path_id: i64, value: &TypeTreeValue) -> Result<()> {
+        let asset = self
+            .get_asset_by_path_id(path_id)
+            .ok_or_else(|| anyhow!("file does not contain path ID '{}'", path_id))?;
+        let ty = self
+            .asset_type(asset)
+            .ok_or_else(|| anyhow!("no type for hash '{}'", asset.type_hash()))?;
+        let data = ty.type_tree.write_value(value)?;
+        let asset = Asset::read_options(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            AssetReadOptions {
+                size: data.len(),
+                class_id: ty.class_id,
+                type_hash: ty.type_hash,
+                pptr: path_id as u64,
+                version: self.unity_version(),
+            },
+        )?;
+        if let Some(target) = self.get_asset_by_path_id_mut(path_id) {
+            *target = asset;
+        }
+        Ok(())
+    }
+}
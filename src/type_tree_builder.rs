@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use md4::{Digest, Md4};
+
+use crate::asset::common_string_offset;
+use crate::{
+    AssetFile, AssetFileType, AssetFileTypeTree, AssetFileTypeTreeNode, AssetScript, MonoScript,
+    PPtr, TypeTreeNodeInfo, MONO_BEHAVIOR_CLASS_ID,
+};
+
+const ALIGN_FLAG: i32 = 0x4000;
+
+fn primitive_size(type_name: &str) -> Option<i32> {
+    Some(match type_name {
+        "bool" | "char" | "UInt8" | "SInt8" => 1,
+        "SInt16" | "UInt16" | "short" | "unsigned short" => 2,
+        "int" | "unsigned int" | "SInt32" | "UInt32" | "float" | "Type*" => 4,
+        "long long" | "unsigned long long" | "SInt64" | "UInt64" | "double" | "FileSize" => 8,
+        _ => return None,
+    })
+}
+
+impl TypeTreeNodeInfo {
+    /// A struct node. Its byte size is the sum of its children, or -1 if any of them vary in size.
+    pub fn structure(type_name: &str, name: &str, children: Vec<TypeTreeNodeInfo>) -> Self {
+        let byte_size = children
+            .iter()
+            .map(|child| child.byte_size)
+            .try_fold(0, |total, size| (size >= 0).then_some(total + size))
+            .unwrap_or(-1);
+        Self {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            level: 0,
+            version: 1,
+            type_flags: 0,
+            byte_size,
+            index: 0,
+            meta_flag: 0,
+            children,
+        }
+    }
+
+    /// A primitive such as "int", "float" or "bool".
+    pub fn primitive(type_name: &str, name: &str) -> Self {
+        Self {
+            byte_size: primitive_size(type_name).unwrap_or(-1),
+            ..Self::structure(type_name, name, vec![])
+        }
+    }
+
+    pub fn string(name: &str) -> Self {
+        Self::structure(
+            "string",
+            name,
+            vec![Self::array_node(Self::primitive("char", "data")).aligned()],
+        )
+    }
+
+    /// A `vector` of `element`. The element's name is replaced with "data".
+    pub fn vector(name: &str, element: TypeTreeNodeInfo) -> Self {
+        Self::structure("vector", name, vec![Self::array_node(element).aligned()])
+    }
+
+    /// A `map` of `key` to `value`. Unlike vectors, Unity doesn't flag a map's array as aligned.
+    pub fn map(name: &str, key: TypeTreeNodeInfo, value: TypeTreeNodeInfo) -> Self {
+        let pair = Self::pair("data", key, value);
+        Self::structure("map", name, vec![Self::array_node(pair)])
+    }
+
+    /// A pair. The key and value are renamed to "first" and "second".
+    pub fn pair(name: &str, first: TypeTreeNodeInfo, second: TypeTreeNodeInfo) -> Self {
+        Self::structure(
+            "pair",
+            name,
+            vec![first.renamed("first"), second.renamed("second")],
+        )
+    }
+
+    /// A PPtr to the given class (ex. `pptr("GameObject", "m_GameObject")`).
+    pub fn pptr(class_name: &str, name: &str) -> Self {
+        Self::structure(
+            &format!("PPtr<{}>", class_name),
+            name,
+            vec![
+                Self::primitive("int", "m_FileID"),
+                Self::primitive("SInt64", "m_PathID"),
+            ],
+        )
+    }
+
+    /// Unity's Hash128, stored as 16 bytes.
+    pub fn hash128(name: &str) -> Self {
+        let bytes = (0..16)
+            .map(|i| Self::primitive("UInt8", &format!("bytes[{}]", i)))
+            .collect();
+        Self::structure("Hash128", name, bytes)
+    }
+
+    pub fn typeless_data(name: &str) -> Self {
+        Self {
+            type_flags: 1,
+            byte_size: -1,
+            ..Self::structure(
+                "TypelessData",
+                name,
+                vec![
+                    Self::primitive("int", "size"),
+                    Self::primitive("UInt8", "data"),
+                ],
+            )
+        }
+    }
+
+    fn array_node(element: TypeTreeNodeInfo) -> Self {
+        Self {
+            type_flags: 1,
+            byte_size: -1,
+            ..Self::structure(
+                "Array",
+                "Array",
+                vec![Self::primitive("int", "size"), element.renamed("data")],
+            )
+        }
+    }
+
+    /// Align the stream to 4 bytes after this node.
+    pub fn aligned(mut self) -> Self {
+        self.meta_flag |= ALIGN_FLAG;
+        self
+    }
+
+    pub fn renamed(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_version(mut self, version: u16) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+#[derive(Default)]
+struct StringTable {
+    buffer: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn offset(&mut self, value: &str) -> u32 {
+        if let Some(offset) = common_string_offset(value) {
+            return offset | 0x80000000;
+        }
+        if let Some(offset) = self.offsets.get(value) {
+            return *offset;
+        }
+        let offset = self.buffer.len() as u32;
+        self.buffer.extend(value.as_bytes());
+        self.buffer.push(0);
+        self.offsets.insert(value.to_string(), offset);
+        offset
+    }
+}
+
+impl AssetFileTypeTree {
+    /// Flatten resolved nodes back into a type tree. Levels and indices are recomputed,
+    /// and strings in Unity's common string table are stored as references to it.
+    pub fn from_nodes(roots: &[TypeTreeNodeInfo]) -> Self {
+        let mut nodes = vec![];
+        let mut strings = StringTable::default();
+        for root in roots {
+            flatten(root, 0, &mut nodes, &mut strings);
+        }
+        Self {
+            node_count: nodes.len() as u32,
+            str_buffer_size: strings.buffer.len() as u32,
+            nodes,
+            str_buffer: strings.buffer,
+        }
+    }
+
+    /// Unity's type hash: MD4 over each node's type, name, byte size, flags, version and
+    /// alignment flag, in order.
+    pub fn compute_hash(&self) -> Result<i128> {
+        let mut hasher = Md4::new();
+        for node in &self.nodes {
+            hasher.update(self.get_string(node.type_str_offset)?.as_bytes());
+            hasher.update(self.get_string(node.name_str_offset)?.as_bytes());
+            hasher.update(node.byte_size.to_le_bytes());
+            hasher.update((node.type_flags as i32).to_le_bytes());
+            hasher.update((node.node_version as i32).to_le_bytes());
+            hasher.update((node.meta_flag & ALIGN_FLAG).to_le_bytes());
+        }
+        let digest: [u8; 16] = hasher.finalize().into();
+        Ok(i128::from_le_bytes(digest))
+    }
+}
+
+fn flatten(
+    node: &TypeTreeNodeInfo,
+    level: u8,
+    nodes: &mut Vec<AssetFileTypeTreeNode>,
+    strings: &mut StringTable,
+) {
+    nodes.push(AssetFileTypeTreeNode {
+        node_version: node.version,
+        level,
+        type_flags: node.type_flags,
+        type_str_offset: strings.offset(&node.type_name),
+        name_str_offset: strings.offset(&node.name),
+        byte_size: node.byte_size,
+        index: nodes.len() as i32,
+        meta_flag: node.meta_flag,
+        ref_type_hash: 0,
+    });
+    for child in &node.children {
+        flatten(child, level + 1, nodes, strings);
+    }
+}
+
+impl AssetFileType {
+    /// Build a type from its tree, computing the type hash.
+    pub fn from_type_tree(class_id: u32, type_tree: AssetFileTypeTree) -> Result<Self> {
+        Ok(Self {
+            type_hash: type_tree.compute_hash()?,
+            type_tree,
+            ..Self::new(class_id, 0)
+        })
+    }
+
+    /// Build a MonoBehaviour type for a script. The script ID is the script's properties hash.
+    /// Unity hashes MonoBehaviours by their editor layout, so the hash is computed over the tree
+    /// with the editor-only header fields added back in.
+    pub fn from_script(script: &MonoScript, type_tree: AssetFileTypeTree) -> Result<Self> {
+        let editor_tree = match type_tree.to_nodes()?.first() {
+            Some(root) => AssetFileTypeTree::from_nodes(&[with_editor_header(root)]),
+            None => type_tree.clone(),
+        };
+        Ok(Self {
+            type_hash: editor_tree.compute_hash()?,
+            script_id: script.properties_hash,
+            type_tree,
+            ..Self::new(MONO_BEHAVIOR_CLASS_ID, 0)
+        })
+    }
+}
+
+// Expects the runtime header: m_GameObject, m_Enabled, m_Script, m_Name.
+fn with_editor_header(root: &TypeTreeNodeInfo) -> TypeTreeNodeInfo {
+    let mut children = root.children.clone();
+    if children.len() < 4 {
+        return root.clone();
+    }
+    let fields = children.split_off(4);
+    let [game_object, enabled, script, name]: [TypeTreeNodeInfo; 4] = children.try_into().unwrap();
+    let mut children = vec![
+        TypeTreeNodeInfo::primitive("unsigned int", "m_ObjectHideFlags"),
+        TypeTreeNodeInfo::pptr("EditorExtension", "m_CorrespondingSourceObject"),
+        TypeTreeNodeInfo::pptr("PrefabInstance", "m_PrefabInstance"),
+        TypeTreeNodeInfo::pptr("Prefab", "m_PrefabAsset"),
+        game_object,
+        enabled,
+        TypeTreeNodeInfo::primitive("unsigned int", "m_EditorHideFlags"),
+        script,
+        name,
+        TypeTreeNodeInfo::string("m_EditorClassIdentifier"),
+    ];
+    children.extend(fields);
+    TypeTreeNodeInfo::structure(&root.type_name, &root.name, children).with_version(root.version)
+}
+
+impl AssetFile {
+    /// Add a type unless one with the same hash exists. Returns its index in the type list.
+    pub fn add_type(&mut self, ty: AssetFileType) -> usize {
+        match self
+            .types
+            .iter()
+            .position(|existing| existing.type_hash == ty.type_hash)
+        {
+            Some(index) => index,
+            None => {
+                self.types.push(ty);
+                self.types.len() - 1
+            }
+        }
+    }
+
+    /// Add a MonoBehaviour type along with the script table entry that points at its MonoScript.
+    pub fn add_script_type(&mut self, mut ty: AssetFileType, script: &PPtr) -> usize {
+        if let Some(index) = self
+            .types
+            .iter()
+            .position(|existing| existing.type_hash == ty.type_hash)
+        {
+            return index;
+        }
+        let entry = AssetScript {
+            file_id: script.file_id as u32,
+            object_id: script.path_id as u64,
+        };
+        let script_index = self
+            .scripts
+            .iter()
+            .position(|s| s.file_id == entry.file_id && s.object_id == entry.object_id)
+            .unwrap_or_else(|| {
+                self.scripts.push(entry);
+                self.scripts.len() - 1
+            });
+        ty.script_type_index = script_index as i16;
+        self.add_type(ty)
+    }
+}
@@ -0,0 +1,461 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde::de::value::{
+    Error, MapDeserializer, SeqDeserializer, StringDeserializer, U32Deserializer,
+};
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
+use serde::ser::{self, Impossible, Serialize};
+use serde::{forward_to_deserialize_any, Deserializer};
+
+use crate::{AssetFile, AssetFileTypeTree, TypeTreeValue, Unparsed};
+
+impl TypeTreeValue {
+    /// Deserialize into a type whose fields use the Unity field names (ex. `m_Name`).
+    /// Fields that aren't in the type don't need to be declared.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(T::deserialize(self.clone())?)
+    }
+
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        Ok(value.serialize(ValueSerializer)?)
+    }
+
+    /// Overwrite the parts of this value that are present in `patch`.
+    /// Structs are merged field by field, arrays element by element and maps entry by entry
+    /// using their keys. Arrays and maps end up with the patch's elements.
+    pub fn merge(&mut self, patch: TypeTreeValue) -> Result<()> {
+        match (self, patch) {
+            (TypeTreeValue::Struct(fields), TypeTreeValue::Struct(patch_fields)) => {
+                for (name, value) in patch_fields {
+                    fields
+                        .get_mut(&name)
+                        .ok_or_else(|| anyhow!("field '{}' is not in the type tree", name))?
+                        .merge(value)?;
+                }
+            }
+            (TypeTreeValue::Array(items), TypeTreeValue::Array(patch_items)) => {
+                items.truncate(patch_items.len());
+                for (i, value) in patch_items.into_iter().enumerate() {
+                    match items.get_mut(i) {
+                        Some(item) => item.merge(value)?,
+                        None => items.push(value),
+                    }
+                }
+            }
+            (TypeTreeValue::Map(entries), TypeTreeValue::Map(patch_entries)) => {
+                // Existing entries keep their position so sorted maps stay sorted.
+                let mut patch_entries = patch_entries.into_iter().map(Some).collect::<Vec<_>>();
+                let mut merged = Vec::with_capacity(patch_entries.len());
+                for (key, mut value) in std::mem::take(entries) {
+                    let patch = patch_entries
+                        .iter_mut()
+                        .find(|entry| matches!(entry, Some((k, _)) if same_key(k, &key)))
+                        .and_then(Option::take);
+                    if let Some((_, patch_value)) = patch {
+                        value.merge(patch_value)?;
+                        merged.push((key, value));
+                    }
+                }
+                merged.extend(patch_entries.into_iter().flatten());
+                *entries = merged;
+            }
+            (target, patch) => *target = patch,
+        }
+        Ok(())
+    }
+}
+
+// Patches built from Rust types may use unsigned keys where the type tree has signed ones.
+fn same_key(a: &TypeTreeValue, b: &TypeTreeValue) -> bool {
+    match (a, b) {
+        (TypeTreeValue::Int(a), TypeTreeValue::UInt(b))
+        | (TypeTreeValue::UInt(b), TypeTreeValue::Int(a)) => u64::try_from(*a) == Ok(*b),
+        (a, b) => a == b,
+    }
+}
+
+impl AssetFileTypeTree {
+    pub fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T> {
+        self.read_value(data)?.deserialize()
+    }
+}
+
+impl Unparsed {
+    pub fn deserialize_with<T: DeserializeOwned>(
+        &self,
+        type_tree: &AssetFileTypeTree,
+    ) -> Result<T> {
+        type_tree.deserialize(&self.blob)
+    }
+
+    /// Write the fields of `value` into the blob. Fields that `value` doesn't declare keep their current data.
+    pub fn serialize_with<T: Serialize>(
+        &mut self,
+        type_tree: &AssetFileTypeTree,
+        value: &T,
+    ) -> Result<()> {
+        let mut current = self.read_value(type_tree)?;
+        current.merge(TypeTreeValue::from_serialize(value)?)?;
+        self.write_value(type_tree, &current)
+    }
+}
+
+impl AssetFile {
+    pub fn deserialize_asset<T: DeserializeOwned>(&self, path_id: i64) -> Result<T> {
+        self.read_value(path_id)?.deserialize()
+    }
+
+    /// Same as [Unparsed::serialize_with], but works for any asset in the file.
+    pub fn serialize_asset<T: Serialize>(&mut self, path_id: i64, value: &T) -> Result<()> {
+        let mut current = self.read_value(path_id)?;
+        current.merge(TypeTreeValue::from_serialize(value)?)?;
+        self.write_value(path_id, &current)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for TypeTreeValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for TypeTreeValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            TypeTreeValue::Bool(value) => visitor.visit_bool(value),
+            TypeTreeValue::Int(value) => visitor.visit_i64(value),
+            TypeTreeValue::UInt(value) => visitor.visit_u64(value),
+            TypeTreeValue::Float(value) => visitor.visit_f64(value),
+            TypeTreeValue::String(value) => visitor.visit_string(value),
+            TypeTreeValue::Bytes(value) => visitor.visit_byte_buf(value),
+            TypeTreeValue::Array(items) => {
+                visitor.visit_seq(SeqDeserializer::new(items.into_iter()))
+            }
+            TypeTreeValue::Map(entries) => {
+                visitor.visit_map(MapDeserializer::new(entries.into_iter()))
+            }
+            TypeTreeValue::Struct(fields) => visitor.visit_map(MapDeserializer::new(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (StringDeserializer::<Error>::new(name), value)),
+            )),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            TypeTreeValue::Bytes(bytes) => {
+                visitor.visit_seq(SeqDeserializer::new(bytes.into_iter()))
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Unity stores enums as integers, so unit variants are matched by index as well as by name.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            TypeTreeValue::Int(value) => {
+                visitor.visit_enum(U32Deserializer::<Error>::new(value as u32))
+            }
+            TypeTreeValue::UInt(value) => {
+                visitor.visit_enum(U32Deserializer::<Error>::new(value as u32))
+            }
+            TypeTreeValue::String(value) => {
+                visitor.visit_enum(StringDeserializer::<Error>::new(value))
+            }
+            value => Err(serde::de::Error::custom(format!(
+                "expected an enum, found {:?}",
+                value
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ValueSerializer;
+
+fn unsupported(kind: &str) -> Error {
+    ser::Error::custom(format!("{} can't be represented in a type tree", kind))
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = Impossible<TypeTreeValue, Error>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = Impossible<TypeTreeValue, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Int(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Int(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Int(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::UInt(v as u64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::UInt(v as u64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::UInt(v as u64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::UInt(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(unsupported("None"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Struct(IndexMap::new()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::UInt(variant_index as u64))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(unsupported("enum variants with data"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeArray(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(unsupported("enum variants with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeMap {
+            entries: vec![],
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SerializeStruct(IndexMap::new()))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(unsupported("enum variants with data"))
+    }
+}
+
+struct SerializeArray(Vec<TypeTreeValue>);
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    entries: Vec<(TypeTreeValue, TypeTreeValue)>,
+    key: Option<TypeTreeValue>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value without a key"))?;
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Map(self.entries))
+    }
+}
+
+struct SerializeStruct(IndexMap<String, TypeTreeValue>);
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = TypeTreeValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.0
+            .insert(key.to_string(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(TypeTreeValue::Struct(self.0))
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// A Unity version (ex. 2020.3.18f1). The release type and build number are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct UnityVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl UnityVersion {
+    /// The version the game was built with. Typed layouts default to it.
+    pub const DEFAULT: Self = Self::new(2020, 3, 18);
+
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a version from a file header. Stripped files report "0.0.0" and fall back to the default.
+    pub fn from_header(version: &str) -> Self {
+        version
+            .parse()
+            .ok()
+            .filter(|version| *version != Self::new(0, 0, 0))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for UnityVersion {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl FromStr for UnityVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split('.').map(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u16>().ok()
+        });
+        let mut next = || {
+            parts
+                .next()
+                .flatten()
+                .ok_or_else(|| anyhow!("malformed Unity version '{}'", s))
+        };
+        Ok(Self::new(next()?, next()?, next()?))
+    }
+}
+
+impl Display for UnityVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
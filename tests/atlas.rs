@@ -0,0 +1,276 @@
+#![cfg(feature = "atlas")]
+
+mod common;
+
+use astra_formats::{
+    Asset, AssetFile, AssetFileType, AtlasBundle, Bundle, BundleFile, Sprite, SpriteAtlas,
+    SPRITE_ATLAS_HASH, SPRITE_HASH, TEXTURE_2D_HASH,
+};
+use common::*;
+use image::{DynamicImage, RgbaImage};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const CAB: &str = "CAB-00000000000000000000000000000001";
+
+/// 4x4 RGBA32 pixels where every byte is `seed` plus its offset.
+fn pixels(seed: u8) -> Vec<u8> {
+    (0..64).map(|i| seed.wrapping_add(i)).collect()
+}
+
+fn rect(x: f32, y: f32, w: f32, h: f32) -> Value {
+    json!({"x": x, "y": y, "w": w, "h": h})
+}
+
+fn render_data_key(guid: i128) -> Value {
+    json!({"guid": guid, "second": 21300000})
+}
+
+/// A sprite whose rect comes from the atlas render data with the same GUID.
+fn sprite(name: &str, guid: i128) -> Sprite {
+    let vector2 = json!({"x": 0.0, "y": 0.0});
+    let vector4 = json!({"x": 0.0, "y": 0.0, "z": 0.0, "w": 0.0});
+    serde_json::from_value(json!({
+        "name": name,
+        "rect": rect(0., 0., 2., 2.),
+        "offset": vector2,
+        "border": vector4,
+        "pixels_to_units": 100.0,
+        "pivot": {"x": 0.5, "y": 0.5},
+        "extrude": 1,
+        "is_polygon": 0,
+        "render_data_key": render_data_key(guid),
+        "atlas_tags": ["atlas"],
+        "sprite_atlas": pptr(1),
+        "sprite_render_data": {
+            "texture": pptr(0),
+            "alpha_texture": pptr(0),
+            "secondary_textures": [],
+            "sub_meshes": [],
+            "index_buffer": [],
+            "vertex_data": {"vertex_count": 0, "channels": [], "data": []},
+            "bind_pose": [],
+            "texture_rect": rect(0., 0., 0., 0.),
+            "texture_rect_offset": vector2,
+            "atlas_rect_offset": vector2,
+            "settings_raw": 0,
+            "uv_transform": vector4,
+            "downscale_multiplier": 1.0,
+        },
+        "physics_shape": [],
+        "bones": [],
+    }))
+    .unwrap()
+}
+
+fn atlas(sprites: &[(i128, i64)]) -> SpriteAtlas {
+    let vector2 = json!({"x": 0.0, "y": 0.0});
+    let render_data: Vec<Value> = sprites
+        .iter()
+        .map(|(guid, texture)| {
+            let data = json!({
+                "texture": pptr(*texture),
+                "alpha_texture": pptr(0),
+                "texture_rect": rect(2., 2., 2., 2.),
+                "texture_rect_offset": vector2,
+                "atlas_rect_offset": vector2,
+                "uv_transform": {"x": 100.0, "y": 3.0, "z": 100.0, "w": 3.0},
+                "downscale_multiplier": 1.0,
+                "settings_raw": 3,
+                "secondary_textures": [],
+            });
+            json!([render_data_key(*guid), data])
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "name": "atlas",
+        "packed_sprites": [],
+        "sprite_names_to_index": [],
+        "render_data_map": render_data,
+        "tag": "atlas",
+        "is_variant": 0,
+    }))
+    .unwrap()
+}
+
+/// Sprites "a" and "b" in the top right of textures 2 and 3. Texture 2 is streamed from the
+/// resource file. With `broken`, texture 4 is added, which no sprite uses and whose data is
+/// truncated.
+fn atlas_bundle(broken: bool) -> AtlasBundle {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    // Anything but the Switch, so textures aren't swizzled.
+    asset_file.set_platform(5);
+    asset_file.types = vec![
+        AssetFileType::new(687078895, SPRITE_ATLAS_HASH),
+        AssetFileType::new(28, TEXTURE_2D_HASH),
+        AssetFileType::new(213, SPRITE_HASH),
+    ];
+    let resource = format!("archive:/{CAB}/{CAB}.resS");
+    let streamed = json!({"offset": 16, "size": 64, "path": resource});
+    let assets = [
+        (1, Asset::SpriteAtlas(atlas(&[(1, 2), (2, 3)]))),
+        (
+            2,
+            Asset::Texture2D(texture_2d("RGBA32", 4, 4, 1, &[], streamed), 0),
+        ),
+        (
+            3,
+            Asset::Texture2D(
+                texture_2d("RGBA32", 4, 4, 1, &pixels(100), streaming_info()),
+                0,
+            ),
+        ),
+        (
+            4,
+            Asset::Texture2D(
+                texture_2d("RGBA32", 4, 4, 1, &pixels(200)[..8], streaming_info()),
+                0,
+            ),
+        ),
+        (5, Asset::Sprite(sprite("a", 1))),
+        (6, Asset::Sprite(sprite("b", 2))),
+    ];
+    for (path_id, asset) in assets {
+        if path_id != 4 || broken {
+            asset_file.insert_asset(path_id, asset).unwrap();
+        }
+    }
+    let mut resource = vec![0; 16];
+    resource.extend(pixels(0));
+
+    let mut bundle = Bundle::new();
+    bundle.insert(String::from(CAB), BundleFile::Assets(asset_file));
+    bundle.insert(format!("{CAB}.resS"), BundleFile::Raw(resource));
+    AtlasBundle::from_slice(&bundle.serialize().unwrap()).unwrap()
+}
+
+/// The top right 2x2 pixels of a texture, flipped to be the right way up.
+fn expected_sprite(seed: u8) -> Vec<u8> {
+    let texture = RgbaImage::from_raw(4, 4, pixels(seed)).unwrap();
+    DynamicImage::ImageRgba8(texture)
+        .crop_imm(2, 2, 2, 2)
+        .flipv()
+        .to_rgba8()
+        .into_raw()
+}
+
+/// An empty directory under the system temp directory, unique to this process and test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("astra-atlas-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn cache_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn textures_are_decoded_on_demand() {
+    let atlas = atlas_bundle(true).extract_data().unwrap();
+    let mut ids: Vec<i64> = atlas.texture_ids().collect();
+    ids.sort();
+    assert_eq!(ids, [2, 3, 4]);
+
+    let a = atlas.get_sprite("a").unwrap().unwrap();
+    assert_eq!(a.to_rgba8().into_raw(), expected_sprite(0));
+    let b = atlas.get_sprite("b").unwrap().unwrap();
+    assert_eq!(b.to_rgba8().into_raw(), expected_sprite(100));
+    assert!(atlas.get_sprite("c").unwrap().is_none());
+
+    // Nothing asked for texture 4 until now.
+    assert!(atlas.texture(4).is_err());
+    assert!(atlas.decode_all().is_err());
+    assert_eq!(atlas.unwrap_sprites().unwrap().len(), 2);
+}
+
+#[test]
+fn decode_all_matches_lazy_decode() {
+    let dir = temp_dir("decode-all");
+    let bundle = atlas_bundle(false);
+    let mut eager = bundle.extract_data().unwrap();
+    eager.set_cache_dir(&dir);
+    // Decodes in parallel with the `parallel` feature.
+    eager.decode_all().unwrap();
+    assert_eq!(cache_entries(&dir).len(), 2);
+
+    let lazy = bundle.extract_data().unwrap();
+    for id in [2, 3] {
+        assert_eq!(eager.texture(id).unwrap(), lazy.texture(id).unwrap());
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cache_is_written_and_read_back() {
+    let dir = temp_dir("read-back");
+    let bundle = atlas_bundle(false);
+    let mut atlas = bundle.extract_data().unwrap();
+    atlas.set_cache_dir(dir.join("cache"));
+    let decoded = atlas.texture(2).unwrap().clone();
+
+    // One entry for the one decoded texture, named by a hex hash of the encoded texture.
+    let entries = cache_entries(&dir.join("cache"));
+    assert_eq!(entries.len(), 1);
+    let stem = entries[0].file_stem().unwrap().to_str().unwrap();
+    assert_eq!(entries[0].extension().unwrap(), "rgba");
+    assert_eq!(stem.len(), 32);
+    assert!(stem.chars().all(|c| c.is_ascii_hexdigit()));
+
+    // The width and height as little endian u32s, then RGBA8 pixels.
+    let data = std::fs::read(&entries[0]).unwrap();
+    assert_eq!(data[..8], [4, 0, 0, 0, 4, 0, 0, 0]);
+    assert_eq!(data[8..], pixels(0));
+
+    // A second atlas reads the same image back.
+    let mut cached = bundle.extract_data().unwrap();
+    cached.set_cache_dir(dir.join("cache"));
+    assert_eq!(cached.texture(2).unwrap(), &decoded);
+
+    // And really reads it from the cache rather than decoding again.
+    let mut marked = data.clone();
+    marked[8..].fill(7);
+    std::fs::write(&entries[0], &marked).unwrap();
+    let mut cached = bundle.extract_data().unwrap();
+    cached.set_cache_dir(dir.join("cache"));
+    assert_eq!(cached.texture(2).unwrap().to_rgba8().into_raw(), [7; 64]);
+
+    // Different image data gets its own entry.
+    cached.texture(3).unwrap();
+    assert_eq!(cache_entries(&dir.join("cache")).len(), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn corrupted_cache_entries_are_decoded_again() {
+    let dir = temp_dir("corrupted");
+    let bundle = atlas_bundle(false);
+    let mut atlas = bundle.extract_data().unwrap();
+    atlas.set_cache_dir(&dir);
+    atlas.texture(2).unwrap();
+    let path = cache_entries(&dir).remove(0);
+    let valid = std::fs::read(&path).unwrap();
+
+    let mut wrong_size = valid.clone();
+    wrong_size[..8].copy_from_slice(&[2, 0, 0, 0, 2, 0, 0, 0]);
+    let mut other_texture = vec![2, 0, 0, 0, 2, 0, 0, 0];
+    other_texture.extend([9; 16]);
+    for corrupted in [valid[..20].to_vec(), wrong_size, other_texture, vec![]] {
+        std::fs::write(&path, corrupted).unwrap();
+        let mut atlas = bundle.extract_data().unwrap();
+        atlas.set_cache_dir(&dir);
+        assert_eq!(atlas.texture(2).unwrap().to_rgba8().into_raw(), pixels(0));
+        // The entry is replaced with the decoded image.
+        assert_eq!(std::fs::read(&path).unwrap(), valid);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
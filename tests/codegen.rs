@@ -0,0 +1,85 @@
+mod common;
+
+use astra_formats::{Asset, AssetFileType, GAME_OBJECT_HASH, TRANSFORM_HASH};
+use common::*;
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+const TRANSFORM_SOURCE: &str = "\
+#[binrw]
+#[derive(Debug, Clone)]
+pub struct Transform {
+    pub game_object: PPtr,
+    pub local_rotation: Quaternionf,
+    pub local_position: Vector3f,
+    pub local_scale: Vector3f,
+    #[brw(align_after = 4)]
+    pub children: UArray<PPtr>,
+    pub father: PPtr,
+}
+";
+
+fn generate(hash: i128, name: &str) -> String {
+    AssetFileType::known(hash)
+        .unwrap()
+        .to_rust_source(name)
+        .unwrap()
+}
+
+/// Field names of the first struct in `source`, in order.
+fn generated_fields(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .skip_while(|line| !line.starts_with("pub struct"))
+        .skip(1)
+        .take_while(|line| *line != "}")
+        .filter_map(|line| line.trim().strip_prefix("pub "))
+        .map(|field| field.split(':').next().unwrap().to_string())
+        .collect()
+}
+
+/// Field names of a hand-written asset struct, in declaration order.
+fn declared_fields(asset: &impl Serialize) -> Vec<String> {
+    let json = serde_json::to_string(asset).unwrap();
+    let fields: IndexMap<String, Value> = serde_json::from_str(&json).unwrap();
+    fields.into_keys().collect()
+}
+
+#[test]
+fn transform_matches_golden_source() {
+    assert_eq!(generate(TRANSFORM_HASH, "Transform"), TRANSFORM_SOURCE);
+}
+
+#[test]
+fn generated_fields_match_hand_written_structs() {
+    let Asset::Transform(transform) = transform(1, 0, &[], vector(0., 0., 0.)) else {
+        unreachable!();
+    };
+    assert_eq!(
+        generated_fields(&generate(TRANSFORM_HASH, "Transform")),
+        declared_fields(&transform)
+    );
+
+    let Asset::GameObject(game_object) = game_object("root", &[2]) else {
+        unreachable!();
+    };
+    assert_eq!(
+        generated_fields(&generate(GAME_OBJECT_HASH, "GameObject")),
+        declared_fields(&game_object)
+    );
+}
+
+#[test]
+fn strings_align_themselves() {
+    let source = generate(GAME_OBJECT_HASH, "GameObject");
+    let lines: Vec<&str> = source.lines().collect();
+    let name = lines
+        .iter()
+        .position(|line| line.trim() == "pub name: UString,")
+        .unwrap();
+    assert!(!lines[name - 1].contains("align_after"));
+    assert!(lines.contains(&"    #[brw(align_after = 4)]"));
+    assert!(lines.contains(&"    pub component: UArray<ComponentPair>,"));
+    assert!(source.contains("pub struct ComponentPair {\n    pub component: PPtr,\n}"));
+}
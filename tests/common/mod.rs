@@ -0,0 +1,180 @@
+// Builders shared by the integration tests. Each test binary uses a different subset of them.
+#![allow(dead_code)]
+
+use astra_formats::{
+    Asset, AssetBundle, AssetFile, AssetFileType, GameObject, Material, PPtr, Quaternionf,
+    Texture2D, Transform, UArray, UString, Unparsed, Vector3f, TRANSFORM_HASH,
+};
+use binrw::BinWrite;
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+pub const RECT_TRANSFORM_HASH: i128 = 4242;
+
+pub fn pptr(path_id: i64) -> PPtr {
+    PPtr {
+        file_id: 0,
+        path_id,
+    }
+}
+
+pub fn vector(x: f32, y: f32, z: f32) -> Vector3f {
+    Vector3f { x, y, z }
+}
+
+pub fn game_object(name: &str, components: &[i64]) -> Asset {
+    Asset::GameObject(GameObject {
+        component: UArray {
+            items: components.iter().copied().map(pptr).collect(),
+        },
+        layer: 0,
+        name: UString(String::from(name)),
+        tag: 0,
+        is_active: 1,
+    })
+}
+
+pub fn transform(game_object: i64, father: i64, children: &[i64], position: Vector3f) -> Asset {
+    Asset::Transform(Transform {
+        game_object: pptr(game_object),
+        local_rotation: Quaternionf {
+            x: 0.,
+            y: 0.,
+            z: 0.,
+            w: 1.,
+        },
+        local_position: position,
+        local_scale: vector(1., 1., 1.),
+        children: UArray {
+            items: children.iter().copied().map(pptr).collect(),
+        },
+        father: pptr(father),
+    })
+}
+
+/// A RectTransform stand-in: unparsed, but described by the Transform type tree.
+pub fn rect_transform(game_object: i64, father: i64, children: &[i64]) -> Asset {
+    let Asset::Transform(transform) = transform(game_object, father, children, vector(0., 0., 0.))
+    else {
+        unreachable!();
+    };
+    let mut cursor = Cursor::new(vec![]);
+    transform.write_le(&mut cursor).unwrap();
+    Asset::Unparsed(Unparsed {
+        type_hash: RECT_TRANSFORM_HASH,
+        path_id: 0,
+        blob: cursor.into_inner(),
+    })
+}
+
+pub fn rect_transform_type() -> AssetFileType {
+    let mut ty = AssetFileType::known(TRANSFORM_HASH).unwrap();
+    ty.class_id = 224;
+    ty.type_hash = RECT_TRANSFORM_HASH;
+    ty
+}
+
+pub fn material(shader: PPtr, texture: i64) -> Asset {
+    let tex_env = json!({
+        "texture": pptr(texture),
+        "scale": {"x": 1.0, "y": 1.0},
+        "offset": {"x": 0.0, "y": 0.0},
+    });
+    let material: Material = serde_json::from_value(json!({
+        "name": "mat",
+        "shader": shader,
+        "shader_keywords": "",
+        "lightmap_flags": 4,
+        "enable_instancing_variants": 0,
+        "double_sided_gi": 0,
+        "custom_render_queue": 0xFFFFFFFFu32,
+        "string_tag_map": [],
+        "disabled_shader_passes": [],
+        "saved_properties": {"text_envs": [["_MainTex", tex_env]], "floats": [], "colors": []},
+        "build_texture_stacks": [],
+    }))
+    .unwrap();
+    Asset::Material(material)
+}
+
+/// An AssetBundle with empty preload and container tables.
+pub fn asset_bundle() -> Asset {
+    let main_asset = json!({"preload_index": 0, "preload_size": 0, "asset": null});
+    let bundle: AssetBundle = serde_json::from_value(json!({
+        "name": "bundle",
+        "preloads": [],
+        "container_map": [],
+        "main_asset": main_asset,
+        "runtime_compatibility": 1,
+        "asset_bundle_name": "bundle",
+        "dependencies": [],
+        "is_streamed_asset_bundle": 0,
+        "explicit_data_layout": 0,
+        "path_flags": 7,
+        "scene_hashes": [],
+    }))
+    .unwrap();
+    Asset::Bundle(bundle)
+}
+
+pub fn streaming_info() -> Value {
+    json!({"offset": 0, "size": 0, "path": ""})
+}
+
+pub fn texture_settings() -> Value {
+    json!({"filter_mode": 1, "aniso": 1, "mip_bias": 0.0, "wrap_u": 0, "wrap_v": 0, "wrap_w": 0})
+}
+
+/// Texture2D fields as JSON, with `stream_data` counted towards the complete image size.
+pub fn texture_2d_json(
+    format: &str,
+    width: u32,
+    height: u32,
+    mip_count: u32,
+    image_data: &[u8],
+    stream_data: Value,
+) -> Value {
+    json!({
+        "name": "tex",
+        "forced_fallback_format": 4,
+        "downscale_fallback": 0,
+        "is_alpha_channel_optional": 0,
+        "width": width,
+        "height": height,
+        "complete_image_size": image_data.len() as u64 + stream_data["size"].as_u64().unwrap(),
+        "mips_stripped": 0,
+        "texture_format": format,
+        "mip_count": mip_count,
+        "is_readable": 0,
+        "is_pre_processed": 0,
+        "ignore_master_texture_limit": 0,
+        "streaming_mipmaps": 0,
+        "streaming_mipmaps_priority": 0,
+        "image_count": 1,
+        "texture_dimension": 2,
+        "texture_settings": texture_settings(),
+        "lightmap_format": 0,
+        "color_space": 1,
+        "platform_blob": [],
+        "image_data": image_data,
+        "stream_data": stream_data,
+    })
+}
+
+pub fn texture_2d(
+    format: &str,
+    width: u32,
+    height: u32,
+    mip_count: u32,
+    image_data: &[u8],
+    stream_data: Value,
+) -> Texture2D {
+    let value = texture_2d_json(format, width, height, mip_count, image_data, stream_data);
+    serde_json::from_value(value).unwrap()
+}
+
+pub fn serialize(asset_file: &AssetFile) -> Vec<u8> {
+    let mut cursor = Cursor::new(vec![]);
+    asset_file.write_le(&mut cursor).unwrap();
+    cursor.into_inner()
+}
@@ -0,0 +1,287 @@
+use astra_formats::*;
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::forward_to_deserialize_any;
+
+// Records the field list serde's derive passes to deserialize_struct, which is the declaration
+// order of the struct, then bails out.
+struct FieldNames(Option<&'static [&'static str]>);
+
+#[derive(Debug)]
+struct Stop;
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stop")
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl de::Error for Stop {
+    fn custom<T: std::fmt::Display>(_msg: T) -> Self {
+        Stop
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut FieldNames {
+    type Error = Stop;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Stop> {
+        Err(Stop)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        self.0 = Some(fields);
+        Err(Stop)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+fn declared_fields<T: DeserializeOwned>() -> Vec<&'static str> {
+    let mut names = FieldNames(None);
+    let _ = T::deserialize(&mut names);
+    names.0.unwrap_or_default().to_vec()
+}
+
+fn layout_fields<T: TypeTreeLayout>() -> Vec<&'static str> {
+    match T::layout() {
+        Layout::Struct(fields) => fields.iter().map(|field| field.name).collect(),
+        layout => panic!("expected a struct layout, found {:?}", layout),
+    }
+}
+
+macro_rules! check_layouts {
+    ($($ty:ty),* $(,)?) => {{
+        let mut mismatches = vec![];
+        $(
+            let declared = declared_fields::<$ty>();
+            let listed = layout_fields::<$ty>();
+            if declared != listed {
+                mismatches.push(format!(
+                    "{}:\n  struct: {:?}\n  layout: {:?}",
+                    stringify!($ty),
+                    declared,
+                    listed
+                ));
+            }
+        )*
+        mismatches
+    }};
+}
+
+#[test]
+fn layouts_list_every_field_in_order() {
+    let mismatches = check_layouts!(
+        AssetBundle,
+        AssetInfo,
+        GameObject,
+        Transform,
+        Animator,
+        TextAsset,
+        MonoScript,
+        TerrainData,
+        TerrainLayerData,
+        TerrainOverlapData,
+        Texture2D,
+        TextureArrayData,
+        Texture2DArray,
+        Texture3D,
+        GlTextureSettings,
+        StreamingInfo,
+        SpriteAtlas,
+        RenderDataKey,
+        SpriteAtlasData,
+        Sprite,
+        RectF,
+        Vector2f,
+        Vector3f,
+        Vector4f,
+        SpriteRenderData,
+        SecondarySpriteTexture,
+        SubMesh,
+        AABB,
+        VertexData,
+        ChannelInfo,
+        Matrix4x4f,
+        SpriteBone,
+        Quaternionf,
+        Mesh,
+        BlendShapeData,
+        BlendShapeVertex,
+        MeshBlendShape,
+        MeshBlendShapeChannel,
+        MinMaxAABB,
+        CompressedMesh,
+        PackedBitVector,
+        PackedBitVector2,
+        Avatar,
+        TosPair,
+        AvatarConstant,
+        Skeleton,
+        SkeletonNode,
+        SkeletonAxes,
+        SkeletonLimit,
+        SkeletonPose,
+        SkeletonTransform,
+        AvatarHuman,
+        HumanDescription,
+        HumanBone,
+        SkeletonBoneLimit,
+        SkeletonBone,
+        Material,
+        UnityPropertySheet,
+        TexEnv,
+        FloatPropertySheetPair,
+        ColorRGBA,
+        MeshFilter,
+        MeshRenderer,
+        StaticBatchInfo,
+        SkinnedMeshRenderer,
+        SpringJob,
+        SpringBoneProperties,
+        AngleLimits,
+        SpringColliderProperty,
+        LengthLimitProperty,
+        SpringBone,
+        AnimationClip,
+        ClipMuscleConstant,
+        QuaternionCurve,
+        QuaternionAnimationCurve,
+        Vector3Curve,
+        FloatCurve,
+        PPtrCurve,
+        QuaternionCurveKeyframe,
+        CompressedAnimationCurve,
+        PackedIntVector,
+        PackedFloatVector,
+        XForm,
+        HumanPose,
+        HumanGoal,
+        HandPose,
+        StreamedClip,
+        DenseClip,
+        ConstantClip,
+        Clip,
+        ValueDelta,
+        GenericBinding,
+        AnimationClipBindingConstant,
+        AnimationEvent,
+        AnimatorOverrideController,
+        AnimationClipOverride,
+        AnimatorController,
+        ControllerConstant,
+        LayerConstant,
+        HumanPoseMask,
+        SkeletonMask,
+        SkeletonMaskElement,
+        StateMachineConstant,
+        StateConstant,
+        TransitionConstant,
+        ConditionConstant,
+        SelectorStateConstant,
+        SelectorTransitionConstant,
+        BlendTreeConstant,
+        BlendTreeNodeConstant,
+        Blend1dDataConstant,
+        Blend2dDataConstant,
+        MotionNeighborList,
+        BlendDirectDataConstant,
+        ValueArrayConstant,
+        ValueConstant,
+        ValueArray,
+        StateMachineBehaviourVectorDescription,
+        StateKey,
+        StateRange,
+    );
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn cubemap_layout_follows_texture_2d() {
+    let mut expected = layout_fields::<Texture2D>();
+    expected.push("source_textures");
+    assert_eq!(layout_fields::<Cubemap>(), expected);
+    assert_eq!(declared_fields::<Cubemap>(), ["texture", "source_textures"]);
+}
+
+/// A known type as an older Unity version writes it: without `removed` and, before 2020.1,
+/// with a 32 bit stream offset.
+fn older_type(type_hash: i128, removed: &[&str], narrow_offset: bool) -> AssetFileType {
+    fn strip(node: &mut TypeTreeNodeInfo, removed: &[&str], narrow_offset: bool) {
+        node.children
+            .retain(|child| !removed.contains(&child.name.as_str()));
+        for child in &mut node.children {
+            if narrow_offset && child.name == "offset" {
+                *child = TypeTreeNodeInfo::primitive("UInt32", "offset");
+            }
+            strip(child, removed, narrow_offset);
+        }
+    }
+    let ty = AssetFileType::known(type_hash).unwrap();
+    let mut root = ty.type_tree.to_nodes().unwrap().remove(0);
+    strip(&mut root, removed, narrow_offset);
+    AssetFileType::from_type_tree(ty.class_id, AssetFileTypeTree::from_nodes(&[root])).unwrap()
+}
+
+#[test]
+fn version_gated_fields_follow_the_file_version() {
+    let cases: [(&str, &[&str], &[&str], bool); 2] = [
+        (
+            "2020.1.17f1",
+            &["m_IsAlphaChannelOptional", "m_PlatformBlob"],
+            &["m_IsAlphaChannelOptional", "m_UsageMode"],
+            false,
+        ),
+        (
+            "2019.2.21f1",
+            &[
+                "m_IsAlphaChannelOptional",
+                "m_MipsStripped",
+                "m_IsPreProcessed",
+                "m_IgnoreMasterTextureLimit",
+                "m_PlatformBlob",
+            ],
+            &["m_IsAlphaChannelOptional", "m_UsageMode"],
+            true,
+        ),
+    ];
+    for (version, texture_removed, array_removed, narrow_offset) in cases {
+        let mut asset_file = AssetFile::new(version);
+        asset_file.types = vec![
+            older_type(TEXTURE_2D_HASH, texture_removed, narrow_offset),
+            older_type(TEXTURE_2D_ARRAY_HASH, array_removed, narrow_offset),
+        ];
+        let mismatches = asset_file.check_layouts().unwrap();
+        assert!(mismatches.is_empty(), "{version}: {mismatches:?}");
+        // Without a version, the gated fields may be there or not.
+        assert_eq!(asset_file.types[0].check_layout::<Texture2D>().unwrap(), []);
+        assert_eq!(
+            asset_file.types[1]
+                .check_layout::<Texture2DArray>()
+                .unwrap(),
+            []
+        );
+
+        // The same trees are missing fields as far as a 2020.3 file is concerned.
+        let mut newer = AssetFile::new("2020.3.18f1");
+        newer.types = asset_file.types.clone();
+        assert!(!newer.check_layouts().unwrap().is_empty(), "{version}");
+    }
+
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::known(TEXTURE_2D_HASH).unwrap(),
+        AssetFileType::known(TEXTURE_2D_ARRAY_HASH).unwrap(),
+    ];
+    assert_eq!(asset_file.check_layouts().unwrap(), []);
+}
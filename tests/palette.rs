@@ -0,0 +1,100 @@
+#![cfg(feature = "atlas")]
+
+use astra_formats::PaletteSprite;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+const DARK_RED: Rgba<u8> = Rgba([128, 0, 0, 255]);
+const DARK_GREEN: Rgba<u8> = Rgba([0, 128, 0, 255]);
+const DARK_BLUE: Rgba<u8> = Rgba([0, 0, 128, 255]);
+const YELLOW: Rgba<u8> = Rgba([255, 255, 0, 255]);
+
+/// A 4x2 sprite with two color schemes. Blue is the most common color, so quantizing
+/// reorders the palette and the second row has to follow.
+fn sprite() -> PaletteSprite {
+    let indices = [2, 2, 2, 1, 1, 0, 0, 2];
+    let index = RgbaImage::from_fn(4, 2, |x, y| {
+        Rgba([indices[(y * 4 + x) as usize], 0, 0, 255])
+    });
+    let rows = [[RED, GREEN, BLUE], [DARK_RED, DARK_GREEN, DARK_BLUE]];
+    let palette = RgbaImage::from_fn(6, 2, |x, y| rows[y as usize][x as usize / 2]);
+    PaletteSprite::new(
+        &DynamicImage::ImageRgba8(index),
+        &DynamicImage::ImageRgba8(palette),
+    )
+}
+
+fn recolor(image: &RgbaImage, from: Rgba<u8>, to: Rgba<u8>) -> RgbaImage {
+    let mut image = image.clone();
+    for pixel in image.pixels_mut() {
+        if *pixel == from {
+            *pixel = to;
+        }
+    }
+    image
+}
+
+#[test]
+fn quantize_unedited_render() {
+    let mut sprite = sprite();
+    let first = sprite.render(0).unwrap();
+    let second = sprite.render(1).unwrap();
+    assert!(sprite.quantize(&first, 0).unwrap().is_empty());
+
+    assert_eq!(sprite.palette_row(0).unwrap(), [BLUE, GREEN, RED]);
+    assert_eq!(
+        sprite.palette_row(1).unwrap(),
+        [DARK_BLUE, DARK_GREEN, DARK_RED]
+    );
+    assert_eq!(sprite.render(0).unwrap(), first);
+    assert_eq!(sprite.render(1).unwrap(), second);
+    // Both pixels of each entry are written.
+    assert_eq!(sprite.palette.get_pixel(1, 1), &DARK_BLUE);
+}
+
+#[test]
+fn quantize_recolored_region() {
+    let mut sprite = sprite();
+    let edited = recolor(&sprite.render(0).unwrap(), GREEN, YELLOW);
+    let second = sprite.render(1).unwrap();
+    assert!(sprite.quantize(&edited, 0).unwrap().is_empty());
+
+    assert_eq!(sprite.render(0).unwrap(), edited);
+    assert_eq!(sprite.render(1).unwrap(), second);
+}
+
+#[test]
+fn quantize_reports_merged_colors() {
+    let mut sprite = sprite();
+    let mut edited = sprite.render(0).unwrap();
+    let second = sprite.render(1).unwrap();
+    // A fourth color in a blue region, closest to red.
+    let off_red = Rgba([250, 10, 10, 255]);
+    edited.put_pixel(0, 0, off_red);
+    assert_eq!(sprite.quantize(&edited, 0).unwrap(), [off_red]);
+
+    let mut expected = edited.clone();
+    expected.put_pixel(0, 0, RED);
+    assert_eq!(sprite.render(0).unwrap(), expected);
+
+    // The pixel now uses the red entry, so the other row colors it with that entry too.
+    let mut expected = second;
+    expected.put_pixel(0, 0, DARK_RED);
+    assert_eq!(sprite.render(1).unwrap(), expected);
+}
+
+#[test]
+fn quantize_rejects_mismatched_images() {
+    let mut sprite = sprite();
+    let err = sprite.quantize(&RgbaImage::new(2, 2), 0).unwrap_err();
+    assert!(err.to_string().contains("is 4x2 but the image is 2x2"));
+    assert!(sprite.quantize(&sprite.render(0).unwrap(), 2).is_err());
+}
+
+#[test]
+fn extract_palette_orders_by_count() {
+    let image = RgbaImage::from_fn(5, 1, |x, _| [GREEN, RED, BLUE, RED, BLUE][x as usize]);
+    assert_eq!(PaletteSprite::extract_palette(&image), [RED, BLUE, GREEN]);
+}
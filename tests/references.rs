@@ -0,0 +1,149 @@
+mod common;
+
+use astra_formats::{
+    Asset, AssetFile, AssetFileType, PPtr, UString, ASSET_BUNDLE_HASH, GAME_OBJECT_HASH,
+    MATERIAL_HASH, TEXTURE_2D_HASH, TRANSFORM_HASH,
+};
+use common::*;
+
+fn pptr_to(file_id: i32, path_id: i64) -> PPtr {
+    PPtr { file_id, path_id }
+}
+
+fn ids(pptrs: &[PPtr]) -> Vec<(i32, i64)> {
+    pptrs
+        .iter()
+        .map(|pptr| (pptr.file_id, pptr.path_id))
+        .collect()
+}
+
+/// Container entries whose preload ranges overlap and lose preloads at their start, middle and
+/// end. The textures at 20 to 22 are only preloaded, so they are collected.
+fn bundle_fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(142, ASSET_BUNDLE_HASH),
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        AssetFileType::new(28, TEXTURE_2D_HASH),
+    ];
+    asset_file.insert_asset(1, asset_bundle()).unwrap();
+    for path_id in [2, 3, 4, 5] {
+        asset_file
+            .insert_asset(path_id, game_object("kept", &[]))
+            .unwrap();
+    }
+    for path_id in [20, 21, 22] {
+        let texture = texture_2d("RGBA32", 1, 1, 1, &[0; 4], streaming_info());
+        asset_file
+            .insert_asset(path_id, Asset::Texture2D(texture, 0))
+            .unwrap();
+    }
+    let Some(Asset::Bundle(bundle)) = asset_file.get_asset_by_path_id_mut(1) else {
+        unreachable!();
+    };
+    bundle.preloads.items = vec![
+        pptr(20),
+        pptr(2),
+        pptr(21),
+        pptr(3),
+        // Another file's object with a removed path ID.
+        pptr_to(1, 20),
+        pptr(4),
+        pptr(22),
+    ];
+    let ranges = [("a", 2, 0, 4), ("b", 3, 2, 4), ("c", 5, 6, 1)];
+    for (name, path_id, preload_index, preload_size) in ranges {
+        let info = serde_json::from_value(serde_json::json!({
+            "preload_index": preload_index,
+            "preload_size": preload_size,
+            "asset": pptr(path_id),
+        }))
+        .unwrap();
+        bundle
+            .container_map
+            .items
+            .push((UString(String::from(name)), info));
+    }
+    bundle.main_asset.asset = pptr(4);
+    bundle.main_asset.preload_index = 4;
+    bundle.main_asset.preload_size = 3;
+    asset_file
+}
+
+#[test]
+fn gc_remaps_preload_ranges() {
+    let mut asset_file = bundle_fixture();
+    let mut removed = asset_file.gc();
+    removed.sort();
+    assert_eq!(removed, [20, 21, 22]);
+
+    let Some(Asset::Bundle(bundle)) = asset_file.get_asset_by_path_id(1) else {
+        panic!("the AssetBundle was removed");
+    };
+    assert_eq!(
+        ids(&bundle.preloads.items),
+        [(0, 2), (0, 3), (1, 20), (0, 4)]
+    );
+    let ranges: Vec<(&str, u32, u32)> = bundle
+        .container_map
+        .iter()
+        .map(|(name, info)| (name.0.as_str(), info.preload_index, info.preload_size))
+        .collect();
+    // "a" lost its first and third preloads, "b" its first and "c" its only one.
+    assert_eq!(ranges, [("a", 0, 2), ("b", 1, 3), ("c", 4, 0)]);
+    // The main asset lost its last preload.
+    let main_asset = &bundle.main_asset;
+    assert_eq!((main_asset.preload_index, main_asset.preload_size), (2, 2));
+}
+
+/// A plain assets file: a root GameObject with a child, a UI root with a RectTransform, a
+/// material with its texture that nothing uses and a second unused texture.
+fn plain_fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        AssetFileType::new(4, TRANSFORM_HASH),
+        rect_transform_type(),
+        AssetFileType::new(21, MATERIAL_HASH),
+        AssetFileType::new(28, TEXTURE_2D_HASH),
+    ];
+    let texture = || {
+        let texture = texture_2d("RGBA32", 1, 1, 1, &[0; 4], streaming_info());
+        Asset::Texture2D(texture, 0)
+    };
+    let assets = [
+        (1, game_object("root", &[2])),
+        (2, transform(1, 0, &[4], vector(0., 0., 0.))),
+        (3, game_object("child", &[4])),
+        (4, transform(3, 2, &[], vector(0., 0., 0.))),
+        (5, material(pptr(0), 6)),
+        (6, texture()),
+        (7, texture()),
+        (8, game_object("ui", &[9])),
+        (9, rect_transform(8, 0, &[])),
+    ];
+    for (path_id, asset) in assets {
+        asset_file.insert_asset(path_id, asset).unwrap();
+    }
+    asset_file
+}
+
+#[test]
+fn plain_file_is_rooted_at_top_level_game_objects() {
+    let mut asset_file = plain_fixture();
+    let graph = asset_file.reference_graph();
+    assert_eq!(graph.roots(), [1, 8]);
+    assert_eq!(graph.orphans(), [5, 6, 7]);
+
+    assert_eq!(asset_file.gc(), [5, 6, 7]);
+    assert!(asset_file.get_asset_by_path_id(4).is_some());
+    assert!(asset_file.get_asset_by_path_id(9).is_some());
+}
+
+#[test]
+fn gc_from_keeps_what_the_roots_reach() {
+    let mut asset_file = plain_fixture();
+    assert_eq!(asset_file.gc_from(&[5]), [1, 2, 3, 4, 7, 8, 9]);
+    assert!(asset_file.get_asset_by_path_id(5).is_some());
+    assert!(asset_file.get_asset_by_path_id(6).is_some());
+}
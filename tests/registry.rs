@@ -0,0 +1,136 @@
+mod common;
+
+use astra_formats::{
+    Asset, AssetFile, AssetFileType, AssetReferences, AssetRegistry, Bundle, BundleFile,
+    MonoBehavior, MonoScript, PPtr, UString, Unparsed, MONO_SCRIPT_HASH,
+};
+use binrw::{binrw, BinWrite};
+use common::*;
+use serde_json::json;
+use std::io::Cursor;
+
+const COLLIDER_HASH: i128 = 5678;
+
+#[binrw]
+#[derive(Debug, Clone)]
+struct Collider {
+    radius: f32,
+    target: PPtr,
+}
+
+impl AssetReferences for Collider {
+    fn references(&self) -> Vec<&PPtr> {
+        vec![&self.target]
+    }
+
+    fn references_mut(&mut self) -> Vec<&mut PPtr> {
+        vec![&mut self.target]
+    }
+}
+
+fn collider(radius: f32) -> MonoBehavior<Collider> {
+    MonoBehavior {
+        game_object: pptr(3),
+        enabled: 1,
+        script: pptr(2),
+        name: UString(String::from("collider")),
+        data: Collider {
+            radius,
+            target: pptr(3),
+        },
+    }
+}
+
+/// A MonoBehaviour of the "Game.SpringCollider" script, left unparsed.
+fn fixture(radius: f32) -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(114, COLLIDER_HASH),
+        AssetFileType::new(115, MONO_SCRIPT_HASH),
+    ];
+    let mut cursor = Cursor::new(vec![]);
+    collider(radius).write_le(&mut cursor).unwrap();
+    let unparsed = Asset::Unparsed(Unparsed {
+        type_hash: COLLIDER_HASH,
+        path_id: 0,
+        blob: cursor.into_inner(),
+    });
+    let script: MonoScript = serde_json::from_value(json!({
+        "name": "SpringCollider",
+        "execution_order": 0,
+        "properties_hash": 0,
+        "class_name": "SpringCollider",
+        "namespace": "Game",
+        "assembly_name": "Assembly-CSharp.dll",
+    }))
+    .unwrap();
+    asset_file.insert_asset(1, unparsed).unwrap();
+    asset_file.insert_asset(2, Asset::Script(script)).unwrap();
+    asset_file
+}
+
+fn edit_and_check(asset_file: &mut AssetFile) {
+    let Some(Asset::Custom(custom)) = asset_file.get_asset_by_path_id_mut(1) else {
+        panic!("the MonoBehaviour was not parsed");
+    };
+    assert_eq!(custom.script.as_ref().map(|script| script.path_id), Some(2));
+    let behavior = custom.downcast_mut::<MonoBehavior<Collider>>().unwrap();
+    assert_eq!(behavior.data.radius, 0.5);
+    behavior.data.radius = 2.0;
+    assert_eq!(serialize(asset_file), serialize(&fixture(2.0)));
+}
+
+fn registries() -> [AssetRegistry; 2] {
+    let mut by_hash = AssetRegistry::new();
+    by_hash.register_asset_type::<MonoBehavior<Collider>>(COLLIDER_HASH);
+    let mut by_class = AssetRegistry::new();
+    by_class.register_mono_behavior_class::<MonoBehavior<Collider>>("Game.SpringCollider");
+    [by_hash, by_class]
+}
+
+#[test]
+fn asset_file_reads_registered_types() {
+    let raw = serialize(&fixture(0.5));
+    for registry in registries() {
+        let mut asset_file =
+            AssetFile::read_with_registry(&mut Cursor::new(&raw), &registry).unwrap();
+        assert_eq!(serialize(&asset_file), raw);
+        edit_and_check(&mut asset_file);
+    }
+}
+
+#[test]
+fn bundle_reads_registered_types() {
+    let mut bundle = Bundle::new();
+    bundle.insert(
+        String::from("CAB-00000000000000000000000000000000"),
+        BundleFile::Assets(fixture(0.5)),
+    );
+    let raw = bundle.serialize().unwrap();
+    for registry in registries() {
+        let mut bundle = Bundle::from_slice_with_registry(&raw, &registry).unwrap();
+        assert_eq!(bundle.serialize().unwrap(), raw);
+        let Some(BundleFile::Assets(asset_file)) =
+            bundle.get_mut("CAB-00000000000000000000000000000000")
+        else {
+            panic!("the assets file is missing");
+        };
+        edit_and_check(asset_file);
+    }
+}
+
+#[test]
+fn unregistered_and_unused_data_stays_unparsed() {
+    let mut asset_file = fixture(0.5);
+    assert!(AssetRegistry::new().apply(&mut asset_file).is_empty());
+    assert!(matches!(
+        asset_file.get_asset_by_path_id(1),
+        Some(Asset::Unparsed(_))
+    ));
+
+    // A registered type that leaves data over is reported and kept as it was.
+    let mut registry = AssetRegistry::new();
+    registry.register_asset_type::<MonoBehavior<()>>(COLLIDER_HASH);
+    assert_eq!(registry.apply(&mut asset_file), [1]);
+    assert_eq!(serialize(&asset_file), serialize(&fixture(0.5)));
+}
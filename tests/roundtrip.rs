@@ -0,0 +1,378 @@
+mod common;
+
+use astra_formats::{
+    AnimatorController, Asset, AssetFile, AssetFileType, Bundle, BundleFile, CompressionType,
+    MonoBehavior, PPtr, TerrainData, TerrainLayerData, TextAsset, UArray, UString, Unparsed,
+    ANIMATOR_CONTROLLER_HASH, GAME_OBJECT_HASH, TERRAIN_MONO_BEHAVIOR_TYPE_HASH, TEXTURE_2D_HASH,
+    TEXT_ASSET_HASH, TRANSFORM_HASH,
+};
+use binrw::{BinRead, BinWrite};
+use common::*;
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+const UNPARSED_HASH: i128 = 1234;
+
+fn fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        AssetFileType::new(4, TRANSFORM_HASH),
+        AssetFileType::new(49, TEXT_ASSET_HASH),
+        AssetFileType::new(114, TERRAIN_MONO_BEHAVIOR_TYPE_HASH),
+        AssetFileType::new(1000, UNPARSED_HASH),
+    ];
+    let assets = [
+        (1, game_object("c_root", &[2, 4])),
+        (2, transform(1, 0, &[], vector(1., 2., 3.))),
+        (
+            3,
+            Asset::Text(TextAsset {
+                name: UString(String::from("odd")),
+                data: UArray {
+                    items: b"hello".to_vec(),
+                },
+            }),
+        ),
+        (
+            4,
+            Asset::Terrain(MonoBehavior {
+                game_object: pptr(1),
+                enabled: 1,
+                script: PPtr {
+                    file_id: 1,
+                    path_id: 99,
+                },
+                name: UString(String::from("terrain")),
+                data: TerrainData {
+                    x: 1,
+                    z: 2,
+                    width: 3,
+                    height: 4,
+                    layers: UArray {
+                        items: vec![TerrainLayerData {
+                            x: 0,
+                            y: 0,
+                            w: 1,
+                            h: 1,
+                            group: 2,
+                            attr: UString(String::from("grass")),
+                        }],
+                    },
+                    overlaps: UArray::default(),
+                    terrains: UArray {
+                        items: vec![UString(String::from("a")), UString(String::from("bc"))],
+                    },
+                },
+            }),
+        ),
+        (
+            5,
+            Asset::Unparsed(Unparsed {
+                type_hash: UNPARSED_HASH,
+                path_id: 0,
+                blob: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            }),
+        ),
+    ];
+    for (path_id, asset) in assets {
+        asset_file.insert_asset(path_id, asset).unwrap();
+    }
+    asset_file
+}
+
+fn texture_array(name: &str) -> Value {
+    json!({
+        "name": name,
+        "forced_fallback_format": 4,
+        "downscale_fallback": 0,
+        "is_alpha_channel_optional": 0,
+        "color_space": 1,
+        "format": 8,
+        "width": 1,
+        "height": 1,
+        "depth": 2,
+        "mip_count": 1,
+        "data_size": 8,
+        "texture_settings": texture_settings(),
+        "usage_mode": 0,
+        "is_readable": 1,
+        "image_data": [1, 2, 3, 4, 5, 6, 7, 8],
+        "stream_data": streaming_info(),
+    })
+}
+
+fn animator_controller() -> Value {
+    let empty_blend_tree_node = json!({
+        "blend_type": 0,
+        "blend_event_id": u32::MAX,
+        "blend_event_y_id": u32::MAX,
+        "child_indices": [],
+        "blend_1d_data": {"child_threshold_array": []},
+        "blend_2d_data": {
+            "child_position_array": [],
+            "child_magnitude_array": [],
+            "child_pair_vector_array": [],
+            "child_pair_avg_mag_inv_array": [],
+            "child_neighbor_list_array": [],
+        },
+        "blend_direct_data": {"child_blend_event_id_array": [], "normalized_blend_values": 0},
+        "clip_id": 0,
+        "duration": 1.0,
+        "cycle_offset": 0.0,
+        "mirror": 0,
+    });
+    let transition = json!({
+        "condition_constant_array": [
+            {"condition_mode": 1, "event_id": 5, "event_threshold": 0.0, "exit_time": 0.0},
+        ],
+        "destination_state": 0,
+        "full_path_id": 1,
+        "id": 2,
+        "user_id": 0,
+        "transition_duration": 0.25,
+        "transition_offset": 0.0,
+        "exit_time": 0.9,
+        "has_exit_time": 1,
+        "has_fixed_duration": 1,
+        "interruption_source": 0,
+        "ordered_interruption": 1,
+        "can_transition_to_self": 1,
+    });
+    json!({
+        "name": "controller",
+        "controller_size": 0,
+        "controller": {
+            "layer_array": [{
+                "state_machine_index": 0,
+                "state_machine_motion_set_index": 0,
+                "body_mask": {"word0": 1, "word1": 2, "word2": 3},
+                "skeleton_mask": {"data": []},
+                "binding": 10,
+                "layer_blending_mode": 0,
+                "default_weight": 0.0,
+                "ik_pass": 0,
+                "synced_layer_affects_timing": 0,
+            }],
+            "state_machine_array": [{
+                "state_constant_array": [{
+                    "transition_constant_array": [transition],
+                    "blend_tree_constant_index_array": [0],
+                    "blend_tree_constant_array": [{"node_array": [empty_blend_tree_node]}],
+                    "name_id": 11,
+                    "path_id": 12,
+                    "full_path_id": 12,
+                    "tag_id": 0,
+                    "speed_param_id": 0,
+                    "mirror_param_id": 0,
+                    "cycle_offset_param_id": 0,
+                    "time_param_id": 0,
+                    "speed": 1.0,
+                    "cycle_offset": 0.0,
+                    "ik_on_feet": 0,
+                    "write_default_values": 1,
+                    "m_loop": 0,
+                    "mirror": 0,
+                }],
+                "any_state_transition_constant_array": [],
+                "selector_state_constant_array": [{
+                    "transition_constant_array": [
+                        {"destination": 0, "condition_constant_array": []},
+                    ],
+                    "full_path_id": 9,
+                    "is_entry": 1,
+                }],
+                "default_state": 0,
+                "motion_set_count": 1,
+            }],
+            "values": {"value_array": [{"id": 5, "m_type": 4, "index": 0}]},
+            "default_values": {
+                "position_values": [],
+                "quaternion_values": [],
+                "scale_values": [],
+                "float_values": [],
+                "int_values": [],
+                "bool_values": [1],
+            },
+        },
+        "tos": [{"first": 10, "second": "Base Layer"}, {"first": 11, "second": "Idle"}],
+        "animation_clips": [{"file_id": 0, "path_id": 100}],
+        "state_machine_behaviour_vector_description": {
+            "state_machine_behaviour_ranges": [],
+            "state_machine_behaviour_indices": [],
+        },
+        "state_machine_behaviours": [],
+        "multi_threaded_state_machine": 1,
+    })
+}
+
+/// Objects whose structs are parsed field by field rather than kept as raw data.
+fn parsed_fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    let texture = texture_2d_json(
+        "RGBA32",
+        2,
+        1,
+        1,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+        streaming_info(),
+    );
+    let mut face = texture.clone();
+    face["name"] = json!("cubemap");
+    face["image_count"] = json!(6);
+    face["texture_dimension"] = json!(4);
+    let cubemap = json!({"texture": face, "source_textures": [{"file_id": 0, "path_id": 1}]});
+    // Hashes differ between Unity revisions, so the texture types are matched by class ID.
+    let assets = [
+        (28, TEXTURE_2D_HASH, json!({"Texture2D": texture})),
+        (89, 1, json!({"Cubemap": cubemap})),
+        (187, 2, json!({"Texture2DArray": texture_array("array")})),
+        (117, 3, json!({"Texture3D": texture_array("volume")})),
+        (
+            91,
+            ANIMATOR_CONTROLLER_HASH,
+            json!({"AnimatorController": animator_controller()}),
+        ),
+    ];
+    for (path_id, (class_id, type_hash, asset)) in (1..).zip(assets) {
+        asset_file.add_type(AssetFileType::new(class_id, type_hash));
+        let asset: Asset = serde_json::from_value(asset).unwrap();
+        asset_file.insert_asset(path_id, asset).unwrap();
+    }
+    asset_file
+}
+
+#[test]
+fn asset_file_roundtrips() {
+    assert_eq!(fixture().roundtrip_check().unwrap(), None);
+}
+
+#[test]
+fn parsed_structs_roundtrip() {
+    let asset_file = parsed_fixture();
+    assert_eq!(asset_file.roundtrip_check().unwrap(), None);
+    let raw = serialize(&asset_file);
+    assert_eq!(AssetFile::roundtrip_check_bytes(&raw).unwrap(), None);
+}
+
+#[test]
+fn serialized_asset_file_roundtrips() {
+    let raw = serialize(&fixture());
+    assert_eq!(AssetFile::roundtrip_check_bytes(&raw).unwrap(), None);
+}
+
+#[test]
+fn bundle_roundtrips() {
+    for compression_type in [CompressionType::Uncompressed, CompressionType::Lz4] {
+        let mut bundle = Bundle::new();
+        bundle.insert(
+            String::from("CAB-00000000000000000000000000000000"),
+            BundleFile::Assets(fixture()),
+        );
+        bundle.insert(
+            String::from("CAB-00000000000000000000000000000000.resS"),
+            BundleFile::Raw(vec![0xAB; 0x30000]),
+        );
+        let raw = bundle
+            .serialize_with_block_compression(compression_type)
+            .unwrap();
+        assert_eq!(Bundle::roundtrip_check(&raw).unwrap(), None);
+    }
+}
+
+#[test]
+fn reports_differing_field() {
+    let mut asset_file = fixture();
+    // Unaligned blobs pick up the writer's padding when read back.
+    if let Some(Asset::Unparsed(unparsed)) = asset_file.get_asset_by_path_id_mut(5) {
+        unparsed.blob = vec![1, 2, 3];
+    }
+    let mismatch = asset_file.roundtrip_check().unwrap().unwrap();
+    assert_eq!(mismatch.path_id, Some(5));
+    assert_eq!(mismatch.field, "blob");
+    assert_eq!(mismatch.expected, "010203");
+    assert_eq!(mismatch.actual, "01020300");
+}
+
+#[test]
+fn json_roundtrips_non_finite_floats() {
+    let mut asset_file = fixture();
+    if let Some(Asset::Transform(transform)) = asset_file.get_asset_by_path_id_mut(2) {
+        transform.local_position = vector(f32::INFINITY, f32::NEG_INFINITY, f32::NAN);
+    }
+    let dir = std::env::temp_dir().join(format!("astra-formats-json-{}", std::process::id()));
+    asset_file.export_json(&dir).unwrap();
+    let mut imported = fixture();
+    let result = imported.import_json(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.unwrap();
+    let Some(Asset::Transform(transform)) = imported.get_asset_by_path_id(2) else {
+        panic!("transform was not imported");
+    };
+    assert_eq!(transform.local_position.x, f32::INFINITY);
+    assert_eq!(transform.local_position.y, f32::NEG_INFINITY);
+    assert!(transform.local_position.z.is_nan());
+    assert_eq!(serialize(&imported), serialize(&asset_file));
+}
+
+#[test]
+fn other_type_hashes_parse_by_class_id() {
+    let mut asset_file = fixture();
+    // Built in classes from another Unity version come with their own hashes.
+    for ty in &mut asset_file.types {
+        if ty.class_id != 114 && ty.class_id != 1000 {
+            ty.type_hash += 1;
+        }
+    }
+    let raw = serialize(&asset_file);
+    let reread = AssetFile::read_le(&mut Cursor::new(&raw)).unwrap();
+    assert!(matches!(
+        reread.get_asset_by_path_id(2),
+        Some(Asset::Transform(_))
+    ));
+    assert!(matches!(
+        reread.get_asset_by_path_id(3),
+        Some(Asset::Text(_))
+    ));
+    assert_eq!(serialize(&reread), raw);
+}
+
+fn controller_file(asset: Asset) -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.add_type(AssetFileType::new(91, ANIMATOR_CONTROLLER_HASH));
+    asset_file.insert_asset(1, asset).unwrap();
+    asset_file
+}
+
+#[test]
+fn animator_controller_roundtrips() {
+    let controller: AnimatorController = serde_json::from_value(animator_controller()).unwrap();
+    let raw = serialize(&controller_file(Asset::AnimatorController(controller)));
+    let reread = AssetFile::read_le(&mut Cursor::new(&raw)).unwrap();
+    assert!(matches!(
+        reread.get_asset_by_path_id(1),
+        Some(Asset::AnimatorController(_))
+    ));
+    assert_eq!(serialize(&reread), raw);
+}
+
+#[test]
+fn animator_controller_with_unknown_data_stays_unparsed() {
+    let controller: AnimatorController = serde_json::from_value(animator_controller()).unwrap();
+    let mut cursor = Cursor::new(vec![]);
+    controller.write_le(&mut cursor).unwrap();
+    let mut blob = cursor.into_inner();
+    // Fields a newer layout added after the ones the crate knows about.
+    blob.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+    let raw = serialize(&controller_file(Asset::Unparsed(Unparsed {
+        type_hash: ANIMATOR_CONTROLLER_HASH,
+        path_id: 1,
+        blob: blob.clone(),
+    })));
+    let reread = AssetFile::read_le(&mut Cursor::new(&raw)).unwrap();
+    match reread.get_asset_by_path_id(1) {
+        Some(Asset::Unparsed(unparsed)) => assert_eq!(unparsed.blob, blob),
+        asset => panic!("expected unparsed data, found {:?}", asset),
+    }
+    assert_eq!(serialize(&reread), raw);
+}
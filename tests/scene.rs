@@ -0,0 +1,62 @@
+mod common;
+
+use astra_formats::{
+    Asset, AssetFile, AssetFileType, ASSET_BUNDLE_HASH, GAME_OBJECT_HASH, TRANSFORM_HASH,
+};
+use common::*;
+
+/// Two GameObjects whose Transforms are each other's parent and child.
+fn fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(142, ASSET_BUNDLE_HASH),
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        AssetFileType::new(4, TRANSFORM_HASH),
+    ];
+    let assets = [
+        (1, asset_bundle()),
+        (2, game_object("a", &[3])),
+        (3, transform(2, 5, &[5], vector(1., 0., 0.))),
+        (4, game_object("b", &[5])),
+        (5, transform(4, 3, &[3], vector(2., 0., 0.))),
+    ];
+    for (path_id, asset) in assets {
+        asset_file.insert_asset(path_id, asset).unwrap();
+    }
+    asset_file
+}
+
+#[test]
+fn cyclic_hierarchy_terminates() {
+    let asset_file = fixture();
+    let graph = asset_file.scene_graph();
+    let node = graph.node(2).unwrap();
+    assert_eq!(node.path(), "b/a");
+    assert_eq!(node.ancestors().len(), 1);
+    assert_eq!(node.world_matrix().e03, 3.);
+}
+
+#[test]
+fn added_game_object_is_in_the_container() {
+    let mut asset_file = fixture();
+    let path_id = asset_file.add_game_object("c_new", None).unwrap();
+    let child = asset_file
+        .add_game_object("c_child", Some(path_id))
+        .unwrap();
+    let Some(Asset::Bundle(bundle)) = asset_file.get_asset_by_path_id(1) else {
+        panic!("bundle is missing");
+    };
+    let entries: Vec<(&str, i64)> = bundle
+        .container_map
+        .items
+        .iter()
+        .map(|(name, info)| (name.0.as_str(), info.asset.path_id))
+        .collect();
+    assert_eq!(entries, [("c_new", path_id), ("c_new/c_child", child)]);
+
+    let removed = asset_file.gc();
+    for kept in [path_id, path_id + 1, child, child + 1] {
+        assert!(!removed.contains(&kept));
+        assert!(asset_file.get_asset_by_path_id(kept).is_some());
+    }
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use astra_formats::binrw::NullString;
+use astra_formats::{
+    Asset, AssetExternal, AssetFile, AssetFileType, Bundle, BundleFile, MonoBehavior, MonoScript,
+    PPtr, ScriptResolver, UString, Unparsed, EMPTY_MONO_BEHAVIOR_HASH, GAME_OBJECT_HASH,
+    MONO_SCRIPT_HASH,
+};
+use binrw::BinWrite;
+use common::*;
+use serde_json::json;
+use std::io::Cursor;
+
+const SCRIPTS: &str = "CAB-00000000000000000000000000000001";
+const BEHAVIORS: &str = "CAB-00000000000000000000000000000002";
+const COLLIDER_HASH: i128 = 5678;
+
+fn script(class_name: &str, namespace: &str) -> Asset {
+    let script: MonoScript = serde_json::from_value(json!({
+        "name": class_name,
+        "execution_order": 0,
+        "properties_hash": 0,
+        "class_name": class_name,
+        "namespace": namespace,
+        "assembly_name": "Assembly-CSharp.dll",
+    }))
+    .unwrap();
+    Asset::Script(script)
+}
+
+fn behavior<T: std::fmt::Debug>(script: PPtr, data: T) -> MonoBehavior<T> {
+    MonoBehavior {
+        game_object: pptr(1),
+        enabled: 1,
+        script,
+        name: UString::default(),
+        data,
+    }
+}
+
+/// Scripts in one file, MonoBehaviours in the other. The behaviours use both the external
+/// scripts and one that lives next to them.
+fn bundle() -> Bundle {
+    let mut scripts = AssetFile::new("2020.3.18f1");
+    scripts.types = vec![AssetFileType::new(115, MONO_SCRIPT_HASH)];
+    scripts
+        .insert_asset(1, script("SpringBone", "Game"))
+        .unwrap();
+    scripts
+        .insert_asset(2, script("SpringCollider", "Game"))
+        .unwrap();
+
+    let mut behaviors = AssetFile::new("2020.3.18f1");
+    behaviors.types = vec![
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        AssetFileType::new(114, EMPTY_MONO_BEHAVIOR_HASH),
+        AssetFileType::new(114, COLLIDER_HASH),
+        AssetFileType::new(115, MONO_SCRIPT_HASH),
+    ];
+    behaviors.externals = vec![AssetExternal {
+        unknown: NullString::default(),
+        guid: 0,
+        ty: 0,
+        path: NullString::from(format!("archive:/{SCRIPTS}/{SCRIPTS}").as_str()),
+    }];
+    let external = |path_id| PPtr {
+        file_id: 1,
+        path_id,
+    };
+    let mut cursor = Cursor::new(vec![]);
+    behavior(external(2), 0.5f32).write_le(&mut cursor).unwrap();
+    let collider = Asset::Unparsed(Unparsed {
+        type_hash: COLLIDER_HASH,
+        path_id: 0,
+        blob: cursor.into_inner(),
+    });
+    let assets = [
+        (1, game_object("spring", &[2, 3, 4, 5])),
+        (2, Asset::EmptyMonoBehavior(behavior(external(1), ()))),
+        (3, collider),
+        (4, Asset::EmptyMonoBehavior(behavior(pptr(6), ()))),
+        (5, Asset::EmptyMonoBehavior(behavior(external(3), ()))),
+        (6, script("SpringBone", "Local")),
+    ];
+    for (path_id, asset) in assets {
+        behaviors.insert_asset(path_id, asset).unwrap();
+    }
+
+    let mut bundle = Bundle::new();
+    bundle.insert(String::from(SCRIPTS), BundleFile::Assets(scripts));
+    bundle.insert(String::from(BEHAVIORS), BundleFile::Assets(behaviors));
+    Bundle::from_slice(&bundle.serialize().unwrap()).unwrap()
+}
+
+fn assets<'a>(bundle: &'a Bundle, name: &str) -> &'a AssetFile {
+    match bundle.get(name) {
+        Some(BundleFile::Assets(asset_file)) => asset_file,
+        _ => panic!("bundle does not contain '{name}'"),
+    }
+}
+
+fn full_name(resolver: &ScriptResolver, asset_file: &AssetFile, path_id: i64) -> Option<String> {
+    let asset = asset_file.get_asset_by_path_id(path_id).unwrap();
+    resolver
+        .resolve_asset(asset_file, asset)
+        .map(MonoScript::full_name)
+}
+
+#[test]
+fn resolves_scripts_in_other_files() {
+    let bundle = bundle();
+    let behaviors = assets(&bundle, BEHAVIORS);
+    let resolver = ScriptResolver::from_bundle(&bundle);
+
+    assert_eq!(
+        full_name(&resolver, behaviors, 2).as_deref(),
+        Some("Game.SpringBone")
+    );
+    // Unparsed MonoBehaviours are resolved through the script PPtr in their blob.
+    assert!(matches!(
+        behaviors.get_asset_by_path_id(3),
+        Some(Asset::Unparsed(_))
+    ));
+    assert_eq!(
+        full_name(&resolver, behaviors, 3).as_deref(),
+        Some("Game.SpringCollider")
+    );
+    assert_eq!(
+        full_name(&resolver, behaviors, 4).as_deref(),
+        Some("Local.SpringBone")
+    );
+    // Path ID 3 doesn't exist in the scripts file.
+    assert_eq!(full_name(&resolver, behaviors, 5), None);
+    assert_eq!(full_name(&resolver, behaviors, 1), None);
+}
+
+#[test]
+fn unknown_files_are_not_resolved() {
+    let bundle = bundle();
+    let behaviors = assets(&bundle, BEHAVIORS);
+    let mut resolver = ScriptResolver::new();
+    resolver.add_file(BEHAVIORS, behaviors);
+    assert_eq!(full_name(&resolver, behaviors, 2), None);
+    assert_eq!(
+        full_name(&resolver, behaviors, 4).as_deref(),
+        Some("Local.SpringBone")
+    );
+
+    let script = PPtr {
+        file_id: 2,
+        path_id: 1,
+    };
+    assert!(ScriptResolver::from_bundle(&bundle)
+        .resolve(behaviors, &script)
+        .is_none());
+}
+
+#[test]
+fn finds_mono_behaviors_by_class_name() {
+    let bundle = bundle();
+    let behaviors = assets(&bundle, BEHAVIORS);
+    let resolver = ScriptResolver::from_bundle(&bundle);
+    let find = |class_name| -> Vec<i64> {
+        resolver
+            .find_mono_behaviors(behaviors, class_name)
+            .into_iter()
+            .map(|(path_id, _)| path_id)
+            .collect()
+    };
+    assert_eq!(find("SpringBone"), [2, 4]);
+    assert_eq!(find("Game.SpringBone"), [2]);
+    assert_eq!(find("Local.SpringBone"), [4]);
+    assert_eq!(find("Game.SpringCollider"), [3]);
+    assert_eq!(find("SpringCollider"), [3]);
+    assert!(find("Other.SpringBone").is_empty());
+}
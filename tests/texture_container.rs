@@ -0,0 +1,36 @@
+#![cfg(feature = "atlas")]
+
+mod common;
+
+use astra_formats::{TextureContainer, TextureSwizzle, UArray};
+use common::*;
+
+#[test]
+fn export_then_import_keeps_texture() {
+    // 8x8 has four 16 byte blocks at the top level, then one block for each smaller level.
+    let cases = [
+        (TextureContainer::Dds, "BC7", 8, 8, 4, 112),
+        (TextureContainer::Ktx2, "BC7", 8, 8, 4, 112),
+        (TextureContainer::Ktx2, "ETC_RGB4", 8, 4, 1, 16),
+        (TextureContainer::Ktx2, "ETC2_RGB", 8, 4, 1, 16),
+        (TextureContainer::Ktx2, "ASTC_RGB_4x4", 8, 8, 1, 64),
+        (TextureContainer::Ktx2, "ASTC_RGBA_4x4", 8, 8, 1, 64),
+    ];
+    for (container, format, width, height, mip_count, size) in cases {
+        let data: Vec<u8> = (0..size).map(|i| i as u8).collect();
+        let original = texture_2d(format, width, height, mip_count, &data, streaming_info());
+        let exported = original
+            .export(&[], TextureSwizzle::Linear, container)
+            .unwrap();
+        assert_eq!(TextureContainer::detect(&exported), Some(container));
+
+        let mut imported = texture_2d(format, 1, 1, 1, &[], streaming_info());
+        let items = imported.import(&exported, TextureSwizzle::Linear).unwrap();
+        imported.image_data = UArray { items };
+        assert_eq!(
+            serde_json::to_value(&imported).unwrap(),
+            serde_json::to_value(&original).unwrap(),
+            "{container:?} {format}"
+        );
+    }
+}
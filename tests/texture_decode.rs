@@ -0,0 +1,478 @@
+#![cfg(feature = "atlas")]
+
+// Known-answer blocks built by hand from the BC7, BC6H and ETC2/EAC specs, one per encoding mode.
+
+mod common;
+
+use astra_formats::{
+    Asset, AssetFile, AssetFileType, Bundle, BundleFile, TextureFormat, TextureSwizzle,
+    TEXTURE_2D_HASH,
+};
+use common::*;
+use image::{DynamicImage, RgbaImage};
+use serde_json::json;
+
+fn decode(format: &str, block: &[u8]) -> DynamicImage {
+    texture_2d(format, 4, 4, 1, block, streaming_info())
+        .decode(&[], TextureSwizzle::Linear)
+        .unwrap()
+}
+
+fn check_rgba8(format: &str, block: &[u8], expected: [[u8; 4]; 16]) {
+    let image = decode(format, block).into_rgba8();
+    let pixels: Vec<[u8; 4]> = image.pixels().map(|pixel| pixel.0).collect();
+    assert_eq!(pixels, expected, "{format} {block:?}");
+}
+
+fn check_rgba32f(format: &str, block: &[u8], expected: [[f32; 4]; 16]) {
+    let DynamicImage::ImageRgba32F(image) = decode(format, block) else {
+        panic!("{format} should decode to RGBA32F");
+    };
+    let pixels: Vec<[f32; 4]> = image.pixels().map(|pixel| pixel.0).collect();
+    assert_eq!(pixels, expected, "{format} {block:?}");
+}
+
+#[test]
+fn bc7_mode_0() {
+    // Three subsets with unique p-bits.
+    check_rgba8(
+        "BC7",
+        &[
+            33, 240, 173, 201, 154, 86, 114, 37, 225, 92, 165, 130, 241, 80, 157, 241,
+        ],
+        [
+            [16, 99, 181, 255],
+            [105, 187, 80, 255],
+            [208, 101, 109, 255],
+            [247, 66, 148, 255],
+            [88, 170, 100, 255],
+            [33, 116, 161, 255],
+            [127, 172, 28, 255],
+            [188, 118, 89, 255],
+            [16, 99, 181, 255],
+            [113, 121, 204, 255],
+            [175, 68, 150, 255],
+            [107, 189, 8, 255],
+            [133, 104, 187, 255],
+            [194, 50, 132, 255],
+            [94, 139, 222, 255],
+            [155, 85, 167, 255],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_1() {
+    // Two subsets with shared p-bits.
+    check_rgba8(
+        "BC7",
+        &[
+            2, 196, 34, 101, 9, 116, 121, 78, 197, 141, 229, 161, 58, 227, 161, 58,
+        ],
+        [
+            [22, 42, 62, 255],
+            [42, 62, 82, 255],
+            [84, 104, 124, 255],
+            [72, 92, 112, 255],
+            [38, 58, 78, 255],
+            [26, 46, 66, 255],
+            [100, 120, 141, 255],
+            [88, 108, 129, 255],
+            [22, 42, 62, 255],
+            [42, 62, 82, 255],
+            [84, 104, 124, 255],
+            [72, 92, 112, 255],
+            [38, 58, 78, 255],
+            [26, 46, 66, 255],
+            [100, 120, 141, 255],
+            [72, 92, 112, 255],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_2() {
+    // Three subsets without p-bits.
+    check_rgba8(
+        "BC7",
+        &[
+            4, 142, 171, 124, 40, 54, 117, 64, 47, 226, 111, 26, 53, 157, 156, 156,
+        ],
+        [
+            [57, 99, 140, 255],
+            [115, 156, 198, 255],
+            [173, 214, 255, 255],
+            [192, 146, 187, 255],
+            [96, 137, 179, 255],
+            [115, 156, 198, 255],
+            [173, 214, 255, 255],
+            [192, 146, 187, 255],
+            [96, 137, 179, 255],
+            [82, 123, 165, 255],
+            [24, 66, 107, 255],
+            [192, 146, 187, 255],
+            [63, 104, 146, 255],
+            [82, 123, 165, 255],
+            [24, 66, 107, 255],
+            [43, 85, 126, 255],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_3() {
+    // Two subsets with unique p-bits.
+    check_rgba8(
+        "BC7",
+        &[
+            8, 40, 34, 152, 207, 195, 210, 33, 81, 54, 162, 84, 33, 39, 39, 39,
+        ],
+        [
+            [21, 31, 41, 255],
+            [21, 31, 41, 255],
+            [53, 63, 73, 255],
+            [58, 68, 78, 255],
+            [34, 44, 54, 255],
+            [21, 31, 41, 255],
+            [53, 63, 73, 255],
+            [58, 68, 78, 255],
+            [34, 44, 54, 255],
+            [21, 31, 41, 255],
+            [53, 63, 73, 255],
+            [58, 68, 78, 255],
+            [34, 44, 54, 255],
+            [21, 31, 41, 255],
+            [53, 63, 73, 255],
+            [49, 59, 69, 255],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_4() {
+    // Alpha swapped with red, 3-bit color and 2-bit alpha indices.
+    check_rgba8(
+        "BC7",
+        &[
+            176, 141, 202, 124, 61, 55, 202, 201, 201, 201, 227, 85, 204, 225, 85, 204,
+        ],
+        [
+            [113, 156, 197, 115],
+            [123, 182, 223, 141],
+            [132, 206, 247, 165],
+            [142, 164, 205, 123],
+            [113, 190, 231, 149],
+            [123, 148, 189, 107],
+            [132, 172, 213, 131],
+            [142, 198, 239, 157],
+            [113, 156, 197, 115],
+            [123, 182, 223, 141],
+            [132, 206, 247, 165],
+            [142, 164, 205, 123],
+            [113, 190, 231, 149],
+            [123, 148, 189, 107],
+            [132, 172, 213, 131],
+            [142, 198, 239, 157],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_5() {
+    // Alpha swapped with green.
+    check_rgba8(
+        "BC7",
+        &[
+            160, 144, 75, 133, 163, 9, 125, 152, 116, 114, 114, 114, 178, 177, 177, 177,
+        ],
+        [
+            [37, 33, 57, 47],
+            [41, 31, 61, 51],
+            [46, 38, 66, 56],
+            [32, 36, 52, 42],
+            [37, 33, 57, 47],
+            [41, 31, 61, 51],
+            [46, 38, 66, 56],
+            [32, 36, 52, 42],
+            [37, 33, 57, 47],
+            [41, 31, 61, 51],
+            [46, 38, 66, 56],
+            [32, 36, 52, 42],
+            [37, 33, 57, 47],
+            [41, 31, 61, 51],
+            [46, 38, 66, 56],
+            [32, 36, 52, 42],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_6() {
+    // One subset with 4-bit indices.
+    check_rgba8(
+        "BC7",
+        &[
+            192, 137, 6, 243, 233, 144, 68, 41, 189, 80, 250, 148, 62, 216, 114, 28,
+        ],
+        [
+            [44, 54, 64, 74],
+            [49, 59, 69, 79],
+            [38, 48, 58, 68],
+            [43, 53, 63, 73],
+            [48, 58, 68, 78],
+            [53, 63, 73, 83],
+            [42, 52, 62, 72],
+            [47, 57, 67, 77],
+            [52, 62, 72, 82],
+            [41, 51, 61, 71],
+            [46, 56, 66, 76],
+            [51, 61, 71, 81],
+            [40, 50, 60, 70],
+            [45, 55, 65, 75],
+            [50, 60, 70, 80],
+            [39, 49, 59, 69],
+        ],
+    );
+}
+
+#[test]
+fn bc7_mode_7() {
+    // Two subsets with alpha.
+    check_rgba8(
+        "BC7",
+        &[
+            128, 128, 237, 100, 109, 145, 32, 56, 174, 22, 54, 117, 33, 39, 39, 39,
+        ],
+        [
+            [182, 223, 4, 44],
+            [182, 223, 4, 44],
+            [53, 94, 134, 175],
+            [72, 113, 153, 194],
+            [235, 16, 56, 97],
+            [182, 223, 4, 44],
+            [53, 94, 134, 175],
+            [72, 113, 153, 194],
+            [235, 16, 56, 97],
+            [182, 223, 4, 44],
+            [53, 94, 134, 175],
+            [72, 113, 153, 194],
+            [235, 16, 56, 97],
+            [182, 223, 4, 44],
+            [53, 94, 134, 175],
+            [36, 77, 117, 158],
+        ],
+    );
+}
+
+#[test]
+fn bc6h_untransformed() {
+    // Mode 0x03: one region with 10-bit endpoints.
+    check_rgba32f(
+        "BC6H",
+        &[
+            3, 75, 10, 254, 39, 131, 240, 166, 166, 129, 111, 77, 43, 9, 231, 197,
+        ],
+        [
+            [1.1044922, 0.0020141602, 3.59e+03, 1.0],
+            [0.007881165, 12.15625, 3.9082031, 1.0],
+            [4.9296875, 0.00015687943, 27728.0, 1.0],
+            [0.03555298, 0.88671875, 30.3125, 1.0],
+            [0.00025439262, 5164.0, 0.034240723, 1.0],
+            [0.12866211, 0.09020996, 192.875, 1.0],
+            [0.0011434555, 387.75, 0.25878906, 1.0],
+            [0.57910156, 0.006626129, 1481.0, 1.0],
+            [0.0041503906, 37.90625, 1.6484375, 1.0],
+            [2.0996094, 0.0006670952, 8.68e+03, 1.0],
+            [0.018615723, 2.8769531, 12.703125, 1.0],
+            [9.4296875, 3.784895e-05, 65504.0, 1.0],
+            [0.06768799, 0.2770996, 76.8125, 1.0],
+            [0.00048542023, 1634.0, 0.087646484, 1.0],
+            [0.30297852, 0.021270752, 584.0, 1.0],
+            [0.0021800995, 118.375, 0.67089844, 1.0],
+        ],
+    );
+}
+
+#[test]
+fn bc6h_transformed() {
+    // Mode 0x07: one region with 11-bit endpoints and 9-bit deltas.
+    check_rgba32f(
+        "BC6H",
+        &[
+            135, 59, 94, 81, 192, 217, 146, 127, 166, 129, 111, 77, 43, 9, 231, 197,
+        ],
+        [
+            [140.5, 0.06652832, 8.523464e-05, 1.0],
+            [53.6875, 0.14099121, 0.00029444695, 1.0],
+            [195.0, 0.054534912, 5.209446e-05, 1.0],
+            [70.625, 0.11303711, 0.00020301342, 1.0],
+            [26.953125, 0.23413086, 0.00071907043, 1.0],
+            [94.875, 0.095336914, 0.00014412403, 1.0],
+            [35.53125, 0.19421387, 0.0004711151, 1.0],
+            [122.125, 0.07537842, 9.995699e-05, 1.0],
+            [47.65625, 0.15881348, 0.00035333633, 1.0],
+            [164.625, 0.060058594, 7.0512295e-05, 1.0],
+            [61.28125, 0.12194824, 0.00023245811, 1.0],
+            [219.125, 0.050079346, 3.7372112e-05, 1.0],
+            [82.75, 0.10418701, 0.00017356873, 1.0],
+            [29.984375, 0.21643066, 0.00060129166, 1.0],
+            [1.1e+02, 0.084228516, 0.00011467934, 1.0],
+            [41.59375, 0.17651367, 0.00041222572, 1.0],
+        ],
+    );
+}
+
+#[test]
+fn etc2_t_mode() {
+    // The red differential overflows.
+    check_rgba8(
+        "ETC2_RGB",
+        &[243, 77, 41, 123, 99, 156, 90, 90],
+        [
+            [187, 68, 221, 255],
+            [2, 121, 87, 255],
+            [34, 153, 119, 255],
+            [66, 185, 151, 255],
+            [66, 185, 151, 255],
+            [187, 68, 221, 255],
+            [2, 121, 87, 255],
+            [34, 153, 119, 255],
+            [34, 153, 119, 255],
+            [66, 185, 151, 255],
+            [187, 68, 221, 255],
+            [2, 121, 87, 255],
+            [2, 121, 87, 255],
+            [34, 153, 119, 255],
+            [66, 185, 151, 255],
+            [187, 68, 221, 255],
+        ],
+    );
+}
+
+#[test]
+fn etc2_h_mode() {
+    // The green differential overflows.
+    check_rgba8(
+        "ETC2_RGB",
+        &[30, 6, 200, 247, 99, 156, 90, 90],
+        [
+            [92, 245, 126, 255],
+            [112, 0, 197, 255],
+            [194, 58, 255, 255],
+            [10, 163, 44, 255],
+            [10, 163, 44, 255],
+            [92, 245, 126, 255],
+            [112, 0, 197, 255],
+            [194, 58, 255, 255],
+            [194, 58, 255, 255],
+            [10, 163, 44, 255],
+            [92, 245, 126, 255],
+            [112, 0, 197, 255],
+            [112, 0, 197, 255],
+            [194, 58, 255, 255],
+            [10, 163, 44, 255],
+            [92, 245, 126, 255],
+        ],
+    );
+}
+
+#[test]
+fn etc2_planar() {
+    // The blue differential overflows.
+    check_rgba8(
+        "ETC2_RGB",
+        &[81, 72, 13, 127, 41, 144, 191, 225],
+        [
+            [162, 201, 40, 255],
+            [185, 161, 81, 255],
+            [209, 121, 122, 255],
+            [232, 80, 162, 255],
+            [127, 215, 64, 255],
+            [150, 174, 104, 255],
+            [173, 134, 145, 255],
+            [196, 94, 186, 255],
+            [91, 228, 87, 255],
+            [114, 188, 128, 255],
+            [138, 148, 169, 255],
+            [161, 107, 209, 255],
+            [56, 242, 111, 255],
+            [79, 201, 151, 255],
+            [102, 161, 192, 255],
+            [125, 121, 233, 255],
+        ],
+    );
+}
+
+#[test]
+fn eac_signed() {
+    // A negative base, a zero multiplier and a -128 base that clamps to -1023.
+    for (block, expected) in [
+        (
+            [156, 53, 57, 136, 115, 240, 229, 225],
+            [
+                -968, -752, -560, -1016, -608, -968, -752, -560, -1023, -608, -968, -752, -872,
+                -1023, -608, -968,
+            ],
+        ),
+        (
+            [120, 13, 57, 136, 115, 240, 229, 225],
+            [
+                958, 960, 969, 957, 962, 958, 960, 969, 950, 962, 958, 960, 959, 950, 962, 958,
+            ],
+        ),
+        (
+            [128, 240, 57, 136, 115, 240, 229, 225],
+            [
+                -1023, -784, 656, -1023, -64, -1023, -784, 656, -1023, -64, -1023, -784, -1023,
+                -1023, -64, -1023,
+            ],
+        ),
+    ] {
+        check_rgba32f(
+            "EAC_R_SIGNED",
+            &block,
+            expected.map(|value: i32| [value as f32 / 1023., 0., 0., 1.]),
+        );
+    }
+}
+
+#[test]
+fn replacing_streamed_texture_moves_following_data() {
+    const RESOURCE: &str = "CAB-00000000000000000000000000000000.resS";
+    let streamed = |offset: u32| {
+        let path = format!("archive:/CAB-00000000000000000000000000000000/{RESOURCE}");
+        json!({"offset": offset, "size": 16, "path": path})
+    };
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.set_platform(13);
+    asset_file.types = vec![AssetFileType::new(28, TEXTURE_2D_HASH)];
+    for (path_id, offset) in [(1, 0), (2, 16)] {
+        let texture = texture_2d("RGBA32", 2, 2, 1, &[], streamed(offset));
+        asset_file
+            .insert_asset(path_id, Asset::Texture2D(texture, 0))
+            .unwrap();
+    }
+    let mut bundle = Bundle::new();
+    bundle.insert(
+        String::from("CAB-00000000000000000000000000000000"),
+        BundleFile::Assets(asset_file),
+    );
+    bundle.insert(String::from(RESOURCE), BundleFile::Raw((0..32).collect()));
+    let following = bundle.decode_texture(2).unwrap();
+
+    let larger = RgbaImage::from_fn(5, 3, |x, y| image::Rgba([x as u8, y as u8, 7, 255]));
+    bundle
+        .replace_texture(1, &larger.clone().into(), Some(TextureFormat::RGBA32))
+        .unwrap();
+
+    assert_eq!(bundle.decode_texture(1).unwrap().into_rgba8(), larger);
+    assert_eq!(bundle.decode_texture(2).unwrap(), following);
+    let (_, BundleFile::Assets(asset_file)) = bundle.files().next().unwrap() else {
+        panic!("the assets file should come first");
+    };
+    let Some(Asset::Texture2D(texture, _)) = asset_file.get_asset_by_path_id(2) else {
+        panic!("path ID 2 should be a Texture2D");
+    };
+    // 60 bytes of new data padded to keep 16 byte alignment.
+    assert_eq!(texture.stream_data.offset, 64);
+}
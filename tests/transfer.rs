@@ -0,0 +1,289 @@
+mod common;
+
+use astra_formats::binrw::NullString;
+use astra_formats::{
+    Asset, AssetExternal, AssetFile, AssetFileType, Bundle, BundleFile, ImportOptions, PPtr,
+    SkinnedMeshRenderer, Transform, TypeTreeValue, ASSET_BUNDLE_HASH, GAME_OBJECT_HASH,
+    MATERIAL_HASH, SKINNED_MESH_RENDERER_HASH, TEXTURE_2D_HASH, TRANSFORM_HASH,
+};
+use common::*;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+const SOURCE: &str = "CAB-00000000000000000000000000000001";
+const TARGET: &str = "CAB-00000000000000000000000000000002";
+const SOURCE_PATH: &str =
+    "archive:/CAB-00000000000000000000000000000001/CAB-00000000000000000000000000000001";
+
+fn skinned_mesh_renderer(
+    game_object: i64,
+    material: i64,
+    mesh: i64,
+    bone: i64,
+    root: i64,
+) -> Asset {
+    let vector4 = json!({"x": 0.0, "y": 0.0, "z": 0.0, "w": 0.0});
+    let vector3 = json!({"x": 0.0, "y": 0.0, "z": 0.0});
+    let renderer: SkinnedMeshRenderer = serde_json::from_value(json!({
+        "game_object": pptr(game_object),
+        "enabled": 1,
+        "cast_shadows": 1,
+        "receive_shadows": 1,
+        "dynamic_occludee": 1,
+        "static_shadow_caster": 0,
+        "motion_vectors": 1,
+        "light_probe_usage": 1,
+        "reflection_probe_usage": 1,
+        "ray_tracing_mode": 0,
+        "ray_trace_procedural": 0,
+        "rendering_layer_mask": 1,
+        "renderer_priority": 0,
+        "lightmap_index": 0xFFFF,
+        "lightmap_index_dynamic": 0xFFFF,
+        "lightmap_tiling_offset": vector4,
+        "lightmap_tiling_offset_dynamic": vector4,
+        "materials": [pptr(material)],
+        "first_sub_mesh": 0,
+        "sub_mesh_count": 0,
+        "static_batch_root": pptr(0),
+        "probe_anchor": pptr(0),
+        "light_probe_volume_override": pptr(0),
+        "sorting_layer_id": 0,
+        "sorting_layer": 0,
+        "sorting_order": 0,
+        "quality": 0,
+        "update_when_offscreen": 0,
+        "skinned_motion_vectors": 1,
+        "mesh": pptr(mesh),
+        "bones": [pptr(bone)],
+        "blend_shape_weights": [],
+        "root_bone": pptr(root),
+        "aabb": {"center": vector3, "extent": vector3},
+        "dirty_aabb": 0,
+    }))
+    .unwrap();
+    Asset::SkinnedMeshRenderer(renderer)
+}
+
+fn external(path: &str) -> AssetExternal {
+    AssetExternal {
+        unknown: NullString::default(),
+        guid: 0,
+        ty: 0,
+        path: NullString::from(path),
+    }
+}
+
+/// "body" with a bone child, a skinned renderer, its material and a texture streamed from the
+/// resource file. The body's RectTransform is parented to "root", which stays behind.
+fn source() -> Bundle {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(142, ASSET_BUNDLE_HASH),
+        AssetFileType::new(1, GAME_OBJECT_HASH),
+        rect_transform_type(),
+        AssetFileType::new(4, TRANSFORM_HASH),
+        AssetFileType::new(137, SKINNED_MESH_RENDERER_HASH),
+        AssetFileType::new(21, MATERIAL_HASH),
+        AssetFileType::new(28, TEXTURE_2D_HASH),
+    ];
+    asset_file.externals = vec![external("library/unity default resources")];
+    let resource = format!("archive:/{SOURCE}/{SOURCE}.resS");
+    let stream_data = json!({"offset": 16, "size": 16, "path": resource});
+    let texture = texture_2d("RGBA32", 2, 2, 1, &[], stream_data);
+    let assets = [
+        (1, asset_bundle()),
+        (2, game_object("body", &[3, 4])),
+        (3, rect_transform(2, 10, &[6])),
+        (4, skinned_mesh_renderer(2, 7, 8, 6, 11)),
+        (5, game_object("bone", &[6])),
+        (6, transform(5, 3, &[], vector(0., 1., 0.))),
+        (
+            7,
+            material(
+                PPtr {
+                    file_id: 1,
+                    path_id: 99,
+                },
+                9,
+            ),
+        ),
+        (9, Asset::Texture2D(texture, 0)),
+        (10, transform(12, 0, &[3], vector(0., 0., 0.))),
+        (11, transform(0, 0, &[], vector(0., 0., 0.))),
+        (12, game_object("root", &[10])),
+    ];
+    for (path_id, asset) in assets {
+        asset_file.insert_asset(path_id, asset).unwrap();
+    }
+    asset_file.add_container_entry("chara/body", 2, &[2]);
+
+    let mut bundle = Bundle::new();
+    bundle.insert(String::from(SOURCE), BundleFile::Assets(asset_file));
+    bundle.insert(format!("{SOURCE}.resS"), BundleFile::Raw((0..32).collect()));
+    bundle
+}
+
+/// An empty container, a skeleton to bind to at path ID 42 and 20 bytes of streamed data.
+fn target() -> Bundle {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.types = vec![
+        AssetFileType::new(142, ASSET_BUNDLE_HASH),
+        AssetFileType::new(4, TRANSFORM_HASH),
+    ];
+    asset_file.insert_asset(1, asset_bundle()).unwrap();
+    asset_file
+        .insert_asset(42, transform(0, 0, &[], vector(0., 0., 0.)))
+        .unwrap();
+    let mut bundle = Bundle::new();
+    bundle.insert(String::from(TARGET), BundleFile::Assets(asset_file));
+    bundle.insert(format!("{TARGET}.resS"), BundleFile::Raw(vec![0xFF; 20]));
+    bundle
+}
+
+fn assets<'a>(bundle: &'a Bundle, name: &str) -> &'a AssetFile {
+    match bundle.get(name) {
+        Some(BundleFile::Assets(asset_file)) => asset_file,
+        _ => panic!("bundle does not contain '{name}'"),
+    }
+}
+
+fn ids(pptr: &PPtr) -> (i32, i64) {
+    (pptr.file_id, pptr.path_id)
+}
+
+fn field_ids(value: &TypeTreeValue, field: &str) -> (i32, i64) {
+    let value = value.get(field).unwrap();
+    (
+        value.get("m_FileID").unwrap().as_i64().unwrap() as i32,
+        value.get("m_PathID").unwrap().as_i64().unwrap(),
+    )
+}
+
+#[test]
+fn bundle_import_copies_subtree() {
+    let options = ImportOptions {
+        redirects: HashMap::from([(11, pptr(42))]),
+        external: HashSet::from([8]),
+        source_path: Some(String::from(SOURCE_PATH)),
+        ..Default::default()
+    };
+    let source = source();
+    let mut target = target();
+    let mapping = target.import_from(&source, 2, &options).unwrap();
+
+    // Breadth first from the GameObject. The parent, the redirected root bone and the external
+    // mesh are left behind.
+    let expected = HashMap::from([
+        (2, 43),
+        (3, 44),
+        (4, 45),
+        (6, 46),
+        (7, 47),
+        (5, 48),
+        (9, 49),
+    ]);
+    assert_eq!(mapping, expected);
+
+    let asset_file = assets(&target, TARGET);
+    let externals: Vec<String> = asset_file
+        .externals
+        .iter()
+        .map(|external| external.path.to_string())
+        .collect();
+    assert_eq!(externals, [SOURCE_PATH, "library/unity default resources"]);
+
+    let Some(Asset::GameObject(body)) = asset_file.get_asset_by_path_id(43) else {
+        panic!("path ID 43 should be the body");
+    };
+    let components: Vec<_> = body.component.iter().map(ids).collect();
+    assert_eq!(components, [(0, 44), (0, 45)]);
+
+    let rect_transform = asset_file.read_value(44).unwrap();
+    assert_eq!(field_ids(&rect_transform, "m_GameObject"), (0, 43));
+    assert_eq!(field_ids(&rect_transform, "m_Father"), (0, 0));
+    let children = rect_transform
+        .get("m_Children")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].get("m_PathID").unwrap().as_i64(), Some(46));
+
+    let Some(Asset::SkinnedMeshRenderer(renderer)) = asset_file.get_asset_by_path_id(45) else {
+        panic!("path ID 45 should be the renderer");
+    };
+    assert_eq!(ids(&renderer.game_object), (0, 43));
+    assert_eq!(ids(&renderer.materials.items[0]), (0, 47));
+    assert_eq!(ids(&renderer.bones.items[0]), (0, 46));
+    assert_eq!(ids(&renderer.root_bone), (0, 42));
+    assert_eq!(ids(&renderer.mesh), (1, 8));
+
+    let Some(Asset::Transform(Transform { father, .. })) = asset_file.get_asset_by_path_id(46)
+    else {
+        panic!("path ID 46 should be the bone's transform");
+    };
+    assert_eq!(ids(father), (0, 44));
+
+    let Some(Asset::Material(material)) = asset_file.get_asset_by_path_id(47) else {
+        panic!("path ID 47 should be the material");
+    };
+    assert_eq!(ids(&material.shader), (2, 99));
+    let (_, tex_env) = &material.saved_properties.text_envs.items[0];
+    assert_eq!(ids(&tex_env.texture), (0, 49));
+
+    let Some(Asset::Bundle(bundle)) = asset_file.get_asset_by_path_id(1) else {
+        panic!("path ID 1 should be the AssetBundle");
+    };
+    let (name, info) = &bundle.container_map.items[0];
+    assert_eq!(name.0, "chara/body");
+    assert_eq!(ids(&info.asset), (0, 43));
+    assert_eq!((info.preload_index, info.preload_size), (0, 7));
+    let preloads: HashSet<i64> = bundle.preloads.iter().map(|pptr| pptr.path_id).collect();
+    assert_eq!(preloads, expected.values().copied().collect());
+
+    // Appended after the existing 20 bytes at the next 16 byte boundary.
+    let Some(Asset::Texture2D(texture, _)) = asset_file.get_asset_by_path_id(49) else {
+        panic!("path ID 49 should be the texture");
+    };
+    assert_eq!(texture.stream_data.offset, 32);
+    assert_eq!(texture.stream_data.size, 16);
+    assert_eq!(
+        texture.stream_data.path.0,
+        format!("archive:/{TARGET}/{TARGET}.resS")
+    );
+    let Some(BundleFile::Raw(resource)) = target.get(&format!("{TARGET}.resS")) else {
+        panic!("target resource file is missing");
+    };
+    assert_eq!(resource.len(), 48);
+    assert_eq!(resource[32..], (16..32).collect::<Vec<u8>>());
+}
+
+#[test]
+fn asset_file_import_rejects_streamed_data() {
+    let source = source();
+    let mut target = AssetFile::new("2020.3.18f1");
+    let err = target
+        .import_from(assets(&source, SOURCE), 7, &ImportOptions::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("streams its data"));
+}
+
+#[test]
+fn unparsed_asset_without_type_tree_is_rejected() {
+    let mut source = source();
+    let Some(BundleFile::Assets(asset_file)) = source.get_mut(SOURCE) else {
+        unreachable!();
+    };
+    for ty in &mut asset_file.types {
+        if ty.type_hash == RECT_TRANSFORM_HASH {
+            ty.type_tree = Default::default();
+        }
+    }
+    let mut target = target();
+    let err = target
+        .import_from(&source, 2, &ImportOptions::default())
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("no type tree"));
+    assert!(assets(&target, TARGET).get_asset_by_path_id(43).is_none());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use astra_formats::{Asset, AssetFile, AssetFileType, TypeTreeValue, GAME_OBJECT_HASH};
+use common::*;
+
+fn fixture() -> AssetFile {
+    let mut asset_file = AssetFile::new("2020.3.18f1");
+    asset_file.add_type(AssetFileType::known(GAME_OBJECT_HASH).unwrap());
+    let mut asset = game_object("c_root", &[2]);
+    if let Asset::GameObject(game_object) = &mut asset {
+        game_object.layer = 5;
+    }
+    asset_file.insert_asset(1, asset).unwrap();
+    asset_file
+}
+
+#[test]
+fn value_roundtrips() {
+    let mut asset_file = fixture();
+    let mut value = asset_file.read_value(1).unwrap();
+    assert_eq!(
+        value.get("m_Name"),
+        Some(&TypeTreeValue::String(String::from("c_root")))
+    );
+
+    let type_tree = &asset_file.get_type(GAME_OBJECT_HASH).unwrap().type_tree;
+    let data = type_tree.write_value(&value).unwrap();
+    assert_eq!(type_tree.read_value(&data).unwrap(), value);
+
+    if let TypeTreeValue::Struct(fields) = &mut value {
+        fields.insert(
+            String::from("m_Name"),
+            TypeTreeValue::String(String::from("c_renamed")),
+        );
+    }
+    asset_file.write_value(1, &value).unwrap();
+    let Some(Asset::GameObject(game_object)) = asset_file.get_asset_by_path_id(1) else {
+        panic!("object is no longer a GameObject");
+    };
+    assert_eq!(game_object.name.0, "c_renamed");
+    assert_eq!(game_object.layer, 5);
+    assert_eq!(asset_file.read_value(1).unwrap(), value);
+}
+
+#[test]
+fn rejects_lengths_past_the_end() {
+    let asset_file = fixture();
+    let type_tree = &asset_file.get_type(GAME_OBJECT_HASH).unwrap().type_tree;
+    // The component array claims far more entries than the data holds.
+    let mut data = i32::MAX.to_le_bytes().to_vec();
+    data.extend([0; 12]);
+    let err = type_tree.read_value(&data).unwrap_err();
+    assert!(format!("{:#}", err).contains("larger than"));
+}
+
+fn fields<const N: usize>(fields: [(&str, TypeTreeValue); N]) -> TypeTreeValue {
+    TypeTreeValue::Struct(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+#[test]
+fn merge_keeps_fields_missing_from_the_patch() {
+    let mut value = fields([(
+        "items",
+        TypeTreeValue::Array(vec![
+            fields([("a", TypeTreeValue::Int(1)), ("b", TypeTreeValue::Int(2))]),
+            fields([("a", TypeTreeValue::Int(3)), ("b", TypeTreeValue::Int(4))]),
+        ]),
+    )]);
+    let patch = fields([(
+        "items",
+        TypeTreeValue::Array(vec![
+            fields([("a", TypeTreeValue::Int(10))]),
+            fields([("a", TypeTreeValue::Int(30))]),
+            fields([("a", TypeTreeValue::Int(50)), ("b", TypeTreeValue::Int(60))]),
+        ]),
+    )]);
+    value.merge(patch).unwrap();
+    let expected = fields([(
+        "items",
+        TypeTreeValue::Array(vec![
+            fields([("a", TypeTreeValue::Int(10)), ("b", TypeTreeValue::Int(2))]),
+            fields([("a", TypeTreeValue::Int(30)), ("b", TypeTreeValue::Int(4))]),
+            fields([("a", TypeTreeValue::Int(50)), ("b", TypeTreeValue::Int(60))]),
+        ]),
+    )]);
+    assert_eq!(value, expected);
+
+    let patch = fields([("missing", TypeTreeValue::Int(0))]);
+    assert!(value.merge(patch).is_err());
+}
+
+#[test]
+fn merge_matches_map_entries_by_key() {
+    let entry =
+        |a: i64, b: i64| fields([("a", TypeTreeValue::Int(a)), ("b", TypeTreeValue::Int(b))]);
+    let mut value = TypeTreeValue::Map(vec![
+        (TypeTreeValue::Int(1), entry(1, 2)),
+        (TypeTreeValue::Int(2), entry(3, 4)),
+        (TypeTreeValue::Int(3), entry(5, 6)),
+    ]);
+    let patch = TypeTreeValue::Map(vec![
+        (TypeTreeValue::UInt(4), entry(7, 8)),
+        (
+            TypeTreeValue::UInt(3),
+            fields([("a", TypeTreeValue::Int(50))]),
+        ),
+        (
+            TypeTreeValue::UInt(1),
+            TypeTreeValue::Struct(Default::default()),
+        ),
+    ]);
+    value.merge(patch).unwrap();
+    assert_eq!(
+        value,
+        TypeTreeValue::Map(vec![
+            (TypeTreeValue::Int(1), entry(1, 2)),
+            (TypeTreeValue::Int(3), entry(50, 6)),
+            (TypeTreeValue::UInt(4), entry(7, 8)),
+        ])
+    );
+}
+
+#[test]
+fn dump_writes_the_tree_listing() {
+    let ty = AssetFileType::known(GAME_OBJECT_HASH).unwrap();
+    let mut out = vec![];
+    ty.type_tree.dump(&mut out).unwrap();
+    let listing = String::from_utf8(out).unwrap();
+    assert_eq!(listing, ty.type_tree.to_tree_string().unwrap());
+    assert!(listing.contains("m_Name"));
+}